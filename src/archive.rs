@@ -0,0 +1,278 @@
+use std::fs::{self, File};
+use std::io::{self, Cursor, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use zip::read::ZipFile;
+use zip::result::ZipError;
+use zip::ZipArchive;
+
+/// Exit code when an archive is encrypted and no password was given
+pub const EXIT_PASSWORD_REQUIRED: i32 = 10;
+/// Exit code when the given password does not decrypt the archive
+pub const EXIT_WRONG_PASSWORD: i32 = 11;
+/// Exit code when the archive is damaged or not a zip file
+pub const EXIT_CORRUPT_ARCHIVE: i32 = 12;
+/// Exit code when no available private key can decrypt a recipient-encrypted archive
+pub const EXIT_NO_MATCHING_IDENTITY: i32 = 13;
+
+/// Number of encrypted entry names listed in error messages
+const LISTED_ENTRIES: usize = 5;
+
+/// Why an archive could not be read.
+///
+/// Carried inside `io::Error` so it passes through the existing `io::Result`
+/// functions; use `archive_error` to get it back out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArchiveError {
+    /// Encrypted entries exist but no password was given
+    PasswordRequired {
+        encrypted: Vec<String>,
+        total: usize,
+    },
+    /// The password does not decrypt the named entry
+    WrongPassword { entry: String },
+    /// The archive or one of its entries is damaged
+    Corrupt(String),
+    /// The archive is encrypted to recipients and none of the keys in `searched` match
+    NoMatchingIdentity { searched: String },
+}
+
+impl ArchiveError {
+    /// Process exit code reported for this error
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ArchiveError::PasswordRequired { .. } => EXIT_PASSWORD_REQUIRED,
+            ArchiveError::WrongPassword { .. } => EXIT_WRONG_PASSWORD,
+            ArchiveError::Corrupt(_) => EXIT_CORRUPT_ARCHIVE,
+            ArchiveError::NoMatchingIdentity { .. } => EXIT_NO_MATCHING_IDENTITY,
+        }
+    }
+}
+
+impl std::fmt::Display for ArchiveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ArchiveError::PasswordRequired { encrypted, .. } if encrypted.is_empty() => write!(
+                f,
+                "Password required: the archive is sealed. Use --password-file, --password-env, --ask-password or PYTRON_PASSWORD"
+            ),
+            ArchiveError::PasswordRequired { encrypted, total } => {
+                write!(
+                    f,
+                    "Password required: {} of {} entries are encrypted ({})",
+                    encrypted.len(),
+                    total,
+                    list_entries(encrypted)
+                )?;
+                write!(
+                    f,
+                    ". Use --password-file, --password-env, --ask-password or PYTRON_PASSWORD"
+                )
+            }
+            ArchiveError::WrongPassword { entry } => {
+                write!(f, "Wrong password: it does not decrypt {}", entry)
+            }
+            ArchiveError::Corrupt(reason) => write!(f, "Corrupt archive: {}", reason),
+            ArchiveError::NoMatchingIdentity { searched } => write!(
+                f,
+                "No matching key: the archive is encrypted to recipients and no key in {} can decrypt it. Use --identity <file>",
+                searched
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ArchiveError {}
+
+impl From<ArchiveError> for io::Error {
+    fn from(err: ArchiveError) -> Self {
+        let kind = match err {
+            ArchiveError::PasswordRequired { .. }
+            | ArchiveError::WrongPassword { .. }
+            | ArchiveError::NoMatchingIdentity { .. } => io::ErrorKind::PermissionDenied,
+            ArchiveError::Corrupt(_) => io::ErrorKind::InvalidData,
+        };
+        io::Error::new(kind, err)
+    }
+}
+
+/// The `ArchiveError` inside an `io::Error`, if it carries one
+pub fn archive_error(err: &io::Error) -> Option<&ArchiveError> {
+    err.get_ref()
+        .and_then(|inner| inner.downcast_ref::<ArchiveError>())
+}
+
+fn list_entries(entries: &[String]) -> String {
+    let mut listed = entries
+        .iter()
+        .take(LISTED_ENTRIES)
+        .cloned()
+        .collect::<Vec<_>>()
+        .join(", ");
+    if entries.len() > LISTED_ENTRIES {
+        listed.push_str(&format!(" and {} more", entries.len() - LISTED_ENTRIES));
+    }
+    listed
+}
+
+/// Archive data read from disk, or decrypted into memory from a sealed container
+pub enum ArchiveReader {
+    File(File),
+    Memory(Cursor<Vec<u8>>),
+}
+
+impl Read for ArchiveReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            ArchiveReader::File(file) => file.read(buf),
+            ArchiveReader::Memory(cursor) => cursor.read(buf),
+        }
+    }
+}
+
+impl Seek for ArchiveReader {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        match self {
+            ArchiveReader::File(file) => file.seek(pos),
+            ArchiveReader::Memory(cursor) => cursor.seek(pos),
+        }
+    }
+}
+
+/// Open a zip archive, reporting unreadable files as corrupt
+pub fn open_archive(path: &Path) -> io::Result<ZipArchive<ArchiveReader>> {
+    open_archive_with_password(path, None)
+}
+
+/// Open a zip archive, unsealing it in memory first if it is a sealed container
+pub fn open_archive_with_password(
+    path: &Path,
+    password: Option<&str>,
+) -> io::Result<ZipArchive<ArchiveReader>> {
+    open_archive_with_keys(path, password, &[])
+}
+
+/// Open a zip archive, decrypting it in memory first if it is sealed with a
+/// password or encrypted to recipients. `identities` are private key files to
+/// try; when empty, the keys in PYTRON_HOME/keys are used.
+pub fn open_archive_with_keys(
+    path: &Path,
+    password: Option<&str>,
+    identities: &[PathBuf],
+) -> io::Result<ZipArchive<ArchiveReader>> {
+    let reader = if crate::recipients::is_recipient_encrypted(path)? {
+        eprintln!("Decrypting {}", path.display());
+        let data = crate::recipients::decrypt(&fs::read(path)?, identities)?;
+        ArchiveReader::Memory(Cursor::new(data))
+    } else if crate::sealed::is_sealed(path)? {
+        let password = password.ok_or(ArchiveError::PasswordRequired {
+            encrypted: Vec::new(),
+            total: 0,
+        })?;
+        eprintln!("Unsealing {}", path.display());
+        let data = crate::sealed::unseal(&fs::read(path)?, password)?;
+        ArchiveReader::Memory(Cursor::new(data))
+    } else {
+        ArchiveReader::File(File::open(path)?)
+    };
+
+    ZipArchive::new(reader).map_err(|e| match e {
+        ZipError::Io(err) if err.kind() != io::ErrorKind::UnexpectedEof => err,
+        other => ArchiveError::Corrupt(format!("{}: {}", path.display(), other)).into(),
+    })
+}
+
+/// Names of all encrypted entries, in archive order
+pub fn encrypted_entries<R: Read + Seek>(archive: &mut ZipArchive<R>) -> io::Result<Vec<String>> {
+    let mut encrypted = Vec::new();
+    for i in 0..archive.len() {
+        let file = archive.by_index_raw(i).map_err(|e| corrupt(i, e))?;
+        if file.encrypted() {
+            encrypted.push(file.name().to_string());
+        }
+    }
+    Ok(encrypted)
+}
+
+fn corrupt(index: usize, err: ZipError) -> io::Error {
+    ArchiveError::Corrupt(format!("entry {}: {}", index, err)).into()
+}
+
+/// Check up front that `password` can read the archive.
+///
+/// Fails with `PasswordRequired` when encrypted entries exist but no password
+/// is given and with `WrongPassword` when it does not decrypt the first
+/// encrypted entry. Plain entries of mixed archives need no password.
+pub fn check_password<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    password: Option<&str>,
+) -> io::Result<Vec<String>> {
+    let encrypted = encrypted_entries(archive)?;
+    let Some(first) = encrypted.first() else {
+        return Ok(encrypted);
+    };
+
+    let Some(password) = password else {
+        return Err(ArchiveError::PasswordRequired {
+            total: archive.len(),
+            encrypted,
+        }
+        .into());
+    };
+
+    match archive.by_name_decrypt(first, password.as_bytes()) {
+        Ok(_) => Ok(encrypted),
+        Err(ZipError::InvalidPassword) => Err(ArchiveError::WrongPassword {
+            entry: first.clone(),
+        }
+        .into()),
+        Err(err) => Err(ArchiveError::Corrupt(format!("{}: {}", first, err)).into()),
+    }
+}
+
+/// Open entry `index` for reading, decrypting it if it is encrypted
+pub fn read_entry<'a, R: Read + Seek>(
+    archive: &'a mut ZipArchive<R>,
+    index: usize,
+    password: Option<&str>,
+) -> io::Result<ZipFile<'a, R>> {
+    let name = archive
+        .by_index_raw(index)
+        .map(|file| file.name().to_string())
+        .map_err(|e| corrupt(index, e))?;
+    let result = match password {
+        Some(password) => archive.by_index_decrypt(index, password.as_bytes()),
+        None => archive.by_index(index),
+    };
+    result.map_err(|err| match err {
+        ZipError::InvalidPassword => ArchiveError::WrongPassword { entry: name }.into(),
+        ZipError::UnsupportedArchive(ZipError::PASSWORD_REQUIRED) => {
+            ArchiveError::PasswordRequired {
+                encrypted: vec![name],
+                total: 1,
+            }
+            .into()
+        }
+        ZipError::Io(err) => err,
+        other => corrupt(index, other),
+    })
+}
+
+/// Copy the contents of an entry. Read failures (bad checksum or
+/// authentication code, truncated data) are reported as a corrupt archive,
+/// write failures are passed through.
+pub fn copy_entry<R: Read>(entry: &mut ZipFile<R>, out: &mut impl io::Write) -> io::Result<u64> {
+    let mut buffer = [0u8; 64 * 1024];
+    let mut copied = 0;
+    loop {
+        let read = match entry.read(&mut buffer) {
+            Ok(0) => return Ok(copied),
+            Ok(read) => read,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => {
+                return Err(ArchiveError::Corrupt(format!("{}: {}", entry.name(), err)).into())
+            }
+        };
+        out.write_all(&buffer[..read])?;
+        copied += read as u64;
+    }
+}
@@ -0,0 +1,237 @@
+use serde::Serialize;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::{
+    dir_size, format_size, get_pytron_home, get_uv_command, is_uv_installed, status, UvStateDir,
+};
+
+/// Disk usage of one area of pytron-managed storage
+#[derive(Debug, Clone, Serialize)]
+pub struct CacheArea {
+    pub name: &'static str,
+    pub path: PathBuf,
+    pub size: u64,
+    /// False when the user pointed the directory outside PYTRON_HOME, in which
+    /// case pytron reports on it but never deletes it
+    pub managed: bool,
+    /// The uv state directory this area is, `None` for pytron's own temp data
+    #[serde(skip)]
+    pub uv_dir: Option<UvStateDir>,
+}
+
+/// Prefixes of the scratch directories pytron creates in PYTRON_HOME
+const SCRATCH_PREFIXES: [&str; 2] = ["pytron_download_", "pytron_install_"];
+
+/// Suffix of the lock file held next to an extraction directory while it is in use
+const LOCK_SUFFIX: &str = ".lock";
+
+fn area(name: &'static str, path: PathBuf, uv_dir: Option<UvStateDir>) -> CacheArea {
+    let managed = path.starts_with(get_pytron_home());
+    CacheArea {
+        name,
+        size: dir_size(&path),
+        path,
+        managed,
+        uv_dir,
+    }
+}
+
+/// Measure every storage area pytron uses
+pub fn cache_info() -> Vec<CacheArea> {
+    let mut areas: Vec<CacheArea> = UvStateDir::ALL
+        .into_iter()
+        .map(|dir| area(dir.label(), dir.path(), Some(dir)))
+        .collect();
+    areas.push(area("temp", get_pytron_home().join("temp"), None));
+    areas
+}
+
+/// Marks an extraction directory as in use for as long as it is alive, so that
+/// `prune` and `clear` leave the directory of a running script alone
+pub struct InUse {
+    lock: PathBuf,
+    file: Option<File>,
+}
+
+impl Drop for InUse {
+    fn drop(&mut self) {
+        // Close before removing, an open file cannot be removed on Windows
+        self.file.take();
+        let _ = fs::remove_file(&self.lock);
+    }
+}
+
+fn lock_path(dir: &Path) -> PathBuf {
+    let mut name = dir.file_name().unwrap_or_default().to_os_string();
+    name.push(LOCK_SUFFIX);
+    dir.with_file_name(name)
+}
+
+/// Hold a lock on `dir` (an extraction directory in `PYTRON_HOME/temp`) until
+/// the returned guard is dropped
+pub fn mark_in_use(dir: &Path) -> io::Result<InUse> {
+    let lock = lock_path(dir);
+    let file = File::create(&lock)?;
+    file.lock()?;
+    Ok(InUse {
+        lock,
+        file: Some(file),
+    })
+}
+
+/// Whether another process holds the lock of `dir`
+fn in_use(dir: &Path) -> bool {
+    match File::open(lock_path(dir)) {
+        Ok(file) => file.try_lock().is_err(),
+        Err(_) => false,
+    }
+}
+
+/// Render `cache_info` as a table
+pub fn format_cache_info(areas: &[CacheArea]) -> String {
+    let mut out = String::new();
+    let total: u64 = areas.iter().map(|a| a.size).sum();
+    for a in areas {
+        let note = if a.managed {
+            ""
+        } else {
+            " (external, not managed)"
+        };
+        out.push_str(&format!(
+            "{:<17} {:>10}  {}{}\n",
+            a.name,
+            format_size(a.size),
+            a.path.display(),
+            note
+        ));
+    }
+    out.push_str(&format!("{:<17} {:>10}\n", "total", format_size(total)));
+    out
+}
+
+/// Remove unused uv cache entries and leftover extraction/scratch directories
+/// older than `older_than`. Returns the number of bytes freed.
+pub fn prune_cache(older_than: Duration) -> io::Result<u64> {
+    let mut freed = 0;
+
+    let uv_cache = UvStateDir::Cache.path();
+    if is_uv_installed() && uv_cache.exists() {
+        let before = dir_size(&uv_cache);
+        status!("Running: uv cache prune");
+        let status = get_uv_command()
+            .args(["cache", "prune"])
+            .stdout(crate::output::child_stdout())
+            .status()?;
+        if !status.success() {
+            return Err(io::Error::other(format!(
+                "uv cache prune failed with {}",
+                status
+            )));
+        }
+        freed += before.saturating_sub(dir_size(&uv_cache));
+    }
+
+    let pytron_home = get_pytron_home();
+    freed += remove_children(&pytron_home.join("temp"), |_| true, Some(older_than))?;
+    freed += remove_children(
+        &pytron_home,
+        |name| SCRATCH_PREFIXES.iter().any(|p| name.starts_with(p)),
+        Some(older_than),
+    )?;
+
+    Ok(freed)
+}
+
+/// Delete the uv cache and temp data, plus Python installs and tools when
+/// `include_python` is set. Directories outside PYTRON_HOME and extraction
+/// directories of running scripts are left alone. Returns the number of bytes freed.
+pub fn clear_cache(include_python: bool) -> io::Result<u64> {
+    let mut freed = 0;
+    for a in cache_info() {
+        let wanted = match a.uv_dir {
+            Some(UvStateDir::PythonInstalls | UvStateDir::Tools) => include_python,
+            Some(UvStateDir::Cache) => true,
+            None => {
+                freed += remove_children(&a.path, |_| true, None)?;
+                continue;
+            }
+        };
+        if !wanted {
+            continue;
+        }
+        if !a.managed {
+            status!(
+                "Skipping {} outside PYTRON_HOME: {}",
+                a.name,
+                a.path.display()
+            );
+            continue;
+        }
+        if a.path.exists() {
+            status!("Removing {}: {}", a.name, a.path.display());
+            fs::remove_dir_all(&a.path)?;
+            freed += a.size;
+        }
+    }
+    freed += remove_children(
+        &get_pytron_home(),
+        |name| SCRATCH_PREFIXES.iter().any(|p| name.starts_with(p)),
+        None,
+    )?;
+    Ok(freed)
+}
+
+/// Remove entries of `dir` whose name matches `filter` and, if given, that were
+/// last modified longer than `older_than` ago. Entries locked with `mark_in_use`
+/// are skipped, their lock files are removed along with them. Returns the bytes freed.
+fn remove_children(
+    dir: &Path,
+    filter: impl Fn(&str) -> bool,
+    older_than: Option<Duration>,
+) -> io::Result<u64> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(0),
+        Err(err) => return Err(err),
+    };
+
+    let now = SystemTime::now();
+    let mut freed = 0;
+    for entry in entries {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.ends_with(LOCK_SUFFIX) || !filter(&name) {
+            continue;
+        }
+        let path = entry.path();
+        if in_use(&path) {
+            status!("Skipping, in use: {}", path.display());
+            continue;
+        }
+        if let Some(min_age) = older_than {
+            let age = entry
+                .metadata()?
+                .modified()
+                .ok()
+                .and_then(|modified| now.duration_since(modified).ok())
+                .unwrap_or_default();
+            if age < min_age {
+                continue;
+            }
+        }
+
+        let size = dir_size(&path);
+        status!("Removing: {}", path.display());
+        if entry.file_type()?.is_dir() {
+            fs::remove_dir_all(&path)?;
+        } else {
+            fs::remove_file(&path)?;
+        }
+        let _ = fs::remove_file(lock_path(&path));
+        freed += size;
+    }
+    Ok(freed)
+}
@@ -0,0 +1,223 @@
+use serde::Serialize;
+use std::fmt;
+use std::fs;
+use std::io::{self, Read, Seek};
+use std::path::Path;
+use zip::ZipArchive;
+
+use crate::archive::{self, ArchiveError};
+use crate::run_args::validate_entry;
+
+/// Project file declaring the named commands of an archive
+pub const COMMANDS_FILE: &str = "pyproject.toml";
+
+/// What a named command runs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CommandTarget {
+    /// A script inside the archive
+    Script(String),
+    /// `module:function`, called like a console script entry point
+    Module { module: String, function: String },
+}
+
+impl fmt::Display for CommandTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CommandTarget::Script(script) => write!(f, "{}", script),
+            CommandTarget::Module { module, function } => write!(f, "{}:{}", module, function),
+        }
+    }
+}
+
+/// A command declared in `[tool.pytron.commands]`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct NamedCommand {
+    pub name: String,
+    pub target: CommandTarget,
+    /// Arguments passed before the ones given on the command line
+    pub args: Vec<String>,
+    pub description: Option<String>,
+}
+
+fn invalid(name: &str, msg: impl fmt::Display) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!(
+            "Invalid command `{}` in [tool.pytron.commands]: {}",
+            name, msg
+        ),
+    )
+}
+
+fn is_identifier(part: &str) -> bool {
+    let mut chars = part.chars();
+    chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// Parse `tools/report.py` or `package.module:function`
+fn parse_target(name: &str, value: &str) -> io::Result<CommandTarget> {
+    match value.split_once(':') {
+        Some((module, function)) => {
+            if !module.split('.').all(is_identifier) || !is_identifier(function) {
+                return Err(invalid(name, format!("`{}` is not module:function", value)));
+            }
+            Ok(CommandTarget::Module {
+                module: module.to_string(),
+                function: function.to_string(),
+            })
+        }
+        None => {
+            validate_entry(value).map_err(|e| invalid(name, e))?;
+            Ok(CommandTarget::Script(value.to_string()))
+        }
+    }
+}
+
+/// Read the named commands from the text of a `pyproject.toml`:
+///
+/// ```toml
+/// [tool.pytron.commands]
+/// sync = "tools/sync.py"
+/// cleanup = "robot.cleanup:main"
+/// report = { script = "tools/report.py", args = ["--format", "md"], description = "Weekly report" }
+/// ```
+pub fn parse_commands(text: &str) -> io::Result<Vec<NamedCommand>> {
+    let pyproject: toml::Table = toml::from_str(text).map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Failed to parse {}: {}", COMMANDS_FILE, e),
+        )
+    })?;
+    let Some(table) = pyproject
+        .get("tool")
+        .and_then(|tool| tool.get("pytron"))
+        .and_then(|pytron| pytron.get("commands"))
+    else {
+        return Ok(Vec::new());
+    };
+    let table = table
+        .as_table()
+        .ok_or_else(|| invalid("commands", "expected a table"))?;
+
+    let mut commands = Vec::new();
+    for (name, value) in table {
+        let valid_name = !name.starts_with('-')
+            && !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+        if !valid_name {
+            return Err(invalid(
+                name,
+                "names use letters, digits, '-', '_' and '.', and do not start with '-'",
+            ));
+        }
+        let command = match value {
+            toml::Value::String(target) => NamedCommand {
+                name: name.clone(),
+                target: parse_target(name, target)?,
+                args: Vec::new(),
+                description: None,
+            },
+            toml::Value::Table(entry) => {
+                let field = |key: &str| entry.get(key).and_then(|v| v.as_str());
+                let target = match (field("script"), field("module")) {
+                    (Some(script), None) if !script.contains(':') => parse_target(name, script)?,
+                    (None, Some(module)) if module.contains(':') => parse_target(name, module)?,
+                    _ => {
+                        return Err(invalid(
+                            name,
+                            "set either `script` to a path or `module` to module:function",
+                        ))
+                    }
+                };
+                let args = match entry.get("args") {
+                    None => Vec::new(),
+                    Some(args) => args
+                        .as_array()
+                        .and_then(|args| {
+                            args.iter()
+                                .map(|arg| arg.as_str().map(str::to_string))
+                                .collect::<Option<Vec<_>>>()
+                        })
+                        .ok_or_else(|| invalid(name, "`args` must be a list of strings"))?,
+                };
+                NamedCommand {
+                    name: name.clone(),
+                    target,
+                    args,
+                    description: field("description").map(str::to_string),
+                }
+            }
+            _ => return Err(invalid(name, "expected a string or a table")),
+        };
+        commands.push(command);
+    }
+    Ok(commands)
+}
+
+/// Read the named commands of an extracted archive
+pub fn load_commands(dir: &Path) -> io::Result<Vec<NamedCommand>> {
+    match fs::read_to_string(dir.join(COMMANDS_FILE)) {
+        Ok(text) => parse_commands(&text),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(err) => Err(err),
+    }
+}
+
+/// Read the named commands of an opened archive without extracting it
+pub fn read_commands<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    password: Option<&str>,
+) -> io::Result<Vec<NamedCommand>> {
+    let Some(index) = archive.index_for_name(COMMANDS_FILE) else {
+        return Ok(Vec::new());
+    };
+    let mut entry = archive::read_entry(archive, index, password)?;
+    let mut data = Vec::new();
+    archive::copy_entry(&mut entry, &mut data)?;
+    let text = String::from_utf8(data)
+        .map_err(|_| ArchiveError::Corrupt(format!("{} is not valid UTF-8", COMMANDS_FILE)))?;
+    parse_commands(&text)
+}
+
+/// Render commands for `pytron run --list`, one per line
+pub fn format_commands(commands: &[NamedCommand]) -> String {
+    let width = commands.iter().map(|c| c.name.len()).max().unwrap_or(0);
+    let mut out = String::new();
+    for command in commands {
+        let mut target = command.target.to_string();
+        for arg in &command.args {
+            target.push(' ');
+            target.push_str(arg);
+        }
+        match &command.description {
+            Some(description) => out.push_str(&format!(
+                "  {:width$}  {} ({})\n",
+                command.name,
+                description,
+                target,
+                width = width
+            )),
+            None => out.push_str(&format!(
+                "  {:width$}  {}\n",
+                command.name,
+                target,
+                width = width
+            )),
+        }
+    }
+    out
+}
+
+/// Python code calling `module:function` the way console scripts do, with the
+/// command name as `sys.argv[0]`. Names are validated identifiers, so they are
+/// safe to put into the code.
+pub fn module_runner(command: &str, module: &str, function: &str) -> String {
+    format!(
+        "import sys; sys.argv[0] = {:?}; from {} import {}; sys.exit({}())",
+        command, module, function, function
+    )
+}
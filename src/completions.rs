@@ -0,0 +1,368 @@
+use clap::ValueEnum;
+use clap_complete::Shell;
+use std::env;
+use std::fs;
+use std::path::Path;
+
+use crate::config::Config;
+use crate::run_args::{SWITCH_FLAGS, TRAILING_FLAGS, VALUE_FLAGS};
+use crate::{cli_command, commands, recipients, sealed};
+
+/// Argument the completion scripts call pytron with to complete `run`
+pub const COMPLETE_COMMAND: &str = "__complete";
+
+/// Shells `pytron completions` writes scripts for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum CompletionShell {
+    Bash,
+    Zsh,
+    Fish,
+    Powershell,
+}
+
+// `run` is parsed by hand, so the scripts hand it to `pytron __complete`
+// instead of completing it from the clap definition. The clap completion
+// function is renamed and called for every other subcommand.
+
+const BASH_RUN: &str = r#"
+_pytron() {
+    if [[ ${COMP_CWORD} -lt 2 || "${COMP_WORDS[1]}" != "run" ]]; then
+        _pytron_clap "$@"
+        return
+    fi
+    # COMP_WORDS splits at ':' and '=', so split the line on whitespace only
+    local cur="${COMP_WORDS[COMP_CWORD]}"
+    local line="${COMP_LINE:0:COMP_POINT}"
+    local -a words
+    read -ra words <<< "${line}"
+    local current=""
+    if [[ ! "${line}" =~ [[:space:]]$ ]]; then
+        current="${words[${#words[@]}-1]}"
+        unset 'words[${#words[@]}-1]'
+    fi
+    # Candidates replace the whole word, bash only replaces the part after the last ':' or '='
+    local strip="${current%"${cur}"}"
+    [[ "${cur}" == ":" || "${cur}" == "=" ]] && strip="${current}"
+    local IFS=$'\n'
+    local candidate
+    COMPREPLY=()
+    for candidate in $(pytron __complete --current="${current}" -- "${words[@]}" 2>/dev/null); do
+        COMPREPLY+=("${candidate#"${strip}"}")
+    done
+    if [[ ${#COMPREPLY[@]} -eq 1 && ( "${COMPREPLY[0]}" == */ || "${COMPREPLY[0]}" == *: ) ]]; then
+        compopt -o nospace
+    fi
+}
+"#;
+
+const ZSH_RUN: &str = r#"
+_pytron() {
+    if (( CURRENT <= 2 )) || [[ "${words[2]}" != "run" ]]; then
+        _pytron_clap "$@"
+        return
+    fi
+    local -a candidates
+    local candidate
+    candidates=("${(@f)$(pytron __complete --current="${words[CURRENT]}" -- "${(@)words[1,CURRENT-1]}" 2>/dev/null)}")
+    for candidate in "${candidates[@]}"; do
+        [[ -z "${candidate}" ]] && continue
+        if [[ "${candidate}" == */ || "${candidate}" == *: ]]; then
+            compadd -Q -S '' -- "${candidate}"
+        else
+            compadd -Q -- "${candidate}"
+        fi
+    done
+}
+"#;
+
+const FISH_RUN: &str = r#"
+complete -c pytron -n "__fish_pytron_using_subcommand run" -f -a "(pytron __complete --current=(commandline -ct | string collect -a) -- (commandline -opc) 2>/dev/null)"
+"#;
+
+const POWERSHELL_RUN: &str = r#"
+Register-ArgumentCompleter -Native -CommandName 'pytron' -ScriptBlock {
+    param($wordToComplete, $commandAst, $cursorPosition)
+
+    $words = @($commandAst.CommandElements |
+        Where-Object { $_.Extent.EndOffset -lt $cursorPosition } |
+        ForEach-Object { $_.Extent.Text })
+    if ($words.Count -lt 2 -or $words[1] -ne 'run') {
+        return & $global:PytronClapCompleter $wordToComplete $commandAst $cursorPosition
+    }
+    pytron __complete "--current=$wordToComplete" -- @words 2>$null | ForEach-Object {
+        [CompletionResult]::new($_, $_, [CompletionResultType]::ParameterValue, $_)
+    }
+}
+"#;
+
+/// The completion script for a shell: clap's completions of the `Cli`
+/// definition with `run` completed by `pytron __complete`
+pub fn generate_script(shell: CompletionShell) -> String {
+    let clap_shell = match shell {
+        CompletionShell::Bash => Shell::Bash,
+        CompletionShell::Zsh => Shell::Zsh,
+        CompletionShell::Fish => Shell::Fish,
+        CompletionShell::Powershell => Shell::PowerShell,
+    };
+    let mut buf = Vec::new();
+    clap_complete::generate(clap_shell, &mut cli_command(), "pytron", &mut buf);
+    let script = String::from_utf8_lossy(&buf).to_string();
+
+    match shell {
+        CompletionShell::Bash => {
+            let script = script.replacen("_pytron() {", "_pytron_clap() {", 1);
+            insert_before(&script, "\nif [[ \"${BASH_VERSINFO[0]}\"", BASH_RUN)
+        }
+        CompletionShell::Zsh => {
+            let script = script.replacen("\n_pytron() {", "\n_pytron_clap() {", 1);
+            insert_before(&script, "\nif [ \"$funcstack[1]\" = \"_pytron\" ]", ZSH_RUN)
+        }
+        CompletionShell::Fish => {
+            let mut out: String = script
+                .lines()
+                .filter(|line| !line.contains("__fish_pytron_using_subcommand run\""))
+                .map(|line| format!("{}\n", line))
+                .collect();
+            out.push_str(FISH_RUN);
+            out
+        }
+        CompletionShell::Powershell => {
+            let mut out = script.replacen(
+                "Register-ArgumentCompleter -Native -CommandName 'pytron' -ScriptBlock {",
+                "$global:PytronClapCompleter = {",
+                1,
+            );
+            out.push_str(POWERSHELL_RUN);
+            out
+        }
+    }
+}
+
+/// Insert `text` before the first occurrence of `marker`, or append it
+fn insert_before(script: &str, marker: &str, text: &str) -> String {
+    match script.find(marker) {
+        Some(pos) => format!("{}{}{}", &script[..pos], text, &script[pos..]),
+        None => format!("{}{}", script, text),
+    }
+}
+
+/// Completions for a command line. `words` are the words before the cursor,
+/// starting with the program name, and `current` is the word being completed.
+/// Only `run` is completed here, everything else comes from the clap definition.
+pub fn complete(words: &[String], current: &str) -> Vec<String> {
+    match words.get(1) {
+        Some(command) if command == "run" => complete_run(&words[2..], current),
+        _ => Vec::new(),
+    }
+}
+
+/// Completions for the arguments of `pytron run`, following the rules of
+/// `parse_run_args`: pytron's options and the archive (with its scripts after
+/// `robot.zip:`), then the archive's named commands, then the script's own arguments.
+pub fn complete_run(before: &[String], current: &str) -> Vec<String> {
+    let mut target: Option<String> = None;
+    let mut script_args = 0;
+    let mut separator = false;
+    let mut pending: Option<&str> = None;
+
+    for arg in before {
+        if pending.take().is_some() {
+            continue;
+        }
+        if separator {
+            script_args += 1;
+            continue;
+        }
+        if arg == "--" {
+            separator = true;
+            continue;
+        }
+        let flag = match arg.split_once('=') {
+            Some((flag, _)) if VALUE_FLAGS.contains(&flag) => flag,
+            _ => arg.as_str(),
+        };
+        let ours = target.is_none() || (script_args == 0 && TRAILING_FLAGS.contains(&flag));
+        if ours && VALUE_FLAGS.contains(&flag) {
+            if !arg.contains('=') {
+                pending = Some(flag);
+            }
+            continue;
+        }
+        if ours && SWITCH_FLAGS.contains(&flag) {
+            continue;
+        }
+        if target.is_some() {
+            script_args += 1;
+        } else if !arg.starts_with('-') {
+            target = Some(arg.clone());
+        }
+    }
+    let target = target.map(|target| match target.to_ascii_lowercase().rfind(".zip:") {
+        Some(pos) => target[..pos + 4].to_string(),
+        None => target,
+    });
+
+    if let Some(flag) = pending {
+        return complete_value(flag, current, target.as_deref());
+    }
+    if separator {
+        return complete_paths(current, |_| true);
+    }
+    if let Some((flag, value)) = current.split_once('=') {
+        if VALUE_FLAGS.contains(&flag) && target.is_none() {
+            return complete_value(flag, value, target.as_deref())
+                .into_iter()
+                .map(|candidate| format!("{}={}", flag, candidate))
+                .collect();
+        }
+    }
+    if current.starts_with('-') {
+        let mut flags: Vec<&str> = VALUE_FLAGS
+            .iter()
+            .chain(SWITCH_FLAGS)
+            .copied()
+            .filter(|flag| target.is_none() || (script_args == 0 && TRAILING_FLAGS.contains(flag)))
+            .collect();
+        if target.is_none() {
+            flags.push("--uv-run-help");
+            if before.is_empty() {
+                flags.push("--help");
+            }
+        } else {
+            flags.push("--");
+        }
+        let mut flags: Vec<String> = flags
+            .into_iter()
+            .filter(|flag| flag.starts_with(current))
+            .map(str::to_string)
+            .collect();
+        flags.sort();
+        return flags;
+    }
+
+    match target {
+        None => {
+            let lower = current.to_ascii_lowercase();
+            if let Some(pos) = lower.rfind(".zip:") {
+                let (archive, entry) = (&current[..pos + 4], &current[pos + 5..]);
+                return archive_scripts(Path::new(archive))
+                    .into_iter()
+                    .filter(|script| script.starts_with(entry))
+                    .map(|script| format!("{}:{}", archive, script))
+                    .collect();
+            }
+            complete_paths(current, |path| {
+                path.extension()
+                    .is_some_and(|ext| ext.eq_ignore_ascii_case("zip") || ext == "py")
+            })
+        }
+        // Scripts are selected as `robot.zip:<path>` or with `--entry`, here
+        // only a named command can follow the archive
+        Some(target) if script_args == 0 && is_archive(&target) => {
+            let mut candidates = archive_commands(Path::new(&target));
+            candidates.retain(|candidate| candidate.starts_with(current));
+            candidates
+        }
+        Some(_) => complete_paths(current, |_| true),
+    }
+}
+
+fn is_archive(target: &str) -> bool {
+    Path::new(target)
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("zip"))
+}
+
+/// Complete the value of an option of `run`
+fn complete_value(flag: &str, value: &str, target: Option<&str>) -> Vec<String> {
+    match flag {
+        "--password-file" | "--identity" => complete_paths(value, |_| true),
+        "--allow-write" => complete_paths(value, |path| path.is_dir()),
+        "--password-env" => {
+            let mut names: Vec<String> = env::vars_os()
+                .filter_map(|(name, _)| name.into_string().ok())
+                .filter(|name| name.starts_with(value))
+                .collect();
+            names.sort();
+            names
+        }
+        "--entry" => {
+            // Before the archive is given, complete from the archive `run` would default to
+            let archive = match target {
+                Some(target) => target.to_string(),
+                None => Config::load(Path::new("."))
+                    .ok()
+                    .and_then(|config| config.get_str("run.archive"))
+                    .unwrap_or_else(|| "robot.zip".to_string()),
+            };
+            archive_scripts(Path::new(&archive))
+                .into_iter()
+                .filter(|script| script.starts_with(value))
+                .collect()
+        }
+        // Never suggest anything for a literal password
+        _ => Vec::new(),
+    }
+}
+
+/// Files and directories starting with `prefix`; files only if `accept` allows them.
+/// Directories end with '/', hidden entries are only offered for a prefix starting with '.'.
+pub fn complete_paths(prefix: &str, accept: impl Fn(&Path) -> bool) -> Vec<String> {
+    let split = prefix.rfind(['/', std::path::MAIN_SEPARATOR]).map_or(0, |pos| pos + 1);
+    let (dir, name_prefix) = prefix.split_at(split);
+    let Ok(entries) = fs::read_dir(if dir.is_empty() { "." } else { dir }) else {
+        return Vec::new();
+    };
+    let mut candidates: Vec<String> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            if !name.starts_with(name_prefix) || (name.starts_with('.') && !name_prefix.starts_with('.')) {
+                return None;
+            }
+            let path = entry.path();
+            if path.is_dir() {
+                Some(format!("{}{}/", dir, name))
+            } else if accept(&path) {
+                Some(format!("{}{}", dir, name))
+            } else {
+                None
+            }
+        })
+        .collect();
+    candidates.sort();
+    candidates
+}
+
+/// Plain zip archives only: sealed and recipient-encrypted ones would need a
+/// key or password just to list their entries
+fn open_plain_archive(path: &Path) -> Option<zip::ZipArchive<crate::archive::ArchiveReader>> {
+    if !path.is_file()
+        || sealed::is_sealed(path).unwrap_or(true)
+        || recipients::is_recipient_encrypted(path).unwrap_or(true)
+    {
+        return None;
+    }
+    crate::archive::open_archive(path).ok()
+}
+
+/// Python scripts inside an archive, in archive order
+pub fn archive_scripts(path: &Path) -> Vec<String> {
+    let Some(zip) = open_plain_archive(path) else {
+        return Vec::new();
+    };
+    zip.file_names()
+        .filter(|name| name.ends_with(".py"))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Named commands declared by an archive; none if its pyproject.toml is encrypted
+pub fn archive_commands(path: &Path) -> Vec<String> {
+    let Some(mut zip) = open_plain_archive(path) else {
+        return Vec::new();
+    };
+    commands::read_commands(&mut zip, None)
+        .map(|commands| commands.into_iter().map(|command| command.name).collect())
+        .unwrap_or_default()
+}
@@ -0,0 +1,387 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use toml::{Table, Value};
+
+use crate::password::PasswordInput;
+use crate::{get_pytron_home, get_system_config_dir};
+
+/// File name of the configuration, both system-wide and in PYTRON_HOME
+pub const CONFIG_FILE: &str = "config.toml";
+/// Project configuration file, used instead of `[tool.pytron]` in pyproject.toml
+pub const PROJECT_CONFIG_FILE: &str = "pytron.toml";
+/// Project file whose `[tool.pytron]` table holds the project configuration
+pub const PYPROJECT_FILE: &str = "pyproject.toml";
+
+/// How a setting is written in the files and in its environment variable
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SettingKind {
+    Str,
+    /// Array in TOML, comma-separated in the environment
+    List,
+    /// Array in TOML, whitespace-separated in the environment
+    Args,
+    /// `true`/`false` in TOML, also `1`/`0`, `yes`/`no`, `on`/`off` in the environment
+    Bool,
+}
+
+/// A setting that can be configured, as `section.name`
+#[derive(Debug, Clone, Copy)]
+pub struct Setting {
+    pub key: &'static str,
+    pub kind: SettingKind,
+    /// Value used when no layer sets it
+    pub default: Option<&'static str>,
+    /// Values a string setting accepts, empty for any
+    pub choices: &'static [&'static str],
+}
+
+impl Setting {
+    /// Environment variable overriding the setting, e.g. `PYTRON_ZIP_OUTPUT`
+    pub fn env_var(&self) -> String {
+        format!("PYTRON_{}", self.key.replace('.', "_").to_ascii_uppercase())
+    }
+}
+
+const fn setting(key: &'static str, kind: SettingKind) -> Setting {
+    Setting {
+        key,
+        kind,
+        default: None,
+        choices: &[],
+    }
+}
+
+/// Every setting pytron reads from its configuration
+pub const SETTINGS: &[Setting] = &[
+    Setting {
+        default: Some("robot.zip"),
+        ..setting("zip.output", SettingKind::Str)
+    },
+    setting("zip.ignore_patterns", SettingKind::List),
+    setting("zip.password_file", SettingKind::Str),
+    setting("zip.password_env", SettingKind::Str),
+    Setting {
+        default: Some("warn"),
+        choices: &["off", "warn", "fail"],
+        ..setting("zip.scan", SettingKind::Str)
+    },
+    Setting {
+        default: Some("robot.zip"),
+        ..setting("run.archive", SettingKind::Str)
+    },
+    setting("run.uv_args", SettingKind::Args),
+    setting("run.password_file", SettingKind::Str),
+    setting("run.password_env", SettingKind::Str),
+    setting("run.identity", SettingKind::List),
+    setting("run.require_signature", SettingKind::Bool),
+];
+
+/// Look up a setting by its `section.name` key
+pub fn find_setting(key: &str) -> Option<&'static Setting> {
+    SETTINGS.iter().find(|setting| setting.key == key)
+}
+
+/// Where an effective value comes from, from lowest to highest precedence.
+/// Command line flags override all of them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigSource {
+    Default,
+    System(PathBuf),
+    User(PathBuf),
+    Project(PathBuf),
+    /// The environment variable holding the value
+    Env(String),
+}
+
+impl fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigSource::Default => write!(f, "default"),
+            ConfigSource::System(path) => write!(f, "system: {}", path.display()),
+            ConfigSource::User(path) => write!(f, "user: {}", path.display()),
+            ConfigSource::Project(path) => write!(f, "project: {}", path.display()),
+            ConfigSource::Env(var) => write!(f, "env: {}", var),
+        }
+    }
+}
+
+/// A configuration file that was looked for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigFile {
+    pub source: ConfigSource,
+    pub found: bool,
+}
+
+/// The effective configuration: for every setting the value of the highest
+/// layer that sets it. Lists are replaced, not merged.
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    values: BTreeMap<&'static str, (Value, ConfigSource)>,
+    /// The files that were looked for, in order of precedence
+    pub files: Vec<ConfigFile>,
+}
+
+fn invalid_data(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// Check a value from a file or the environment against its setting
+fn check_value(setting: &Setting, value: &Value) -> Result<(), String> {
+    let valid = match setting.kind {
+        SettingKind::Str => value.as_str().is_some_and(|text| {
+            setting.choices.is_empty() || setting.choices.contains(&text)
+        }),
+        SettingKind::List | SettingKind::Args => value
+            .as_array()
+            .is_some_and(|items| items.iter().all(Value::is_str)),
+        SettingKind::Bool => value.is_bool(),
+    };
+    if valid {
+        return Ok(());
+    }
+    Err(match setting.kind {
+        SettingKind::Str if setting.choices.is_empty() => "expected a string".to_string(),
+        SettingKind::Str => format!("expected one of {}", setting.choices.join(", ")),
+        SettingKind::List | SettingKind::Args => "expected an array of strings".to_string(),
+        SettingKind::Bool => "expected true or false".to_string(),
+    })
+}
+
+/// Read the settings of a configuration table with `[zip]` and `[run]` sections.
+/// Other sections are left to their own readers (e.g. `commands`).
+pub fn parse_table(table: &Table, origin: &Path) -> io::Result<Vec<(&'static Setting, Value)>> {
+    let mut settings = Vec::new();
+    for section in ["zip", "run"] {
+        let Some(value) = table.get(section) else {
+            continue;
+        };
+        let values = value.as_table().ok_or_else(|| {
+            invalid_data(format!("Invalid configuration {}: [{}] must be a table", origin.display(), section))
+        })?;
+        for (name, value) in values {
+            let key = format!("{}.{}", section, name);
+            let setting = find_setting(&key).ok_or_else(|| {
+                invalid_data(format!("Unknown setting {} in {}", key, origin.display()))
+            })?;
+            check_value(setting, value).map_err(|msg| {
+                invalid_data(format!("Invalid value for {} in {}: {}", key, origin.display(), msg))
+            })?;
+            settings.push((setting, value.clone()));
+        }
+    }
+    Ok(settings)
+}
+
+/// Read a configuration file; a missing file has no settings
+fn read_file(path: &Path) -> io::Result<Option<Table>> {
+    match fs::read_to_string(path) {
+        Ok(text) => text
+            .parse::<Table>()
+            .map(Some)
+            .map_err(|e| invalid_data(format!("Invalid configuration {}: {}", path.display(), e))),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+/// Read the project configuration of `dir`: `pytron.toml` if it exists,
+/// otherwise the `[tool.pytron]` table of `pyproject.toml`. With `lenient`, a
+/// `pyproject.toml` that is not valid TOML is skipped with a warning, as its
+/// `[tool.pytron]` table cannot be told apart from the rest of it.
+fn read_project(dir: &Path, lenient: bool) -> io::Result<(PathBuf, Option<Table>)> {
+    let path = dir.join(PROJECT_CONFIG_FILE);
+    if let Some(table) = read_file(&path)? {
+        return Ok((path, Some(table)));
+    }
+    let path = dir.join(PYPROJECT_FILE);
+    let pyproject = match read_file(&path) {
+        Err(err) if lenient && err.kind() == io::ErrorKind::InvalidData => {
+            eprintln!("Warning: ignoring {}, it is not valid TOML", path.display());
+            None
+        }
+        result => result?,
+    };
+    let table = pyproject.map(|pyproject| {
+        pyproject
+            .get("tool")
+            .and_then(|tool| tool.get("pytron"))
+            .and_then(Value::as_table)
+            .cloned()
+            .unwrap_or_default()
+    });
+    Ok((path, table))
+}
+
+/// Parse the value of a setting's environment variable
+pub fn parse_env_value(setting: &Setting, text: &str) -> Result<Value, String> {
+    let value = match setting.kind {
+        SettingKind::Str => Value::String(text.to_string()),
+        SettingKind::List => Value::Array(
+            text.split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(|item| Value::String(item.to_string()))
+                .collect(),
+        ),
+        SettingKind::Args => Value::Array(
+            text.split_whitespace()
+                .map(|item| Value::String(item.to_string()))
+                .collect(),
+        ),
+        SettingKind::Bool => match text.trim().to_ascii_lowercase().as_str() {
+            "1" | "true" | "yes" | "on" => Value::Boolean(true),
+            "0" | "false" | "no" | "off" => Value::Boolean(false),
+            _ => return Err("expected true or false".to_string()),
+        },
+    };
+    check_value(setting, &value)?;
+    Ok(value)
+}
+
+impl Config {
+    /// Load the configuration layers for a project in `project_dir`:
+    /// system file, `PYTRON_HOME/config.toml`, the project file and the
+    /// `PYTRON_<SECTION>_<NAME>` environment variables.
+    pub fn load(project_dir: &Path) -> io::Result<Config> {
+        Config::load_layers(project_dir, false)
+    }
+
+    /// Like `load`, for commands that only look at the current directory
+    /// without being about its project, such as `run`: a `pyproject.toml` that
+    /// is not valid TOML does not stop them. Invalid pytron settings still do.
+    pub fn load_lenient(project_dir: &Path) -> io::Result<Config> {
+        Config::load_layers(project_dir, true)
+    }
+
+    fn load_layers(project_dir: &Path, lenient: bool) -> io::Result<Config> {
+        let mut config = Config::default();
+
+        let system = get_system_config_dir().join(CONFIG_FILE);
+        let user = get_pytron_home().join(CONFIG_FILE);
+        let (project, project_table) = read_project(project_dir, lenient)?;
+        let layers = [
+            (read_file(&system)?, ConfigSource::System(system.clone()), system),
+            (read_file(&user)?, ConfigSource::User(user.clone()), user),
+            (project_table, ConfigSource::Project(project.clone()), project),
+        ];
+        for (table, source, path) in layers {
+            config.files.push(ConfigFile {
+                source: source.clone(),
+                found: table.is_some(),
+            });
+            let Some(table) = table else {
+                continue;
+            };
+            for (setting, value) in parse_table(&table, &path)? {
+                config.values.insert(setting.key, (value, source.clone()));
+            }
+        }
+
+        for setting in SETTINGS {
+            let var = setting.env_var();
+            if let Ok(text) = env::var(&var) {
+                let value = parse_env_value(setting, &text)
+                    .map_err(|msg| invalid_data(format!("Invalid value for {} in {}: {}", setting.key, var, msg)))?;
+                config.values.insert(setting.key, (value, ConfigSource::Env(var)));
+            }
+        }
+        Ok(config)
+    }
+
+    /// The effective value of a setting and where it comes from, falling back
+    /// to its default. `None` if the setting is unset and has no default.
+    pub fn get(&self, key: &str) -> Option<(Value, ConfigSource)> {
+        if let Some((value, source)) = self.values.get(key) {
+            return Some((value.clone(), source.clone()));
+        }
+        find_setting(key)
+            .and_then(|setting| setting.default)
+            .map(|default| (Value::String(default.to_string()), ConfigSource::Default))
+    }
+
+    /// The effective value of a string setting
+    pub fn get_str(&self, key: &str) -> Option<String> {
+        self.get(key)
+            .and_then(|(value, _)| value.as_str().map(str::to_string))
+    }
+
+    /// The effective value of a list setting
+    pub fn get_list(&self, key: &str) -> Option<Vec<String>> {
+        self.get(key).and_then(|(value, _)| {
+            value.as_array().map(|items| {
+                items
+                    .iter()
+                    .filter_map(|item| item.as_str().map(str::to_string))
+                    .collect()
+            })
+        })
+    }
+
+    /// The effective value of a setting with fixed choices, such as `zip.scan`.
+    /// A value `T` does not accept is an error naming the key and the choices.
+    pub fn get_enum<T: clap::ValueEnum>(&self, key: &str) -> io::Result<Option<T>> {
+        let Some((value, source)) = self.get(key) else {
+            return Ok(None);
+        };
+        let text = value.as_str().unwrap_or_default();
+        T::from_str(text, false).map(Some).map_err(|_| {
+            let choices: Vec<String> = T::value_variants()
+                .iter()
+                .filter_map(|variant| variant.to_possible_value())
+                .map(|choice| choice.get_name().to_string())
+                .collect();
+            invalid_data(format!(
+                "Invalid value for {} in {}: expected one of {}",
+                key,
+                source,
+                choices.join(", ")
+            ))
+        })
+    }
+
+    /// The effective value of a boolean setting, `false` if unset
+    pub fn get_bool(&self, key: &str) -> bool {
+        self.get(key)
+            .and_then(|(value, _)| value.as_bool())
+            .unwrap_or(false)
+    }
+
+    /// Fill in the configured password source of a `section` (`zip` or `run`)
+    /// when no password option was given. `password_file` wins over `password_env`.
+    pub fn apply_password(&self, section: &str, input: &mut PasswordInput) {
+        if !input.is_empty() {
+            return;
+        }
+        if let Some(file) = self.get_str(&format!("{}.password_file", section)) {
+            input.file = Some(PathBuf::from(file));
+        } else {
+            input.env = self.get_str(&format!("{}.password_env", section));
+        }
+    }
+
+    /// Describe the files that were read and every setting with its value and
+    /// origin, for `pytron config show`
+    pub fn to_text(&self) -> String {
+        let mut text = String::from("Configuration files (lowest precedence first):\n");
+        for file in &self.files {
+            let state = if file.found { "" } else { " (not found)" };
+            text.push_str(&format!("  {}{}\n", file.source, state));
+        }
+        text.push_str("  env: PYTRON_<SECTION>_<NAME>\n  command line flags override all of them\n\n");
+
+        let width = SETTINGS.iter().map(|s| s.key.len()).max().unwrap_or(0);
+        for setting in SETTINGS {
+            let line = match self.get(setting.key) {
+                Some((value, source)) => format!("{:<width$} = {}  ({})", setting.key, value, source),
+                None => format!("{:<width$}   (unset, {})", setting.key, setting.env_var()),
+            };
+            text.push_str(line.trim_end());
+            text.push('\n');
+        }
+        text
+    }
+}
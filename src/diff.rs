@@ -0,0 +1,497 @@
+use serde::Serialize;
+use similar::{ChangeTag, TextDiff};
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{self, Read, Seek};
+use std::path::Path;
+use zip::ZipArchive;
+
+use crate::archive;
+use crate::commands;
+use crate::format_size;
+use crate::inspect::{self, ArchiveInfo};
+use crate::sbom::{self, RESERVED_PREFIX};
+
+/// Files larger than this are compared but not diffed line by line
+const MAX_TEXT_DIFF: usize = 1024 * 1024;
+/// Lines of context around each change in the unified diffs
+const CONTEXT_LINES: usize = 3;
+
+/// An entry outside `.pytron/` as listed in the central directory
+#[derive(Debug, Clone, Copy)]
+struct EntrySummary {
+    index: usize,
+    size: u64,
+    crc32: u32,
+    encrypted: bool,
+}
+
+/// An archive opened for comparison: what `pytron inspect` reports plus the
+/// size and checksum of every file outside `.pytron/`. Contents are only read
+/// for the entries that differ.
+pub struct ArchiveSnapshot<R> {
+    pub info: ArchiveInfo,
+    archive: ZipArchive<R>,
+    password: Option<String>,
+    entries: BTreeMap<String, EntrySummary>,
+}
+
+impl<R: Read + Seek> ArchiveSnapshot<R> {
+    /// Read an opened archive. Encrypted entries need the password.
+    pub fn read(
+        mut archive: ZipArchive<R>,
+        path: &Path,
+        password: Option<&str>,
+    ) -> io::Result<Self> {
+        archive::check_password(&mut archive, password)?;
+        let info = inspect::inspect_archive(&mut archive, path, password)?;
+        let mut entries = BTreeMap::new();
+        for index in 0..archive.len() {
+            let entry = archive.by_index_raw(index)?;
+            if entry.is_dir() || entry.name().starts_with(RESERVED_PREFIX) {
+                continue;
+            }
+            let summary = EntrySummary {
+                index,
+                size: entry.size(),
+                crc32: entry.crc32(),
+                encrypted: entry.encrypted(),
+            };
+            entries.insert(entry.name().to_string(), summary);
+        }
+        Ok(ArchiveSnapshot {
+            info,
+            archive,
+            password: password.map(str::to_string),
+            entries,
+        })
+    }
+
+    fn mode(&self, name: &str) -> Option<u32> {
+        self.info
+            .entries
+            .iter()
+            .find(|entry| entry.name == name)
+            .and_then(|entry| entry.mode)
+    }
+
+    /// Contents of an entry, decrypted if needed
+    fn load(&mut self, name: &str) -> io::Result<Option<Vec<u8>>> {
+        let Some(summary) = self.entries.get(name) else {
+            return Ok(None);
+        };
+        let mut entry =
+            archive::read_entry(&mut self.archive, summary.index, self.password.as_deref())?;
+        let mut data = Vec::new();
+        archive::copy_entry(&mut entry, &mut data)?;
+        Ok(Some(data))
+    }
+
+    /// Contents of a small text entry such as `uv.lock`, `None` if missing or not UTF-8
+    fn text(&mut self, name: &str) -> io::Result<Option<String>> {
+        Ok(self
+            .load(name)?
+            .and_then(|data| String::from_utf8(data).ok()))
+    }
+}
+
+/// An entry only in one of the archives
+#[derive(Debug, Clone, Serialize)]
+pub struct EntryChange {
+    pub name: String,
+    pub size: u64,
+}
+
+/// An entry in both archives whose contents or permissions differ
+#[derive(Debug, Clone, Serialize)]
+pub struct ModifiedEntry {
+    pub name: String,
+    pub old_size: u64,
+    pub new_size: u64,
+    /// Permission bits, if they changed; octal strings in JSON
+    pub old_mode: Option<String>,
+    pub new_mode: Option<String>,
+    /// Whether the contents differ (false if only the permissions changed)
+    pub content_changed: bool,
+    /// Not valid UTF-8 text or too large to diff
+    pub binary: bool,
+    pub lines_added: usize,
+    pub lines_removed: usize,
+    /// Unified diff of text files
+    pub diff: Option<String>,
+}
+
+/// A locked package whose version changed
+#[derive(Debug, Clone, Serialize)]
+pub struct VersionChange {
+    pub name: String,
+    pub old: String,
+    pub new: String,
+}
+
+/// Changes of the packages pinned in `uv.lock` and the requirements declared
+/// in `pyproject.toml`
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DependencyChanges {
+    /// `name==version` of packages only locked in the new archive
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub changed: Vec<VersionChange>,
+    /// Requirements added to `[project] dependencies`
+    pub declared_added: Vec<String>,
+    pub declared_removed: Vec<String>,
+}
+
+impl DependencyChanges {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.changed.is_empty()
+            && self.declared_added.is_empty()
+            && self.declared_removed.is_empty()
+    }
+}
+
+/// A changed property of the archive or its project
+#[derive(Debug, Clone, Serialize)]
+pub struct MetadataChange {
+    pub field: String,
+    pub old: Option<String>,
+    pub new: Option<String>,
+}
+
+/// Everything `pytron diff` reports
+#[derive(Debug, Clone, Serialize)]
+pub struct ArchiveDiff {
+    pub old: String,
+    pub new: String,
+    pub metadata: Vec<MetadataChange>,
+    pub dependencies: DependencyChanges,
+    pub added: Vec<EntryChange>,
+    pub removed: Vec<EntryChange>,
+    pub modified: Vec<ModifiedEntry>,
+    pub unchanged: usize,
+}
+
+impl ArchiveDiff {
+    /// Whether the archives have the same files, dependencies and metadata
+    pub fn is_empty(&self) -> bool {
+        self.metadata.is_empty()
+            && self.dependencies.is_empty()
+            && self.added.is_empty()
+            && self.removed.is_empty()
+            && self.modified.is_empty()
+    }
+}
+
+fn octal(mode: Option<u32>) -> Option<String> {
+    mode.map(|mode| format!("{:04o}", mode))
+}
+
+/// Compare one entry present in both archives. Entries of the same size and
+/// CRC-32 are equal without reading them; encrypted entries may not carry a
+/// CRC-32, so those are compared by contents.
+fn compare_entry<A: Read + Seek, B: Read + Seek>(
+    name: &str,
+    old: &mut ArchiveSnapshot<A>,
+    new: &mut ArchiveSnapshot<B>,
+) -> io::Result<Option<ModifiedEntry>> {
+    let (old_entry, new_entry) = (old.entries[name], new.entries[name]);
+    let (old_mode, new_mode) = (old.mode(name), new.mode(name));
+    let mut contents = None;
+    let content_changed = if old_entry.size != new_entry.size {
+        true
+    } else if old_entry.encrypted || new_entry.encrypted {
+        let loaded = (old.load(name)?, new.load(name)?);
+        let changed = loaded.0 != loaded.1;
+        contents = Some(loaded);
+        changed
+    } else {
+        old_entry.crc32 != new_entry.crc32
+    };
+    if !content_changed && old_mode == new_mode {
+        return Ok(None);
+    }
+    let mode_changed = old_mode != new_mode;
+    let mut modified = ModifiedEntry {
+        name: name.to_string(),
+        old_size: old_entry.size,
+        new_size: new_entry.size,
+        old_mode: octal(old_mode).filter(|_| mode_changed),
+        new_mode: octal(new_mode).filter(|_| mode_changed),
+        content_changed,
+        binary: false,
+        lines_added: 0,
+        lines_removed: 0,
+        diff: None,
+    };
+    if !content_changed {
+        return Ok(Some(modified));
+    }
+    if old_entry.size.max(new_entry.size) > MAX_TEXT_DIFF as u64 {
+        modified.binary = true;
+        return Ok(Some(modified));
+    }
+    let (old_data, new_data) = match contents {
+        Some(loaded) => loaded,
+        None => (old.load(name)?, new.load(name)?),
+    };
+    let texts = (
+        old_data.and_then(|data| String::from_utf8(data).ok()),
+        new_data.and_then(|data| String::from_utf8(data).ok()),
+    );
+    let (Some(old_text), Some(new_text)) = texts else {
+        modified.binary = true;
+        return Ok(Some(modified));
+    };
+    if old_text.contains('\0') || new_text.contains('\0') {
+        modified.binary = true;
+        return Ok(Some(modified));
+    }
+    let diff = TextDiff::from_lines(&old_text, &new_text);
+    for change in diff.iter_all_changes() {
+        match change.tag() {
+            ChangeTag::Insert => modified.lines_added += 1,
+            ChangeTag::Delete => modified.lines_removed += 1,
+            ChangeTag::Equal => {}
+        }
+    }
+    modified.diff = Some(
+        diff.unified_diff()
+            .context_radius(CONTEXT_LINES)
+            .header(&format!("a/{}", name), &format!("b/{}", name))
+            .to_string(),
+    );
+    Ok(Some(modified))
+}
+
+/// `name -> version` of the packages locked in `uv.lock`, without the project itself
+fn locked_packages(lock: Option<&str>) -> BTreeMap<String, String> {
+    lock.and_then(|text| sbom::parse_uv_lock(text).ok())
+        .map(|(packages, _)| {
+            packages
+                .into_iter()
+                .filter(|package| !package.is_root)
+                .map(|package| (package.name, package.version))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Requirements listed in `[project] dependencies` of `pyproject.toml`
+fn declared_dependencies(pyproject: Option<&str>) -> BTreeSet<String> {
+    pyproject
+        .and_then(|text| toml::from_str::<toml::Table>(text).ok())
+        .and_then(|pyproject| {
+            pyproject
+                .get("project")
+                .and_then(|project| project.get("dependencies"))
+                .and_then(|deps| deps.as_array())
+                .map(|deps| {
+                    deps.iter()
+                        .filter_map(|dep| dep.as_str().map(|dep| dep.trim().to_string()))
+                        .collect()
+                })
+        })
+        .unwrap_or_default()
+}
+
+fn compare_dependencies<A: Read + Seek, B: Read + Seek>(
+    old: &mut ArchiveSnapshot<A>,
+    new: &mut ArchiveSnapshot<B>,
+) -> io::Result<DependencyChanges> {
+    let old_locked = locked_packages(old.text("uv.lock")?.as_deref());
+    let new_locked = locked_packages(new.text("uv.lock")?.as_deref());
+    let mut changes = DependencyChanges::default();
+    for (name, version) in &new_locked {
+        match old_locked.get(name) {
+            None => changes.added.push(format!("{}=={}", name, version)),
+            Some(old_version) if old_version != version => changes.changed.push(VersionChange {
+                name: name.clone(),
+                old: old_version.clone(),
+                new: version.clone(),
+            }),
+            Some(_) => {}
+        }
+    }
+    for (name, version) in &old_locked {
+        if !new_locked.contains_key(name) {
+            changes.removed.push(format!("{}=={}", name, version));
+        }
+    }
+
+    let old_declared = declared_dependencies(old.text("pyproject.toml")?.as_deref());
+    let new_declared = declared_dependencies(new.text("pyproject.toml")?.as_deref());
+    changes.declared_added = new_declared.difference(&old_declared).cloned().collect();
+    changes.declared_removed = old_declared.difference(&new_declared).cloned().collect();
+    Ok(changes)
+}
+
+fn compare_metadata(old: &ArchiveInfo, new: &ArchiveInfo) -> Vec<MetadataChange> {
+    let describe = |info: &ArchiveInfo| -> Vec<(&'static str, Option<String>)> {
+        let project = &info.project;
+        let metadata = &info.metadata;
+        let commands = (!project.commands.is_empty())
+            .then(|| commands::format_commands(&project.commands).trim_end().to_string());
+        vec![
+            ("container", Some(info.container.describe().to_string())),
+            ("entry script", info.entry_script.clone()),
+            ("name", project.name.clone()),
+            ("version", project.version.clone()),
+            ("requires-python", project.requires_python.clone()),
+            ("python version", project.python_version.clone()),
+            ("commands", commands),
+            ("signature", Some(metadata.signature.clone())),
+            ("signer", metadata.signer.clone()),
+            ("manifest", metadata.manifest.then(|| "present".to_string())),
+            ("sbom", (!metadata.sbom.is_empty()).then(|| metadata.sbom.join(", "))),
+            ("secrets", metadata.secrets.clone()),
+        ]
+    };
+    describe(old)
+        .into_iter()
+        .zip(describe(new))
+        .filter(|((_, old), (_, new))| old != new)
+        .map(|((field, old), (_, new))| MetadataChange {
+            field: field.to_string(),
+            old,
+            new,
+        })
+        .collect()
+}
+
+/// Compare two archives
+pub fn diff_archives<A: Read + Seek, B: Read + Seek>(
+    old: &mut ArchiveSnapshot<A>,
+    new: &mut ArchiveSnapshot<B>,
+) -> io::Result<ArchiveDiff> {
+    let mut diff = ArchiveDiff {
+        old: old.info.archive.clone(),
+        new: new.info.archive.clone(),
+        metadata: compare_metadata(&old.info, &new.info),
+        dependencies: compare_dependencies(old, new)?,
+        added: Vec::new(),
+        removed: Vec::new(),
+        modified: Vec::new(),
+        unchanged: 0,
+    };
+    let names: Vec<String> = new.entries.keys().cloned().collect();
+    for name in names {
+        if !old.entries.contains_key(&name) {
+            diff.added.push(EntryChange {
+                size: new.entries[&name].size,
+                name,
+            });
+        } else {
+            match compare_entry(&name, old, new)? {
+                Some(modified) => diff.modified.push(modified),
+                None => diff.unchanged += 1,
+            }
+        }
+    }
+    for (name, entry) in &old.entries {
+        if !new.entries.contains_key(name) {
+            diff.removed.push(EntryChange {
+                name: name.clone(),
+                size: entry.size,
+            });
+        }
+    }
+    Ok(diff)
+}
+
+impl ArchiveDiff {
+    /// Render the report for humans, followed by the unified diffs
+    pub fn to_text(&self) -> String {
+        let mut out = format!("--- {}\n+++ {}\n", self.old, self.new);
+        if self.is_empty() {
+            out.push_str("\nNo differences\n");
+            return out;
+        }
+
+        if !self.metadata.is_empty() {
+            out.push_str("\nMetadata:\n");
+            let width = self.metadata.iter().map(|c| c.field.len()).max().unwrap_or(0);
+            for change in &self.metadata {
+                let value = |value: &Option<String>| match value {
+                    Some(value) if value.contains('\n') => format!("\n{}\n", value),
+                    Some(value) => value.clone(),
+                    None => "none".to_string(),
+                };
+                out.push_str(&format!(
+                    "  {:<width$}  {} -> {}\n",
+                    change.field,
+                    value(&change.old),
+                    value(&change.new),
+                    width = width
+                ));
+            }
+        }
+
+        let deps = &self.dependencies;
+        if !deps.added.is_empty() || !deps.removed.is_empty() || !deps.changed.is_empty() {
+            out.push_str("\nLocked packages (uv.lock):\n");
+            for package in &deps.added {
+                out.push_str(&format!("  + {}\n", package));
+            }
+            for package in &deps.removed {
+                out.push_str(&format!("  - {}\n", package));
+            }
+            for change in &deps.changed {
+                out.push_str(&format!("  ~ {} {} -> {}\n", change.name, change.old, change.new));
+            }
+        }
+        if !deps.declared_added.is_empty() || !deps.declared_removed.is_empty() {
+            out.push_str("\nDeclared dependencies (pyproject.toml):\n");
+            for requirement in &deps.declared_added {
+                out.push_str(&format!("  + {}\n", requirement));
+            }
+            for requirement in &deps.declared_removed {
+                out.push_str(&format!("  - {}\n", requirement));
+            }
+        }
+
+        out.push_str(&format!(
+            "\nEntries: {} added, {} removed, {} modified, {} unchanged\n",
+            self.added.len(),
+            self.removed.len(),
+            self.modified.len(),
+            self.unchanged
+        ));
+        for entry in &self.added {
+            out.push_str(&format!("  A {} ({})\n", entry.name, format_size(entry.size)));
+        }
+        for entry in &self.removed {
+            out.push_str(&format!("  D {} ({})\n", entry.name, format_size(entry.size)));
+        }
+        for entry in &self.modified {
+            let mut details = Vec::new();
+            if entry.binary {
+                details.push(format!(
+                    "binary, {} -> {}",
+                    format_size(entry.old_size),
+                    format_size(entry.new_size)
+                ));
+            } else if entry.content_changed {
+                details.push(format!("+{} -{}", entry.lines_added, entry.lines_removed));
+            }
+            if entry.old_mode.is_some() || entry.new_mode.is_some() {
+                let old = entry.old_mode.as_deref().unwrap_or("none");
+                let new = entry.new_mode.as_deref().unwrap_or("none");
+                details.push(format!("mode {} -> {}", old, new));
+            }
+            out.push_str(&format!("  M {} ({})\n", entry.name, details.join(", ")));
+        }
+
+        for entry in &self.modified {
+            if let Some(diff) = &entry.diff {
+                out.push('\n');
+                out.push_str(diff);
+                if !diff.ends_with('\n') {
+                    out.push('\n');
+                }
+            }
+        }
+        out
+    }
+}
+
@@ -0,0 +1,352 @@
+use serde::Serialize;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::cache::cache_info;
+use crate::{
+    detect_libc, dir_size, format_size, get_pytron_home, get_uv_command, get_uv_path, host_arch,
+    uv_target_triple, UV_ARCHIVE_ENV, UV_VERSION,
+};
+
+/// Free space below which the disk check warns
+const LOW_DISK_SPACE: u64 = 1024 * 1024 * 1024;
+/// Free space below which the disk check fails (enough for uv plus a small environment)
+const CRITICAL_DISK_SPACE: u64 = 200 * 1024 * 1024;
+
+/// Proxy-related environment variables honoured by uv and reqwest
+const PROXY_VARS: [&str; 8] = [
+    "HTTP_PROXY",
+    "http_proxy",
+    "HTTPS_PROXY",
+    "https_proxy",
+    "ALL_PROXY",
+    "all_proxy",
+    "NO_PROXY",
+    "no_proxy",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Ok,
+    Warn,
+    Fail,
+}
+
+/// The outcome of a single diagnostic
+#[derive(Debug, Serialize)]
+pub struct Check {
+    pub name: String,
+    pub status: CheckStatus,
+    pub message: String,
+}
+
+/// The full set of diagnostics gathered by `pytron doctor`
+#[derive(Debug, Serialize)]
+pub struct DoctorReport {
+    pub pytron_version: String,
+    pub healthy: bool,
+    pub checks: Vec<Check>,
+}
+
+impl DoctorReport {
+    /// Render the report for humans, one line per check
+    pub fn to_text(&self) -> String {
+        let mut out = format!("pytron {} environment report\n\n", self.pytron_version);
+        let width = self.checks.iter().map(|c| c.name.len()).max().unwrap_or(0);
+        for check in &self.checks {
+            let tag = match check.status {
+                CheckStatus::Ok => "[ok]  ",
+                CheckStatus::Warn => "[warn]",
+                CheckStatus::Fail => "[FAIL]",
+            };
+            out.push_str(&format!(
+                "{} {:width$}  {}\n",
+                tag,
+                check.name,
+                check.message,
+                width = width
+            ));
+        }
+        out.push('\n');
+        if self.healthy {
+            out.push_str("No problems found.\n");
+        } else {
+            out.push_str("Some checks failed, see [FAIL] entries above.\n");
+        }
+        out
+    }
+}
+
+fn check(name: &str, status: CheckStatus, message: impl Into<String>) -> Check {
+    Check {
+        name: name.to_string(),
+        status,
+        message: message.into(),
+    }
+}
+
+/// Run all diagnostics and collect them into a report
+pub fn run_doctor() -> DoctorReport {
+    let pytron_home = get_pytron_home();
+    let temp_path = pytron_home.join("temp");
+
+    let checks = vec![
+        check_pytron_home(&pytron_home),
+        check_platform(),
+        check_uv(&pytron_home),
+        check_disk_space(&pytron_home),
+        check_storage_usage(&pytron_home, &temp_path),
+        check_proxy(),
+        check_writable("PYTRON_HOME writable", &pytron_home),
+        check_writable("temp dir writable", &temp_path),
+        check_noexec(&temp_path),
+    ];
+
+    DoctorReport {
+        pytron_version: env!("CARGO_PKG_VERSION").to_string(),
+        healthy: checks.iter().all(|c| c.status != CheckStatus::Fail),
+        checks,
+    }
+}
+
+fn check_pytron_home(pytron_home: &Path) -> Check {
+    let source = if env::var_os("PYTRON_HOME").is_some() {
+        "from PYTRON_HOME"
+    } else {
+        "default, PYTRON_HOME not set"
+    };
+    let state = if pytron_home.is_dir() {
+        "exists"
+    } else if pytron_home.exists() {
+        return check(
+            "PYTRON_HOME",
+            CheckStatus::Fail,
+            format!("{} ({}) is not a directory", pytron_home.display(), source),
+        );
+    } else {
+        "will be created on first use"
+    };
+    check(
+        "PYTRON_HOME",
+        CheckStatus::Ok,
+        format!("{} ({}, {})", pytron_home.display(), source, state),
+    )
+}
+
+fn check_platform() -> Check {
+    let os = env::consts::OS;
+    let arch = host_arch();
+    let libc = detect_libc();
+    let platform = match libc {
+        Some(libc) => format!("{}/{} ({})", os, arch, libc),
+        None => format!("{}/{}", os, arch),
+    };
+    match uv_target_triple(os, arch, libc) {
+        Ok(triple) => check(
+            "platform",
+            CheckStatus::Ok,
+            format!("{}, uv build uv-{}", platform, triple),
+        ),
+        Err(msg) => check(
+            "platform",
+            CheckStatus::Warn,
+            format!("{}: {}", platform, msg),
+        ),
+    }
+}
+
+fn check_uv(pytron_home: &Path) -> Check {
+    let uv_path = get_uv_path();
+    if !uv_path.exists() {
+        let source = match env::var_os(UV_ARCHIVE_ENV) {
+            Some(archive) => format!("installed from {}", PathBuf::from(archive).display()),
+            None => format!("uv {} will be downloaded", UV_VERSION),
+        };
+        return check(
+            "uv",
+            CheckStatus::Warn,
+            format!(
+                "not installed in {}, {} on first run",
+                pytron_home.display(),
+                source
+            ),
+        );
+    }
+
+    let location = if uv_path.parent() == Some(pytron_home) {
+        "PYTRON_HOME"
+    } else {
+        "PYTRON_HOME/bin, legacy location"
+    };
+
+    match get_uv_command().arg("--version").output() {
+        Ok(output) if output.status.success() => {
+            let version = String::from_utf8_lossy(&output.stdout).trim().to_string();
+            check(
+                "uv",
+                CheckStatus::Ok,
+                format!("{} at {} ({})", version, uv_path.display(), location),
+            )
+        }
+        Ok(output) => check(
+            "uv",
+            CheckStatus::Fail,
+            format!(
+                "{} exited with {}: {}",
+                uv_path.display(),
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+        ),
+        Err(err) => check(
+            "uv",
+            CheckStatus::Fail,
+            format!("{} could not be executed: {}", uv_path.display(), err),
+        ),
+    }
+}
+
+fn check_disk_space(pytron_home: &Path) -> Check {
+    let probe = nearest_existing(pytron_home);
+    match fs4::available_space(&probe) {
+        Ok(free) => {
+            let status = if free < CRITICAL_DISK_SPACE {
+                CheckStatus::Fail
+            } else if free < LOW_DISK_SPACE {
+                CheckStatus::Warn
+            } else {
+                CheckStatus::Ok
+            };
+            check(
+                "disk space",
+                status,
+                format!("{} available on {}", format_size(free), probe.display()),
+            )
+        }
+        Err(err) => check(
+            "disk space",
+            CheckStatus::Warn,
+            format!(
+                "could not determine free space on {}: {}",
+                probe.display(),
+                err
+            ),
+        ),
+    }
+}
+
+fn check_storage_usage(pytron_home: &Path, temp_path: &Path) -> Check {
+    let leftover_runs = fs::read_dir(temp_path)
+        .map(|entries| entries.filter_map(Result::ok).count())
+        .unwrap_or(0);
+    let areas: Vec<String> = cache_info()
+        .iter()
+        .map(|area| format!("{} {}", area.name, format_size(area.size)))
+        .collect();
+    check(
+        "storage",
+        CheckStatus::Ok,
+        format!(
+            "{} ({} leftover extraction dirs), PYTRON_HOME total {}",
+            areas.join(", "),
+            leftover_runs,
+            format_size(dir_size(pytron_home))
+        ),
+    )
+}
+
+fn check_proxy() -> Check {
+    let settings: Vec<String> = PROXY_VARS
+        .iter()
+        .filter_map(|name| {
+            env::var(name)
+                .ok()
+                .map(|value| format!("{}={}", name, redact_url_credentials(&value)))
+        })
+        .collect();
+    if settings.is_empty() {
+        check("network proxy", CheckStatus::Ok, "no proxy configured")
+    } else {
+        check("network proxy", CheckStatus::Ok, settings.join(", "))
+    }
+}
+
+fn check_writable(name: &str, dir: &Path) -> Check {
+    // Missing directories are created on demand, so test the closest existing parent
+    let probe = nearest_existing(dir);
+    match tempfile::tempfile_in(&probe) {
+        Ok(_) => check(name, CheckStatus::Ok, format!("{}", probe.display())),
+        Err(err) => check(
+            name,
+            CheckStatus::Fail,
+            format!("cannot write to {}: {}", probe.display(), err),
+        ),
+    }
+}
+
+fn check_noexec(temp_path: &Path) -> Check {
+    let probe = nearest_existing(temp_path);
+    match is_noexec_mount(&probe) {
+        Some(true) => check(
+            "temp dir exec",
+            CheckStatus::Fail,
+            format!(
+                "{} is on a noexec mount, virtual environments created there cannot run. Point PYTRON_HOME elsewhere",
+                probe.display()
+            ),
+        ),
+        Some(false) => check("temp dir exec", CheckStatus::Ok, format!("{}", probe.display())),
+        None => check(
+            "temp dir exec",
+            CheckStatus::Ok,
+            "noexec detection not supported on this platform",
+        ),
+    }
+}
+
+/// Returns whether `path` lives on a filesystem mounted noexec, or None if unknown
+#[cfg(target_os = "linux")]
+pub fn is_noexec_mount(path: &Path) -> Option<bool> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    // SAFETY: c_path is a valid NUL-terminated string and stat is a valid out pointer
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+    Some(stat.f_flag & libc::ST_NOEXEC != 0)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn is_noexec_mount(_path: &Path) -> Option<bool> {
+    None
+}
+
+/// Walk up from `path` until an existing directory is found
+fn nearest_existing(path: &Path) -> PathBuf {
+    let mut current = path;
+    while !current.exists() {
+        match current.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => current = parent,
+            _ => return PathBuf::from("."),
+        }
+    }
+    current.to_path_buf()
+}
+
+/// Hide `user:password@` in proxy URLs
+fn redact_url_credentials(value: &str) -> String {
+    if let Some(scheme_end) = value.find("://") {
+        let rest = &value[scheme_end + 3..];
+        if let Some(at) = rest.find('@') {
+            if !rest[..at].contains('/') {
+                return format!("{}://***@{}", &value[..scheme_end], &rest[at + 1..]);
+            }
+        }
+    }
+    value.to_string()
+}
@@ -0,0 +1,183 @@
+use globset::{GlobBuilder, GlobMatcher};
+use std::fs::{self, File};
+use std::io::{self, Read, Seek};
+use std::path::{Path, PathBuf};
+use zip::ZipArchive;
+
+use crate::archive::{self, ArchiveError};
+use crate::manifest::{self, Manifest, Mismatch};
+use crate::{secrets, sha256_file};
+
+/// Which entries `extract_entries` writes and where
+#[derive(Debug, Clone, Default)]
+pub struct ExtractOptions {
+    /// Replace files that already exist in the destination
+    pub overwrite: bool,
+    /// Only extract entries matching one of these globs, or below a matching directory
+    pub only: Vec<String>,
+    /// Leading path components removed from every entry name; shorter entries are skipped
+    pub strip_components: usize,
+}
+
+/// What `extract_entries` wrote
+#[derive(Debug, Default)]
+pub struct Extracted {
+    /// Entry names and the files they were written to
+    pub files: Vec<(String, PathBuf)>,
+    /// Contents of the secrets entry, which is never written to disk
+    pub secrets: Option<Vec<u8>>,
+}
+
+fn invalid_input(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, msg)
+}
+
+fn compile_globs(patterns: &[String]) -> io::Result<Vec<GlobMatcher>> {
+    patterns
+        .iter()
+        .map(|pattern| {
+            GlobBuilder::new(pattern.trim_end_matches('/'))
+                .literal_separator(true)
+                .build()
+                .map(|glob| glob.compile_matcher())
+                .map_err(|e| invalid_input(format!("Invalid pattern '{}': {}", pattern, e)))
+        })
+        .collect()
+}
+
+/// Whether the entry or one of the directories it is in matches a glob
+fn selected(globs: &[GlobMatcher], name: &str) -> bool {
+    if globs.is_empty() {
+        return true;
+    }
+    let name = name.trim_end_matches('/');
+    let mut prefixes = name
+        .match_indices('/')
+        .map(|(pos, _)| &name[..pos])
+        .chain(std::iter::once(name));
+    prefixes.any(|prefix| globs.iter().any(|glob| glob.is_match(prefix)))
+}
+
+/// Path of an entry below the destination, rejecting names that would escape
+/// it (absolute paths, `..`) as a corrupt archive. None when the entry has no
+/// components left after stripping.
+fn entry_path(name: &str, enclosed: Option<PathBuf>, strip: usize) -> io::Result<Option<PathBuf>> {
+    let enclosed = enclosed.ok_or_else(|| {
+        io::Error::from(ArchiveError::Corrupt(format!(
+            "entry {} points outside the extraction directory",
+            name
+        )))
+    })?;
+    let stripped: PathBuf = enclosed.components().skip(strip).collect();
+    Ok((!stripped.as_os_str().is_empty()).then_some(stripped))
+}
+
+/// Scripts and extension-less files (binaries, shell scripts) are made executable
+#[cfg(unix)]
+fn set_permissions(name: &str, path: &Path) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    if name.ends_with(".py") || !name.rsplit('/').next().unwrap_or(name).contains('.') {
+        let mut perms = path.metadata()?.permissions();
+        perms.set_mode(0o755);
+        fs::set_permissions(path, perms)?;
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn set_permissions(_name: &str, _path: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+/// Extract the entries of an opened archive below `dest`, which is created if
+/// needed. Entry names are validated and existing files checked before
+/// anything is written. The secrets entry is returned instead of written.
+pub fn extract_entries<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    dest: &Path,
+    password: Option<&str>,
+    options: &ExtractOptions,
+) -> io::Result<Extracted> {
+    let globs = compile_globs(&options.only)?;
+
+    // Plan the extraction first so nothing is written for an unsafe archive
+    let mut planned = Vec::new();
+    let mut secrets_index = None;
+    for index in 0..archive.len() {
+        let entry = archive.by_index_raw(index).map_err(|err| {
+            io::Error::from(ArchiveError::Corrupt(format!("entry {}: {}", index, err)))
+        })?;
+        let name = entry.name().to_string();
+        if name == secrets::SECRETS_ENTRY {
+            secrets_index = Some(index);
+            continue;
+        }
+        let Some(relative) = entry_path(&name, entry.enclosed_name(), options.strip_components)?
+        else {
+            continue;
+        };
+        if !selected(&globs, &name) {
+            continue;
+        }
+        let outpath = dest.join(relative);
+        if !entry.is_dir() && !options.overwrite && outpath.exists() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!(
+                    "{} already exists, use --overwrite to replace it",
+                    outpath.display()
+                ),
+            ));
+        }
+        planned.push((index, name, entry.is_dir(), outpath));
+    }
+
+    let mut extracted = Extracted::default();
+    fs::create_dir_all(dest)?;
+    for (index, name, is_dir, outpath) in planned {
+        if is_dir {
+            fs::create_dir_all(&outpath)?;
+            continue;
+        }
+        if let Some(parent) = outpath.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file = archive::read_entry(archive, index, password)?;
+        let mut outfile = File::create(&outpath)?;
+        archive::copy_entry(&mut file, &mut outfile)?;
+        set_permissions(&name, &outpath)?;
+        extracted.files.push((name, outpath));
+    }
+
+    if let Some(index) = secrets_index {
+        let mut file = archive::read_entry(archive, index, password)?;
+        let mut data = Vec::new();
+        archive::copy_entry(&mut file, &mut data)?;
+        extracted.secrets = Some(data);
+    }
+    Ok(extracted)
+}
+
+/// Check the extracted files against the archive manifest. Unlike the check
+/// in `run`, only the files that were extracted are compared, so it also
+/// works with `--only` and `--strip-components`.
+pub fn verify_extracted(expected: &Manifest, extracted: &Extracted) -> io::Result<usize> {
+    let mut mismatches = Vec::new();
+    let mut checked = 0;
+    for (name, path) in &extracted.files {
+        if name == manifest::MANIFEST_ENTRY {
+            continue;
+        }
+        match expected.files.get(name) {
+            None => mismatches.push(Mismatch::Extra(name.clone())),
+            Some(digest) if *digest != sha256_file(path)? => {
+                mismatches.push(Mismatch::Modified(name.clone()))
+            }
+            Some(_) => checked += 1,
+        }
+    }
+    if !mismatches.is_empty() {
+        return Err(manifest::mismatch_error(&mismatches));
+    }
+    Ok(checked)
+}
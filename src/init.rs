@@ -0,0 +1,243 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+use crate::get_pytron_home;
+
+/// Template used when `--template` is not given
+pub const DEFAULT_TEMPLATE: &str = "cli";
+/// Python version written to `.python-version` and `requires-python`
+pub const DEFAULT_PYTHON_VERSION: &str = "3.12";
+
+const GITIGNORE: &str = include_str!("templates/common/gitignore");
+const PYTHON_VERSION: &str = include_str!("templates/common/python-version");
+
+/// Relative path and contents of a file in a builtin template
+type TemplateFile = (&'static str, &'static str);
+
+/// Templates shipped with pytron: name, description and files
+const BUILTIN_TEMPLATES: &[(&str, &str, &[TemplateFile])] = &[
+    (
+        "cli",
+        "command line tool with argparse",
+        &[
+            ("pyproject.toml", include_str!("templates/cli/pyproject.toml")),
+            ("main.py", include_str!("templates/cli/main.py")),
+        ],
+    ),
+    (
+        "service",
+        "long-running loop with signal handling and logging",
+        &[
+            ("pyproject.toml", include_str!("templates/service/pyproject.toml")),
+            ("main.py", include_str!("templates/service/main.py")),
+        ],
+    ),
+    (
+        "data-pipeline",
+        "extract/transform/load steps in a package",
+        &[
+            ("pyproject.toml", include_str!("templates/data-pipeline/pyproject.toml")),
+            ("main.py", include_str!("templates/data-pipeline/main.py")),
+            (
+                "{{module}}/__init__.py",
+                include_str!("templates/data-pipeline/package/__init__.py"),
+            ),
+            (
+                "{{module}}/steps.py",
+                include_str!("templates/data-pipeline/package/steps.py"),
+            ),
+        ],
+    ),
+];
+
+/// Where a template comes from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemplateSource {
+    Builtin,
+    /// A directory in PYTRON_HOME/templates
+    User(PathBuf),
+}
+
+/// A template `pytron init` can generate a project from
+#[derive(Debug, Clone)]
+pub struct Template {
+    pub name: String,
+    pub description: String,
+    pub source: TemplateSource,
+}
+
+/// Values substituted for `{{name}}`, `{{module}}` and `{{python_version}}`
+/// in file names and contents
+#[derive(Debug, Clone)]
+pub struct TemplateVars {
+    /// Project name for pyproject.toml, e.g. `my-robot`
+    pub name: String,
+    /// Importable Python name, e.g. `my_robot`
+    pub module: String,
+    pub python_version: String,
+}
+
+impl TemplateVars {
+    /// Derive the names from a project name, normalized like package names
+    pub fn new(name: &str, python_version: &str) -> Self {
+        let mut normalized = String::new();
+        for c in name.trim().chars() {
+            if c.is_ascii_alphanumeric() {
+                normalized.push(c.to_ascii_lowercase());
+            } else if !normalized.is_empty() && !normalized.ends_with('-') {
+                normalized.push('-');
+            }
+        }
+        let normalized = normalized.trim_end_matches('-');
+        let name = if normalized.is_empty() {
+            "project"
+        } else {
+            normalized
+        };
+        let mut module = name.replace('-', "_");
+        if module.starts_with(|c: char| c.is_ascii_digit()) {
+            module.insert(0, '_');
+        }
+        TemplateVars {
+            name: name.to_string(),
+            module,
+            python_version: python_version.to_string(),
+        }
+    }
+
+    fn apply(&self, text: &str) -> String {
+        text.replace("{{name}}", &self.name)
+            .replace("{{module}}", &self.module)
+            .replace("{{python_version}}", &self.python_version)
+    }
+}
+
+/// Directory holding user-defined templates, one subdirectory each
+pub fn get_templates_dir() -> PathBuf {
+    get_pytron_home().join("templates")
+}
+
+/// All available templates. User templates come first and replace builtin
+/// templates of the same name.
+pub fn list_templates() -> io::Result<Vec<Template>> {
+    let mut templates = Vec::new();
+    match fs::read_dir(get_templates_dir()) {
+        Ok(entries) => {
+            for entry in entries {
+                let path = entry?.path();
+                if !path.is_dir() {
+                    continue;
+                }
+                let name = path.file_name().unwrap().to_string_lossy().to_string();
+                let description = fs::read_to_string(path.join(".description"))
+                    .map(|text| text.trim().to_string())
+                    .unwrap_or_else(|_| "user template".to_string());
+                templates.push(Template {
+                    name,
+                    description,
+                    source: TemplateSource::User(path),
+                });
+            }
+        }
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+        Err(err) => return Err(err),
+    }
+    templates.sort_by(|a, b| a.name.cmp(&b.name));
+    for (name, description, _) in BUILTIN_TEMPLATES {
+        if !templates.iter().any(|t| t.name == *name) {
+            templates.push(Template {
+                name: name.to_string(),
+                description: description.to_string(),
+                source: TemplateSource::Builtin,
+            });
+        }
+    }
+    Ok(templates)
+}
+
+/// The files of a template as relative paths and contents, before substitution
+fn template_files(template: &Template) -> io::Result<Vec<(String, Vec<u8>)>> {
+    let mut files: Vec<(String, Vec<u8>)> = vec![
+        (".gitignore".to_string(), GITIGNORE.as_bytes().to_vec()),
+        (".python-version".to_string(), PYTHON_VERSION.as_bytes().to_vec()),
+    ];
+    let own: Vec<(String, Vec<u8>)> = match &template.source {
+        TemplateSource::Builtin => BUILTIN_TEMPLATES
+            .iter()
+            .find(|(name, _, _)| *name == template.name)
+            .map(|(_, _, files)| files.iter())
+            .into_iter()
+            .flatten()
+            .map(|(path, contents)| (path.to_string(), contents.as_bytes().to_vec()))
+            .collect(),
+        TemplateSource::User(dir) => {
+            let mut own = Vec::new();
+            for entry in WalkDir::new(dir).sort_by_file_name() {
+                let entry = entry.map_err(io::Error::other)?;
+                if !entry.file_type().is_file() {
+                    continue;
+                }
+                let relative = entry
+                    .path()
+                    .strip_prefix(dir)
+                    .map_err(io::Error::other)?
+                    .to_string_lossy()
+                    .replace('\\', "/");
+                if relative != ".description" {
+                    own.push((relative, fs::read(entry.path())?));
+                }
+            }
+            own
+        }
+    };
+    // Files of the template replace the common ones
+    files.retain(|(path, _)| !own.iter().any(|(own_path, _)| own_path == path));
+    files.extend(own);
+    Ok(files)
+}
+
+/// Generate a project from `template` in `dir`, which is created if needed.
+/// Existing files are only replaced with `overwrite`; nothing is written when
+/// one would be. Returns the paths written.
+pub fn init_project(
+    dir: &Path,
+    template: &Template,
+    vars: &TemplateVars,
+    overwrite: bool,
+) -> io::Result<Vec<PathBuf>> {
+    let files: Vec<(PathBuf, Vec<u8>)> = template_files(template)?
+        .into_iter()
+        .map(|(path, contents)| {
+            // Only text files get their placeholders replaced
+            let contents = match String::from_utf8(contents) {
+                Ok(text) => vars.apply(&text).into_bytes(),
+                Err(err) => err.into_bytes(),
+            };
+            (dir.join(vars.apply(&path)), contents)
+        })
+        .collect();
+
+    if !overwrite {
+        if let Some((existing, _)) = files.iter().find(|(path, _)| path.exists()) {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!(
+                    "{} already exists, use --force to overwrite it",
+                    existing.display()
+                ),
+            ));
+        }
+    }
+
+    let mut written = Vec::new();
+    for (path, contents) in files {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, contents)?;
+        written.push(path);
+    }
+    Ok(written)
+}
@@ -0,0 +1,494 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Read, Seek};
+use std::path::Path;
+use zip::ZipArchive;
+
+use crate::archive::{self, ArchiveError};
+use crate::commands::{self, NamedCommand};
+use crate::manifest::{self, MANIFEST_ENTRY};
+use crate::run_args::DEFAULT_ENTRY;
+use crate::sbom::{self, SbomFormat};
+use crate::secrets::SECRETS_ENTRY;
+use crate::signing::{self, SignatureStatus};
+use crate::{format_size, recipients, sealed};
+
+/// Project files whose presence `pytron inspect` reports
+pub const PROJECT_FILES: [&str; 3] = ["pyproject.toml", "uv.lock", ".python-version"];
+
+/// How the archive file itself is stored
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Container {
+    /// A plain zip file, possibly with AES-encrypted entries
+    Zip,
+    /// Encrypted as a whole with a password (`pytron zip --seal`)
+    Sealed,
+    /// Encrypted as a whole to recipients (`pytron zip --recipient`)
+    Recipients,
+}
+
+impl Container {
+    /// Detect how the archive at `path` is stored
+    pub fn detect(path: &Path) -> io::Result<Self> {
+        if recipients::is_recipient_encrypted(path)? {
+            Ok(Container::Recipients)
+        } else if sealed::is_sealed(path)? {
+            Ok(Container::Sealed)
+        } else {
+            Ok(Container::Zip)
+        }
+    }
+
+    pub(crate) fn describe(&self) -> &'static str {
+        match self {
+            Container::Zip => "zip",
+            Container::Sealed => "sealed with a password",
+            Container::Recipients => "encrypted to recipients",
+        }
+    }
+}
+
+fn saved_percent(size: u64, compressed: u64) -> f64 {
+    if size == 0 {
+        0.0
+    } else {
+        100.0 * (1.0 - compressed as f64 / size as f64)
+    }
+}
+
+/// One entry of the archive
+#[derive(Debug, Clone, Serialize)]
+pub struct EntryInfo {
+    pub name: String,
+    pub is_dir: bool,
+    pub size: u64,
+    pub compressed_size: u64,
+    pub compression: String,
+    pub encrypted: bool,
+    /// Unix permission bits, if the archive records them; octal in JSON
+    #[serde(serialize_with = "serialize_mode")]
+    pub mode: Option<u32>,
+    /// Space saved by compression, in percent of the original size
+    pub ratio: f64,
+}
+
+fn serialize_mode<S: serde::Serializer>(
+    mode: &Option<u32>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match mode {
+        Some(mode) => serializer.serialize_str(&format!("{:04o}", mode)),
+        None => serializer.serialize_none(),
+    }
+}
+
+impl EntryInfo {
+    /// Permissions in `ls -l` form, e.g. `-rwxr-xr-x`
+    pub fn permissions(&self) -> String {
+        let Some(mode) = self.mode else {
+            return "-".to_string();
+        };
+        let mut text = String::from(if self.is_dir { "d" } else { "-" });
+        for shift in [6, 3, 0] {
+            let bits = (mode >> shift) & 0o7;
+            text.push(if bits & 4 != 0 { 'r' } else { '-' });
+            text.push(if bits & 2 != 0 { 'w' } else { '-' });
+            text.push(if bits & 1 != 0 { 'x' } else { '-' });
+        }
+        text
+    }
+}
+
+/// Python project details read from the packaged project files
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ProjectInfo {
+    /// Which of `PROJECT_FILES` are in the archive
+    pub files: Vec<String>,
+    pub name: Option<String>,
+    pub version: Option<String>,
+    pub requires_python: Option<String>,
+    /// Interpreter pinned in `.python-version`
+    pub python_version: Option<String>,
+    /// Number of packages pinned in `uv.lock`
+    pub locked_packages: Option<usize>,
+    /// Named commands from `[tool.pytron.commands]`
+    pub commands: Vec<NamedCommand>,
+    /// Project files that could not be read because they are encrypted
+    pub unreadable: Vec<String>,
+}
+
+/// Metadata pytron embeds in an archive
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct EmbeddedMetadata {
+    /// Signature status: unsigned, valid or invalid, with the signer key
+    pub signature: String,
+    pub signer: Option<String>,
+    /// Whether the archive has a content manifest
+    pub manifest: bool,
+    /// Number of files listed in the content manifest, if it could be read
+    pub manifest_files: Option<usize>,
+    /// SBOM entries stored in the archive
+    pub sbom: Vec<String>,
+    /// How the embedded secrets are protected: "password", "recipients", or
+    /// "unknown" when the entry is encrypted and no password was given
+    pub secrets: Option<String>,
+}
+
+/// Everything `pytron inspect` reports about an archive
+#[derive(Debug, Clone, Serialize)]
+pub struct ArchiveInfo {
+    pub archive: String,
+    pub container: Container,
+    /// Size of the archive file on disk
+    pub archive_size: u64,
+    /// Script `pytron run` starts by default, if the archive contains it
+    pub entry_script: Option<String>,
+    pub project: ProjectInfo,
+    pub metadata: EmbeddedMetadata,
+    pub entries: Vec<EntryInfo>,
+}
+
+impl ArchiveInfo {
+    fn files(&self) -> impl Iterator<Item = &EntryInfo> {
+        self.entries.iter().filter(|entry| !entry.is_dir)
+    }
+
+    /// Summary lines about the archive, without the entry listing
+    fn header(&self) -> String {
+        let mut out = format!(
+            "Archive:    {} ({}, {})\n",
+            self.archive,
+            self.container.describe(),
+            format_size(self.archive_size)
+        );
+        out.push_str(&format!(
+            "Entry:      {}\n",
+            self.entry_script.as_deref().unwrap_or("none (no main.py)")
+        ));
+        if !self.project.commands.is_empty() {
+            out.push_str("Commands:\n");
+            out.push_str(&commands::format_commands(&self.project.commands));
+        }
+
+        let project = &self.project;
+        if project.files.is_empty() {
+            out.push_str("Project:    no pyproject.toml, uv.lock or .python-version\n");
+        } else {
+            let mut details = Vec::new();
+            if let Some(name) = &project.name {
+                let version = project.version.as_deref().unwrap_or("");
+                details.push(format!("{} {}", name, version).trim_end().to_string());
+            }
+            if let Some(requires) = &project.requires_python {
+                details.push(format!("requires-python {}", requires));
+            }
+            if let Some(version) = &project.python_version {
+                details.push(format!("Python {}", version));
+            }
+            if let Some(count) = project.locked_packages {
+                details.push(format!("{} locked package(s)", count));
+            }
+            if !project.unreadable.is_empty() {
+                details.push(format!("encrypted: {}", project.unreadable.join(", ")));
+            }
+            out.push_str(&format!("Project:    {}\n", project.files.join(", ")));
+            if !details.is_empty() {
+                out.push_str(&format!("            {}\n", details.join(", ")));
+            }
+        }
+
+        let metadata = &self.metadata;
+        let signature = match &metadata.signer {
+            Some(signer) => format!("{} (key {})", metadata.signature, signer),
+            None => metadata.signature.clone(),
+        };
+        out.push_str(&format!("Signature:  {}\n", signature));
+        out.push_str(&format!(
+            "Manifest:   {}\n",
+            match (metadata.manifest, metadata.manifest_files) {
+                (_, Some(count)) => format!("{} file(s) in {}", count, MANIFEST_ENTRY),
+                (true, None) => format!("{} (encrypted)", MANIFEST_ENTRY),
+                (false, None) => "none".to_string(),
+            }
+        ));
+        if !metadata.sbom.is_empty() {
+            out.push_str(&format!("SBOM:       {}\n", metadata.sbom.join(", ")));
+        }
+        if let Some(protection) = &metadata.secrets {
+            out.push_str(&format!("Secrets:    {} ({})\n", SECRETS_ENTRY, protection));
+        }
+        out
+    }
+
+    fn totals(&self) -> String {
+        let size: u64 = self.files().map(|entry| entry.size).sum();
+        let compressed: u64 = self.files().map(|entry| entry.compressed_size).sum();
+        let encrypted = self.files().filter(|entry| entry.encrypted).count();
+        format!(
+            "{} file(s), {} ({} packed, {:.0}% saved), {} encrypted\n",
+            self.files().count(),
+            format_size(size),
+            format_size(compressed),
+            saved_percent(size, compressed),
+            encrypted
+        )
+    }
+
+    /// Render the report for humans with a table of entries
+    pub fn to_text(&self) -> String {
+        let mut out = self.header();
+        out.push('\n');
+        let width = self
+            .entries
+            .iter()
+            .map(|entry| entry.compression.len())
+            .max()
+            .unwrap_or(0)
+            .max("Method".len());
+        out.push_str(&format!(
+            "{:<10} {:>10} {:>10} {:>6} {:<width$} {:<3}  Name\n",
+            "Mode",
+            "Size",
+            "Packed",
+            "Ratio",
+            "Method",
+            "Enc",
+            width = width
+        ));
+        for entry in &self.entries {
+            out.push_str(&format!(
+                "{:<10} {:>10} {:>10} {:>5.0}% {:<width$} {:<3}  {}\n",
+                entry.permissions(),
+                entry.size,
+                entry.compressed_size,
+                entry.ratio,
+                entry.compression,
+                if entry.encrypted { "AES" } else { "-" },
+                entry.name,
+                width = width
+            ));
+        }
+        out.push('\n');
+        out.push_str(&self.totals());
+        out
+    }
+
+    /// Render the report for humans with the entries as a directory tree
+    pub fn to_tree(&self) -> String {
+        #[derive(Default)]
+        struct Node<'a> {
+            children: BTreeMap<&'a str, Node<'a>>,
+            entry: Option<&'a EntryInfo>,
+        }
+
+        fn render(node: &Node, prefix: &str, out: &mut String) {
+            let count = node.children.len();
+            for (i, (name, child)) in node.children.iter().enumerate() {
+                let last = i + 1 == count;
+                let branch = if last { "└── " } else { "├── " };
+                let label = match child.entry {
+                    Some(entry) if !entry.is_dir => format!(
+                        "{} ({}{})",
+                        name,
+                        format_size(entry.size),
+                        if entry.encrypted { ", encrypted" } else { "" }
+                    ),
+                    _ => format!("{}/", name),
+                };
+                out.push_str(&format!("{}{}{}\n", prefix, branch, label));
+                let indent = if last { "    " } else { "│   " };
+                render(child, &format!("{}{}", prefix, indent), out);
+            }
+        }
+
+        let mut root = Node::default();
+        for entry in &self.entries {
+            let mut node = &mut root;
+            for part in entry.name.split('/').filter(|part| !part.is_empty()) {
+                node = node.children.entry(part).or_default();
+            }
+            node.entry = Some(entry);
+        }
+
+        let mut out = self.header();
+        out.push('\n');
+        out.push_str(&format!("{}\n", self.archive));
+        render(&root, "", &mut out);
+        out.push('\n');
+        out.push_str(&self.totals());
+        out
+    }
+}
+
+fn password_required(err: &io::Error) -> bool {
+    matches!(
+        archive::archive_error(err),
+        Some(ArchiveError::PasswordRequired { .. })
+    )
+}
+
+/// Read a small text entry, or None if it is encrypted and no password was given
+fn read_text<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    index: usize,
+    password: Option<&str>,
+) -> io::Result<Option<String>> {
+    let mut entry = match archive::read_entry(archive, index, password) {
+        Ok(entry) => entry,
+        Err(err) if password_required(&err) => return Ok(None),
+        Err(err) => return Err(err),
+    };
+    let name = entry.name().to_string();
+    let mut data = Vec::new();
+    archive::copy_entry(&mut entry, &mut data)?;
+    String::from_utf8(data)
+        .map(Some)
+        .map_err(|_| ArchiveError::Corrupt(format!("{} is not valid UTF-8", name)).into())
+}
+
+fn read_project<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    password: Option<&str>,
+) -> io::Result<ProjectInfo> {
+    let mut project = ProjectInfo::default();
+    for file in PROJECT_FILES {
+        let Some(index) = archive.index_for_name(file) else {
+            continue;
+        };
+        project.files.push(file.to_string());
+        let Some(text) = read_text(archive, index, password)? else {
+            project.unreadable.push(file.to_string());
+            continue;
+        };
+        match file {
+            "pyproject.toml" => {
+                // Only the fields are needed, so a damaged file is reported without them
+                let Ok(pyproject) = toml::from_str::<toml::Table>(&text) else {
+                    continue;
+                };
+                let field = |key: &str| {
+                    pyproject
+                        .get("project")
+                        .and_then(|p| p.get(key))
+                        .and_then(|v| v.as_str())
+                        .map(str::to_string)
+                };
+                project.name = field("name");
+                project.version = field("version");
+                project.requires_python = field("requires-python").or(project.requires_python);
+                project.commands = commands::parse_commands(&text).unwrap_or_default();
+            }
+            "uv.lock" => {
+                if let Ok((packages, requires_python)) = sbom::parse_uv_lock(&text) {
+                    project.locked_packages =
+                        Some(packages.iter().filter(|package| !package.is_root).count());
+                    if project.requires_python.is_none() {
+                        project.requires_python = requires_python;
+                    }
+                }
+            }
+            _ => {
+                project.python_version = text
+                    .lines()
+                    .map(str::trim)
+                    .find(|line| !line.is_empty() && !line.starts_with('#'))
+                    .map(str::to_string);
+            }
+        }
+    }
+    Ok(project)
+}
+
+fn read_metadata<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    password: Option<&str>,
+) -> io::Result<EmbeddedMetadata> {
+    let mut metadata = EmbeddedMetadata::default();
+    match signing::verify_archive_with(archive, &signing::load_trusted_keys()?)? {
+        SignatureStatus::Unsigned => metadata.signature = "unsigned".to_string(),
+        SignatureStatus::Valid {
+            key_id,
+            trusted_key,
+        } => {
+            metadata.signature = match trusted_key {
+                Some(_) => "valid, trusted".to_string(),
+                None => "valid, not trusted".to_string(),
+            };
+            metadata.signer = Some(key_id);
+        }
+        SignatureStatus::Invalid(reason) => metadata.signature = format!("INVALID: {}", reason),
+    }
+
+    metadata.manifest = archive.index_for_name(MANIFEST_ENTRY).is_some();
+    metadata.manifest_files = match manifest::read_manifest(archive, password) {
+        Ok(manifest) => manifest.map(|manifest| manifest.files.len()),
+        Err(err) if password_required(&err) => None,
+        Err(err) => return Err(err),
+    };
+    for format in [SbomFormat::Cyclonedx, SbomFormat::Spdx] {
+        if archive.index_for_name(format.entry_name()).is_some() {
+            metadata.sbom.push(format.entry_name().to_string());
+        }
+    }
+    if let Some(index) = archive.index_for_name(SECRETS_ENTRY) {
+        let mut magic = [0u8; 8];
+        let protection = match archive::read_entry(archive, index, password) {
+            Ok(mut entry) => {
+                if entry.read_exact(&mut magic).is_ok() && &magic == sealed::SEALED_MAGIC {
+                    "password"
+                } else {
+                    "recipients"
+                }
+            }
+            Err(err) if password_required(&err) => "unknown",
+            Err(err) => return Err(err),
+        };
+        metadata.secrets = Some(protection.to_string());
+    }
+    Ok(metadata)
+}
+
+/// Gather the report for an opened archive. Entries are listed from the
+/// central directory, so only the project files and pytron's metadata are
+/// read; without a password, encrypted ones are reported as unreadable.
+pub fn inspect_archive<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    path: &Path,
+    password: Option<&str>,
+) -> io::Result<ArchiveInfo> {
+    let mut entries = Vec::with_capacity(archive.len());
+    for index in 0..archive.len() {
+        let entry = archive.by_index_raw(index).map_err(|err| {
+            io::Error::from(ArchiveError::Corrupt(format!("entry {}: {}", index, err)))
+        })?;
+        entries.push(EntryInfo {
+            name: entry.name().to_string(),
+            is_dir: entry.is_dir(),
+            size: entry.size(),
+            compressed_size: entry.compressed_size(),
+            compression: entry.compression().to_string(),
+            encrypted: entry.encrypted(),
+            mode: entry.unix_mode().map(|mode| mode & 0o7777),
+            ratio: (saved_percent(entry.size(), entry.compressed_size()) * 10.0).round() / 10.0,
+        });
+    }
+
+    let entry_script = entries
+        .iter()
+        .any(|entry| entry.name == DEFAULT_ENTRY)
+        .then(|| DEFAULT_ENTRY.to_string());
+    let project = read_project(archive, password)?;
+    let metadata = read_metadata(archive, password)?;
+
+    Ok(ArchiveInfo {
+        archive: path.display().to_string(),
+        container: Container::detect(path)?,
+        archive_size: fs::metadata(path)?.len(),
+        entry_script,
+        project,
+        metadata,
+        entries,
+    })
+}
@@ -1,5 +1,4 @@
-use clap::{Parser, Subcommand};
-use ignore::WalkBuilder;
+use clap::{CommandFactory, Parser, Subcommand};
 use reqwest::blocking::Client;
 use std::env;
 use std::fs::{self, File};
@@ -9,6 +8,30 @@ use std::process::Command;
 use zip::write::SimpleFileOptions;
 use zip::{ZipArchive, ZipWriter};
 
+pub mod archive;
+pub mod cache;
+pub mod commands;
+pub mod completions;
+pub mod config;
+pub mod diff;
+pub mod doctor;
+pub mod extract;
+pub mod init;
+pub mod inspect;
+pub mod manifest;
+pub mod output;
+pub mod password;
+pub mod recipients;
+pub mod run_args;
+pub mod sandbox;
+pub mod sbom;
+pub mod scan;
+pub mod secrets;
+pub mod sealed;
+pub mod selection;
+pub mod signing;
+pub mod trust;
+
 #[cfg(windows)]
 use winreg::enums::{HKEY_LOCAL_MACHINE, KEY_READ, KEY_WRITE};
 #[cfg(windows)]
@@ -20,6 +43,38 @@ use winreg::RegKey;
 pub struct Cli {
     #[command(subcommand)]
     pub command: Commands,
+
+    /// Print a single JSON result document instead of text (zip, run, inspect, diff, doctor, cache, uv)
+    /// Progress messages and the output of scripts go to stderr
+    #[arg(long, global = true)]
+    pub json: bool,
+}
+
+/// Subcommands that print a result document with `--json`
+pub const JSON_COMMANDS: &[&str] = &["zip", "run", "inspect", "diff", "doctor", "cache", "uv"];
+
+/// The clap command of [`Cli`], with the global `--json` hidden from the help
+/// of the subcommands that have no JSON output. main.rs rejects it there.
+pub fn cli_command() -> clap::Command {
+    let command = Cli::command();
+    let names: Vec<String> = command
+        .get_subcommands()
+        .map(|sub| sub.get_name().to_string())
+        .filter(|name| !JSON_COMMANDS.contains(&name.as_str()))
+        .collect();
+    names.into_iter().fold(command, |command, name| {
+        // clap only propagates a global argument to subcommands without one
+        // of the same id
+        command.mut_subcommand(name, |sub| {
+            sub.arg(
+                clap::Arg::new("json")
+                    .long("json")
+                    .action(clap::ArgAction::SetTrue)
+                    .global(true)
+                    .hide(true),
+            )
+        })
+    })
 }
 
 #[derive(Subcommand)]
@@ -30,7 +85,7 @@ pub enum Commands {
         #[arg(default_value = ".")]
         directory: String,
 
-        /// Output zip filename
+        /// Output zip filename, also configurable as zip.output
         #[arg(short, long, default_value = "robot.zip")]
         output: String,
 
@@ -41,9 +96,69 @@ pub enum Commands {
         #[arg(short, long, value_delimiter = ',')]
         ignore_patterns: Option<Vec<String>>,
 
-        /// Additional AES encryption password
-        #[arg(short, long)]
-        password: Option<String>
+        /// Additional AES encryption password (visible in process lists, prefer the options below)
+        #[arg(short, long, group = "password_source")]
+        password: Option<String>,
+
+        /// Read the encryption password from the first line of this file
+        #[arg(long, value_name = "FILE", value_hint = clap::ValueHint::FilePath, group = "password_source")]
+        password_file: Option<String>,
+
+        /// Read the encryption password from this environment variable
+        /// Without any password option, PYTRON_PASSWORD is used if set
+        #[arg(long, value_name = "VAR", group = "password_source")]
+        password_env: Option<String>,
+
+        /// Prompt for the encryption password without echoing it
+        #[arg(long, group = "password_source")]
+        ask_password: bool,
+
+        /// Seal the whole archive (AES-256-GCM, Argon2id) so entry names and sizes are hidden too
+        /// Needs a password; `pytron run` unseals it in memory
+        #[arg(long)]
+        seal: bool,
+
+        /// Encrypt the archive to this X25519 recipient (age1... key or a file of keys)
+        /// Repeat to grant access to several hosts; each decrypts with its own private key
+        #[arg(short, long = "recipient", value_name = "RECIPIENT", conflicts_with = "seal")]
+        recipients: Vec<String>,
+
+        /// Store the KEY=value pairs of this file encrypted in the archive
+        /// `pytron run` decrypts them in memory and passes them to the script as environment variables
+        /// Needs a password or --recipient; the file itself is never added in plain text
+        #[arg(long, value_name = "FILE", value_hint = clap::ValueHint::FilePath)]
+        secrets: Option<String>,
+
+        /// Embed a software bill of materials in .pytron/ (comma-separated for both)
+        #[arg(long, value_name = "FORMAT", value_enum, value_delimiter = ',')]
+        sbom: Vec<sbom::SbomFormat>,
+
+        /// Sign the archive with this Ed25519 private key, see `pytron sign`
+        /// Sealed and recipient archives are signed before they are encrypted
+        #[arg(long, value_name = "KEY", value_hint = clap::ValueHint::FilePath)]
+        sign: Option<String>,
+
+        /// Scan the packed files for secrets and sensitive files (.env, private keys, tokens)
+        /// Also configurable as zip.scan
+        #[arg(long, value_name = "MODE", value_enum, default_value = "warn")]
+        scan: scan::ScanMode,
+
+        /// Path (relative to DIRECTORY, globs allowed) the scanner must not flag (repeatable)
+        #[arg(long, value_name = "PATH")]
+        allow: Vec<String>,
+
+        /// Additional scan rules (TOML); defaults to DIRECTORY/.pytron-scan.toml if present
+        #[arg(long, value_name = "FILE", value_hint = clap::ValueHint::FilePath)]
+        scan_rules: Option<String>,
+
+        /// List the files that would be included and excluded, with totals, without writing the archive
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Name the pattern and its source that include or exclude PATH (relative to DIRECTORY)
+        /// Does not write the archive
+        #[arg(long, value_name = "PATH", value_hint = clap::ValueHint::AnyPath)]
+        explain: Option<String>,
     },
 
     #[command(
@@ -55,25 +170,48 @@ pub enum Commands {
         #[arg(
             default_value = "robot.zip",
             help = "Path to the zip file or script",
-            long_help = "Path to the zip file or script\nIf a zip file (.zip), will extract and run the specified script from it\nIf a Python file (.py), will run it directly using uv"
+            long_help = "Path to the zip file or script\nIf a zip file (.zip), will extract and run the specified script from it\nIf a Python file (.py), will run it directly using uv\nThe script inside the archive can be attached: robot.zip:tools/report.py"
         )]
         zipfile: String,
 
         
         #[arg(
             default_value = "main.py",
-            help = "Script to run from the zip (if zipfile is a zip archive)",
-            long_help = "Script to run from the zip (if zipfile is a zip archive)\nIf running a script directly, this is optional"
+            help = "Script or named command to run from the zip (if zipfile is a zip archive)",
+            long_help = "Script to run from the zip (if zipfile is a zip archive)\nIf the archive declares [tool.pytron.commands] in pyproject.toml, a command name\nruns that command with its default args, see --list\nIf running a script directly, this is optional"
         )]
         script: String,
 
+        /// Script inside the archive to run instead of main.py
+        /// Also accepted as robot.zip:tools/report.py
+        #[arg(long, value_name = "PATH", conflicts_with = "script")]
+        entry: Option<String>,
+
+        /// List the commands declared in [tool.pytron.commands] of the archive's pyproject.toml
+        #[arg(long)]
+        list: bool,
+
         /// Additional AES decryption password
         #[arg(
             short,
             long,
             help="AES Decryption password to decrypt the given ZIP file",
-            long_help="AES Decryption password to decrypt the given ZIP file\nThis depends on if the file has been encrypted before\n Example: \n --password hello-world")]
+            long_help="AES Decryption password to decrypt the given ZIP file\nThis depends on if the file has been encrypted before\nThe password is visible in process lists, prefer --password-file,\n--password-env, --ask-password or the PYTRON_PASSWORD variable\n Example: \n --password hello-world",
+            group = "password_source")]
         password: Option<String>,
+
+        /// Read the decryption password from the first line of this file
+        #[arg(long, value_name = "FILE", value_hint = clap::ValueHint::FilePath, group = "password_source")]
+        password_file: Option<String>,
+
+        /// Read the decryption password from this environment variable
+        /// Without any password option, PYTRON_PASSWORD is used if set
+        #[arg(long, value_name = "VAR", group = "password_source")]
+        password_env: Option<String>,
+
+        /// Prompt for the decryption password without echoing it
+        #[arg(long, group = "password_source")]
+        ask_password: bool,
         
         #[arg(
             value_name = "UV_ARGS",
@@ -94,220 +232,707 @@ pub enum Commands {
         )]
         script_args: Vec<String>,
 
+        /// Refuse to run archives that are not signed by a trusted key
+        #[arg(
+            long,
+            long_help = "Refuse to run archives the trust policy does not allow: unsigned or signed by\nan untrusted key, and not allowed by hash or name pattern.\nThis enforces the trust policy regardless of its configured mode"
+        )]
+        require_signature: bool,
+
+        /// Private key file for archives encrypted to recipients
+        /// Defaults to trying every key in PYTRON_HOME/keys
+        #[arg(long, value_name = "FILE", value_hint = clap::ValueHint::FilePath)]
+        identity: Option<String>,
+
+        /// Run the script in a sandbox (Linux): writes are limited to the extraction
+        /// directory, uv's directories and --allow-write paths, the rest of $HOME is hidden
+        #[arg(long)]
+        sandbox: bool,
+
+        /// Additional path the sandboxed script may write to (repeatable)
+        #[arg(long, value_name = "PATH", requires = "sandbox")]
+        allow_write: Vec<String>,
+
+        /// Run the sandboxed script without network access (uv runs with --offline)
+        #[arg(long, requires = "sandbox")]
+        no_network: bool,
+    },
+
+    /// Generate an Ed25519 key pair for signing archives, or an X25519 key pair for encryption
+    Keygen {
+        /// Path stem for the key files, <OUTPUT>.key (private) and <OUTPUT>.pub (public)
+        /// Defaults to pytron_signing, or pytron_encryption with --encryption
+        #[arg(short, long)]
+        output: Option<String>,
+
+        /// Generate an X25519 key pair for `pytron zip --recipient` instead of a signing key
+        #[arg(long)]
+        encryption: bool,
+
+        /// Overwrite existing key files
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Sign an archive with an Ed25519 private key
+    Sign {
+        /// Archive to sign
+        #[arg(default_value = "robot.zip")]
+        archive: String,
+
+        /// Private key file created by `pytron keygen`
+        #[arg(short, long)]
+        key: String,
+    },
+
+    /// Verify the signature of an archive
+    Verify {
+        /// Archive to verify
+        #[arg(default_value = "robot.zip")]
+        archive: String,
+
+        /// Verify against this public key instead of PYTRON_HOME/trusted_keys
+        #[arg(short, long, conflicts_with = "contents")]
+        key: Option<String>,
+
+        /// Check every file against the archive's SHA-256 content manifest instead
+        /// of the signature
+        #[arg(long)]
+        contents: bool,
+
+        /// Read the password of a sealed or encrypted archive from the first line of this file
+        #[arg(long, value_name = "FILE", value_hint = clap::ValueHint::FilePath, group = "password_source")]
+        password_file: Option<String>,
+
+        /// Read the password of a sealed or encrypted archive from this environment variable
+        #[arg(long, value_name = "VAR", group = "password_source")]
+        password_env: Option<String>,
+
+        /// Prompt for the password of a sealed or encrypted archive
+        #[arg(long, group = "password_source")]
+        ask_password: bool,
+
+        /// Private key file for archives encrypted to recipients
+        #[arg(long, value_name = "FILE", value_hint = clap::ValueHint::FilePath)]
+        identity: Option<String>,
+    },
+
+    /// Print a software bill of materials (CycloneDX or SPDX JSON) for an archive
+    Sbom {
+        /// Archive to describe
+        #[arg(default_value = "robot.zip")]
+        archive: String,
+
+        /// Document format
+        #[arg(short, long, value_enum, default_value = "cyclonedx")]
+        format: sbom::SbomFormat,
+
+        /// Write the document to this file instead of stdout
+        #[arg(short, long)]
+        output: Option<String>,
+
+        /// Read the password of an encrypted archive from the first line of this file
+        #[arg(long, value_name = "FILE", value_hint = clap::ValueHint::FilePath, group = "password_source")]
+        password_file: Option<String>,
+
+        /// Read the password of an encrypted archive from this environment variable
+        #[arg(long, value_name = "VAR", group = "password_source")]
+        password_env: Option<String>,
+
+        /// Prompt for the password of an encrypted archive
+        #[arg(long, group = "password_source")]
+        ask_password: bool,
+
+        /// Private key file for archives encrypted to recipients
+        #[arg(long, value_name = "FILE", value_hint = clap::ValueHint::FilePath)]
+        identity: Option<String>,
+    },
+
+    /// List the entries of an archive with sizes, encryption and permissions, its
+    /// entry script, project files and embedded metadata
+    Inspect {
+        /// Archive to inspect
+        #[arg(default_value = "robot.zip")]
+        archive: String,
+
+        /// Show the entries as a directory tree
+        #[arg(long, conflicts_with = "json")]
+        tree: bool,
+
+        /// Read the password of an encrypted archive from the first line of this file
+        #[arg(long, value_name = "FILE", value_hint = clap::ValueHint::FilePath, group = "password_source")]
+        password_file: Option<String>,
+
+        /// Read the password of an encrypted archive from this environment variable
+        #[arg(long, value_name = "VAR", group = "password_source")]
+        password_env: Option<String>,
+
+        /// Prompt for the password of an encrypted archive
+        #[arg(long, group = "password_source")]
+        ask_password: bool,
+
+        /// Private key file for archives encrypted to recipients
+        #[arg(long, value_name = "FILE", value_hint = clap::ValueHint::FilePath)]
+        identity: Option<String>,
+    },
+
+    /// Unpack an archive with the same password handling and path checks as `run`
+    Extract {
+        /// Archive to extract
+        #[arg(default_value = "robot.zip")]
+        archive: String,
+
+        /// Destination directory (default: the archive name without .zip)
+        dest: Option<String>,
+
+        /// Replace files that already exist in the destination
+        #[arg(long)]
+        overwrite: bool,
+
+        /// Only extract entries matching this glob, or below a matching directory (repeatable)
+        #[arg(long, value_name = "GLOB")]
+        only: Vec<String>,
+
+        /// Remove this many leading path components from entry names
+        #[arg(long, value_name = "N", default_value_t = 0)]
+        strip_components: usize,
+
+        /// Read the password of an encrypted archive from the first line of this file
+        #[arg(long, value_name = "FILE", value_hint = clap::ValueHint::FilePath, group = "password_source")]
+        password_file: Option<String>,
+
+        /// Read the password of an encrypted archive from this environment variable
+        #[arg(long, value_name = "VAR", group = "password_source")]
+        password_env: Option<String>,
+
+        /// Prompt for the password of an encrypted archive
+        #[arg(long, group = "password_source")]
+        ask_password: bool,
+
+        /// Private key file for archives encrypted to recipients
+        #[arg(long, value_name = "FILE", value_hint = clap::ValueHint::FilePath)]
+        identity: Option<String>,
+    },
+
+    /// Compare two archives: entries, source diffs, dependencies and metadata
+    Diff {
+        /// Archive to compare against
+        old: String,
+
+        /// Archive to compare
+        new: String,
+
+        /// Read the password of encrypted archives from the first line of this file
+        #[arg(long, value_name = "FILE", value_hint = clap::ValueHint::FilePath, group = "password_source")]
+        password_file: Option<String>,
+
+        /// Read the password of encrypted archives from this environment variable
+        #[arg(long, value_name = "VAR", group = "password_source")]
+        password_env: Option<String>,
+
+        /// Prompt for the password of each encrypted archive
+        #[arg(long, group = "password_source")]
+        ask_password: bool,
+
+        /// Private key file for archives encrypted to recipients
+        #[arg(long, value_name = "FILE", value_hint = clap::ValueHint::FilePath)]
+        identity: Option<String>,
+    },
+
+    /// Create a new project with pyproject.toml, main.py, .python-version and .gitignore
+    Init {
+        /// Directory to create the project in
+        #[arg(default_value = ".")]
+        dir: String,
+
+        /// Template to generate from, a builtin one (cli, service, data-pipeline) or
+        /// a directory in PYTRON_HOME/templates
+        #[arg(short, long, default_value = init::DEFAULT_TEMPLATE)]
+        template: String,
+
+        /// Project name (default: the directory name)
+        #[arg(long)]
+        name: Option<String>,
+
+        /// Python version for .python-version and requires-python
+        #[arg(long, value_name = "VERSION", default_value = init::DEFAULT_PYTHON_VERSION)]
+        python: String,
+
+        /// Replace files that already exist
+        #[arg(long)]
+        force: bool,
+
+        /// Print the available templates and exit
+        #[arg(long)]
+        list: bool,
+    },
+
+    /// Show the configuration from the system, PYTRON_HOME, project and environment layers
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommands,
+    },
+
+    /// Print a shell completion script, e.g. `source <(pytron completions bash)`
+    Completions {
+        /// Shell to complete for
+        #[arg(value_enum)]
+        shell: completions::CompletionShell,
+    },
+
+    /// Manage the uv installation in PYTRON_HOME
+    Uv {
+        #[command(subcommand)]
+        command: UvCommands,
+    },
+
+    /// Inspect and trim the uv cache, Python installs and temp data in PYTRON_HOME
+    Cache {
+        #[command(subcommand)]
+        command: CacheCommands,
+    },
+
+    /// Diagnose the local pytron environment (PYTRON_HOME, uv, disk, network)
+    Doctor,
+
+    /// Manage the trust policy deciding which archives `pytron run` accepts
+    Trust {
+        #[command(subcommand)]
+        command: TrustCommands,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ConfigCommands {
+    /// Print every effective setting and the file or variable it comes from
+    Show {
+        /// Project directory whose pytron.toml or pyproject.toml is read
+        #[arg(long, default_value = ".")]
+        dir: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum UvCommands {
+    /// Install uv into PYTRON_HOME, downloading it or from a local archive
+    Install {
+        /// Install from a local uv release archive (.zip or .tar.gz) instead of downloading
+        #[arg(long, value_name = "ARCHIVE")]
+        from: Option<String>,
+
+        /// Expected SHA-256 of the archive
+        /// Defaults to the contents of <ARCHIVE>.sha256 if that file exists
+        #[arg(long, requires = "from")]
+        sha256: Option<String>,
+
+        /// Install from the archive even if no checksum is available
+        #[arg(long, requires = "from")]
+        no_verify: bool,
+    },
+}
+
+/// What a `pytron trust add|remove` entry refers to; exactly one must be given
+#[derive(clap::Args, Debug, Clone)]
+#[group(required = true, multiple = false)]
+pub struct TrustTarget {
+    /// Public key file (`pytron trust remove` also accepts the key's name or id)
+    #[arg(long)]
+    pub key: Option<String>,
+
+    /// SHA-256 digest of an archive file
+    #[arg(long)]
+    pub hash: Option<String>,
+
+    /// Archive file to trust by its current SHA-256 digest
+    #[arg(long)]
+    pub archive: Option<String>,
+
+    /// Glob matched against the archive file name, or its full path if it contains '/'
+    #[arg(long)]
+    pub pattern: Option<String>,
+}
+
+impl TrustTarget {
+    pub fn entry(&self) -> trust::TrustEntry {
+        if let Some(key) = &self.key {
+            trust::TrustEntry::Key(key.clone())
+        } else if let Some(hash) = &self.hash {
+            trust::TrustEntry::Hash(hash.clone())
+        } else if let Some(archive) = &self.archive {
+            trust::TrustEntry::Archive(PathBuf::from(archive))
+        } else {
+            trust::TrustEntry::Pattern(self.pattern.clone().unwrap_or_default())
+        }
+    }
+}
+
+#[derive(Subcommand)]
+pub enum TrustCommands {
+    /// Trust a publisher key, an archive hash or an archive name pattern
+    Add {
+        #[command(flatten)]
+        target: TrustTarget,
+
+        /// Name to store a key under (defaults to the key file name)
+        #[arg(long)]
+        name: Option<String>,
+
+        /// Edit the system-wide policy instead of the one in PYTRON_HOME
+        #[arg(long)]
+        system: bool,
+    },
+
+    /// Remove a key, hash or pattern from the policy
+    Remove {
+        #[command(flatten)]
+        target: TrustTarget,
+
+        /// Edit the system-wide policy instead of the one in PYTRON_HOME
+        #[arg(long)]
+        system: bool,
+    },
+
+    /// Show the system and user policies and the effective enforcement mode
+    List,
+
+    /// Set the enforcement mode: off, warn or enforce
+    Mode {
+        mode: trust::EnforcementMode,
+
+        /// Edit the system-wide policy instead of the one in PYTRON_HOME
+        #[arg(long)]
+        system: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum CacheCommands {
+    /// Show how much space pytron's uv cache, Python installs and temp data use
+    Info,
+
+    /// Remove unused uv cache entries and stale extraction directories
+    Prune {
+        /// Only remove leftover extraction directories older than this many hours
+        #[arg(long, default_value_t = 24)]
+        older_than_hours: u64,
+    },
+
+    /// Remove the uv cache and all temp data
+    Clear {
+        /// Also remove managed Python installs and uv tools
+        #[arg(long)]
+        all: bool,
     },
 }
 
+/// Settings for `zip_directory_with_options` beyond the ones `zip_directory` takes
+#[derive(Clone, Default)]
+pub struct ZipOptions {
+    /// Additional gitignore-style patterns, see `pytron zip --ignore-patterns`
+    pub ignore_patterns: Option<Vec<String>>,
+    /// AES password for the entries
+    pub password: Option<String>,
+    /// Files never added, whatever the ignore rules say (such as the plain secrets file)
+    pub exclude: Vec<PathBuf>,
+    /// Extra entries added as-is after the project files, without entry encryption
+    pub extra_entries: Vec<(String, Vec<u8>)>,
+    /// SBOM documents to embed, generated from the packed files
+    pub sbom: Vec<sbom::SbomFormat>,
+    /// Scanner checking the packed files for secrets
+    pub scanner: Option<scan::Scanner>,
+    /// Encryption of the archive as a whole
+    pub encryption: ArchiveEncryption,
+    /// Key the archive is signed with, before any encryption of the archive as a whole
+    pub signing_key: Option<ed25519_dalek::SigningKey>,
+}
+
+/// How the finished archive is encrypted as a whole. Encrypted archives are
+/// built in memory, so only the encrypted bytes are ever written to disk.
+#[derive(Clone, Default)]
+pub enum ArchiveEncryption {
+    #[default]
+    None,
+    /// Sealed with this password, see `pytron zip --seal`
+    Sealed(String),
+    /// Encrypted to these public keys, see `pytron zip --recipient`
+    Recipients(Vec<age::x25519::Recipient>),
+}
+
 pub fn zip_directory(
     directory: &str,
     output: &str,
     ignore_patterns: Option<&Vec<String>>,
     password: Option<&String>
 ) -> io::Result<()> {
+    let options = ZipOptions {
+        ignore_patterns: ignore_patterns.cloned(),
+        password: password.cloned(),
+        ..Default::default()
+    };
+    zip_directory_with_options(directory, output, &options).map(|_| ())
+}
+
+/// Like `zip_directory`, with the additional settings of `ZipOptions`.
+/// Returns the selection of project files that were packed.
+pub fn zip_directory_with_options(
+    directory: &str,
+    output: &str,
+    zip_options: &ZipOptions,
+) -> io::Result<selection::Selection> {
     let dir_path = Path::new(directory);
     let output_path = Path::new(output);
+    let selection = selection::select_files(
+        dir_path,
+        output_path,
+        zip_options.ignore_patterns.as_ref(),
+        &zip_options.exclude,
+    )?;
+    if selection.defaults_overridden {
+        status!("Overriding default excludes (no default patterns will be used)");
+    } else if zip_options.ignore_patterns.is_some() {
+        let patterns: Vec<&str> = selection.rules.iter().filter_map(|rule| rule.pattern.as_deref()).collect();
+        status!("Using ignore patterns: {:?}", patterns);
+    }
 
-    // Create the zip file
-    let file = File::create(output_path)?;
-    let mut zip = ZipWriter::new(file);
-
-    // Walk the directory using ignore, which respects .gitignore
-    let walker = WalkBuilder::new(dir_path)
-        .hidden(false) // Process hidden files too, but respect .gitignore
-        .git_ignore(true) // Use .gitignore rules
-        .build();
-
-    // Create .gitignore matcher
-    let gitignore_path = dir_path.join(".gitignore");
-    let mut explicit_ignores = Vec::new();
-
-    // Check if user provided ignore patterns
-    let default_ignores = vec![".git".to_string()];
-
-    match ignore_patterns {
-        // Empty string means override default excludes
-        Some(patterns) if patterns.len() == 1 && patterns[0].is_empty() => {
-            println!("Overriding default excludes (no default patterns will be used)");
-            // Use only gitignore content, no default excludes
-            if gitignore_path.exists() {
-                if let Ok(content) = std::fs::read_to_string(&gitignore_path) {
-                    for line in content.lines() {
-                        let line = line.trim();
-                        if !line.is_empty() && !line.starts_with('#') {
-                            explicit_ignores.push(line.to_string());
-                        }
-                    }
-                }
-            }
+    // Scan before the output is opened, so a refused build touches nothing
+    if let Some(scanner) = &zip_options.scanner {
+        let mut findings = Vec::new();
+        for included in &selection.included {
+            findings.extend(scanner.scan(&included.name, &fs::read(&included.path)?));
         }
-        // User provided custom patterns, use those plus defaults
-        Some(patterns) => {
-            if gitignore_path.exists() {
-                if let Ok(content) = std::fs::read_to_string(&gitignore_path) {
-                    let lines = content.lines();
-                    // Combine .gitignore content with default ignores and user-provided patterns
-                    let combined_lines = lines
-                        .chain(default_ignores.iter().map(|s| s.as_str()))
-                        .chain(patterns.iter().map(|s| s.as_str()));
-
-                    for line in combined_lines {
-                        let line = line.trim();
-                        if !line.is_empty() && !line.starts_with('#') {
-                            explicit_ignores.push(line.to_string());
-                        }
-                    }
-                }
-            } else {
-                // No .gitignore file, just use defaults and user patterns
-                let combined_patterns = default_ignores
-                    .iter()
-                    .chain(patterns.iter())
-                    .map(|s| s.to_string());
-
-                explicit_ignores.extend(combined_patterns);
+        scanner.report(&findings)?;
+    }
+
+    match &zip_options.encryption {
+        ArchiveEncryption::None => {
+            let mut zip = ZipWriter::new(File::create(output_path)?);
+            if let Err(err) = write_entries(&mut zip, dir_path, &selection, zip_options) {
+                // Do not leave a half-written archive behind
+                drop(zip);
+                fs::remove_file(output_path)?;
+                return Err(err);
             }
-            println!("Using ignore patterns: {:?}", explicit_ignores);
-        }
-        // No user patterns, use .gitignore plus default excludes
-        None => {
-            if gitignore_path.exists() {
-                if let Ok(content) = std::fs::read_to_string(&gitignore_path) {
-                    let lines = content.lines();
-                    let combined_lines = lines.chain(default_ignores.iter().map(|s| s.as_str()));
-                    for line in combined_lines {
-                        let line = line.trim();
-                        if !line.is_empty() && !line.starts_with('#') {
-                            explicit_ignores.push(line.to_string());
-                        }
-                    }
-                }
-            } else {
-                // No .gitignore file, just use default excludes
-                explicit_ignores.extend(default_ignores);
+            zip.finish()?;
+            if let Some(signing_key) = &zip_options.signing_key {
+                signing::sign_archive(output_path, signing_key)?;
             }
         }
+        ArchiveEncryption::Sealed(password) => {
+            let archive = zip_in_memory(dir_path, &selection, zip_options)?;
+            fs::write(output_path, sealed::seal(&archive, password)?)?;
+        }
+        ArchiveEncryption::Recipients(recipients) => {
+            let archive = zip_in_memory(dir_path, &selection, zip_options)?;
+            fs::write(output_path, recipients::encrypt(&archive, recipients)?)?;
+        }
+    }
+    status!("Archive created successfully: {}", output);
+
+    Ok(selection)
+}
+
+/// Build the archive in memory, for encrypting it before anything touches the disk
+fn zip_in_memory(
+    dir_path: &Path,
+    selection: &selection::Selection,
+    zip_options: &ZipOptions,
+) -> io::Result<Vec<u8>> {
+    let mut zip = ZipWriter::new(io::Cursor::new(Vec::new()));
+    write_entries(&mut zip, dir_path, selection, zip_options)?;
+    let archive = zip.finish()?.into_inner();
+    // The zip inside is signed, so the signature is checked once it is decrypted
+    match &zip_options.signing_key {
+        Some(signing_key) => signing::sign_archive_bytes(archive, signing_key),
+        None => Ok(archive),
     }
+}
 
+/// Write the selected project files, SBOMs, extra entries and the manifest to `zip`
+fn write_entries<W: Write + io::Seek>(
+    zip: &mut ZipWriter<W>,
+    dir_path: &Path,
+    selection: &selection::Selection,
+    zip_options: &ZipOptions,
+) -> io::Result<()> {
+    let password = zip_options.password.as_ref();
     let mut options = SimpleFileOptions::default()
         .compression_method(zip::CompressionMethod::Stored);
 
     if let Some(pwd) = password {
             options = options.with_aes_encryption(zip::AesMode::Aes256, pwd);
     }
-    for result in walker {
-        match result {
-            Ok(entry) => {
-                let path = entry.path();
-
-                // Skip the output zip file itself
-                if path.canonicalize().ok() == output_path.canonicalize().ok() {
-                    continue;
-                }
-
-                // Skip files that match explicit .gitignore patterns
-                let rel_path = path
-                    .strip_prefix(dir_path)
-                    .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
-                let should_ignore = explicit_ignores.iter().any(|pattern| {
-                    // Get filename for extension matching
-                    let file_name = rel_path
-                        .file_name()
-                        .map(|f| f.to_string_lossy().to_string())
-                        .unwrap_or_default();
-
-                    // Get path string for full path matching and normalize to use forward slashes
-                    let rel_path_str = rel_path.to_string_lossy().replace('\\', "/");
-
-                    if pattern.starts_with("*.") {
-                        // Handle extension patterns like "*.log"
-                        let ext = &pattern[1..]; // Get ".log"
-                        file_name.ends_with(ext)
-                    } else if pattern.ends_with("*")
-                        && pattern.starts_with("*")
-                        && pattern.len() > 2
-                    {
-                        // Handle middle patterns like "*custom_ignore*"
-                        let middle = &pattern[1..pattern.len() - 1];
-                        rel_path_str.contains(middle)
-                    } else if pattern.ends_with("*") {
-                        // Handle prefix patterns like "prefix*"
-                        let prefix = &pattern[..pattern.len() - 1];
-                        rel_path_str.starts_with(prefix)
-                    } else if let Some(stripped) = pattern.strip_prefix("*") {
-                        // Handle suffix patterns like "*suffix"
-                        rel_path_str.ends_with(stripped)
-                    } else {
-                        // Exact match
-                        &*rel_path_str == pattern
-                    }
-                });
-
-                if should_ignore {
-                    println!("Ignoring: {}", rel_path.display());
-                    continue;
-                }
-
-                if path.is_file() {
-                    // Print progress
-                    println!("Adding: {}", rel_path.display());
+    for excluded in &selection.excluded {
+        if excluded.rule.source != selection::RuleSource::Output {
+            status!("Ignoring: {}", excluded.name);
+        }
+    }
+    // Digests of every packed file, checked again after extraction
+    let mut manifest = manifest::Manifest::default();
+    for included in &selection.included {
+        // Print progress
+        status!("Adding: {}", included.name);
+        zip.start_file(&included.name, options)?;
 
-                    // Convert path to use forward slashes for cross-platform compatibility
-                    let zip_path = rel_path.to_string_lossy().replace('\\', "/");
-                    zip.start_file(&zip_path, options)?;
+        // Write file contents
+        let mut file = File::open(&included.path)?;
+        let mut buffer = Vec::new();
+        file.read_to_end(&mut buffer)?;
+        zip.write_all(&buffer)?;
+        manifest.add(&included.name, &buffer);
+    }
 
-                    // Write file contents
-                    let mut file = File::open(path)?;
-                    let mut buffer = Vec::new();
-                    file.read_to_end(&mut buffer)?;
-                    zip.write_all(&buffer)?;
-                }
-            }
-            Err(err) => return Err(io::Error::new(io::ErrorKind::Other, err)),
+    if !zip_options.sbom.is_empty() {
+        let input = sbom::collect_from_directory(dir_path, &manifest)?;
+        for format in &zip_options.sbom {
+            let document = sbom::render(&input, *format);
+            status!("Adding SBOM: {}", format.entry_name());
+            zip.start_file(format.entry_name(), options)?;
+            zip.write_all(document.as_bytes())?;
+            manifest.add(format.entry_name(), document.as_bytes());
         }
     }
 
-    // Finalize the zip
-    zip.finish()?;
-    println!("Archive created successfully: {}", output);
+    for (name, data) in &zip_options.extra_entries {
+        zip.start_file(name, SimpleFileOptions::default())?;
+        zip.write_all(data)?;
+        manifest.add(name, data);
+    }
 
+    zip.start_file(manifest::MANIFEST_ENTRY, options)?;
+    zip.write_all(manifest.to_text().as_bytes())?;
     Ok(())
 }
 
 /// The current uv version to download
 pub const UV_VERSION: &str = "0.7.2";
 
-/// Get the download URL for the current platform
-pub fn get_uv_download_url() -> Option<String> {
-    let base_url = format!("https://github.com/astral-sh/uv/releases/download/{}", UV_VERSION);
-    
-    if cfg!(target_os = "windows") {
-        if cfg!(target_arch = "x86_64") {
-            Some(format!("{}/uv-x86_64-pc-windows-msvc.zip", base_url))
-        } else if cfg!(target_arch = "aarch64") {
-            Some(format!("{}/uv-aarch64-pc-windows-msvc.zip", base_url))
-        } else {
-            None
+/// C library flavour of a Linux host, used to pick a matching uv build
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Libc {
+    Gnu,
+    Musl,
+}
+
+impl std::fmt::Display for Libc {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Libc::Gnu => write!(f, "glibc"),
+            Libc::Musl => write!(f, "musl"),
         }
-    } else if cfg!(target_os = "macos") {
-        if cfg!(target_arch = "x86_64") {
-            Some(format!("{}/uv-x86_64-apple-darwin.tar.gz", base_url))
-        } else if cfg!(target_arch = "aarch64") {
-            Some(format!("{}/uv-aarch64-apple-darwin.tar.gz", base_url))
-        } else {
-            None
+    }
+}
+
+/// Detect the C library of the running host.
+///
+/// This is done at runtime rather than with `cfg!(target_env)` because our own
+/// release binary is a static musl build that also runs on glibc hosts.
+/// Returns None on platforms other than Linux.
+pub fn detect_libc() -> Option<Libc> {
+    if !cfg!(target_os = "linux") {
+        return None;
+    }
+
+    // The musl dynamic loader lives at /lib/ld-musl-<arch>.so.1
+    if let Ok(entries) = fs::read_dir("/lib") {
+        let has_musl_loader = entries.filter_map(Result::ok).any(|entry| {
+            entry.file_name().to_string_lossy().starts_with("ld-musl-")
+        });
+        if has_musl_loader {
+            return Some(Libc::Musl);
         }
-    } else if cfg!(target_os = "linux") {
-        if cfg!(target_arch = "x86_64") {
-            Some(format!("{}/uv-x86_64-unknown-linux-gnu.tar.gz", base_url))
-        } else if cfg!(target_arch = "aarch64") {
-            Some(format!("{}/uv-aarch64-unknown-linux-gnu.tar.gz", base_url))
-        } else {
-            None
+    }
+
+    // Ask ldd, which identifies itself differently on glibc and musl
+    // (musl's ldd prints its banner to stderr and exits non-zero)
+    if let Ok(output) = Command::new("ldd").arg("--version").output() {
+        let text = format!(
+            "{}{}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        )
+        .to_lowercase();
+        if text.contains("musl") {
+            return Some(Libc::Musl);
+        }
+        if text.contains("glibc") || text.contains("gnu libc") {
+            return Some(Libc::Gnu);
         }
+    }
+
+    // Fall back to the environment pytron itself was built for
+    if cfg!(target_env = "musl") {
+        Some(Libc::Musl)
     } else {
-        None
+        Some(Libc::Gnu)
+    }
+}
+
+/// Get the architecture name of the current host as used by uv's release artifacts
+pub fn host_arch() -> &'static str {
+    match env::consts::ARCH {
+        "powerpc64" if cfg!(target_endian = "little") => "powerpc64le",
+        arch => arch,
     }
 }
 
+/// Get the uv release target triple for a platform.
+///
+/// `os` and `arch` follow `std::env::consts` naming (with "powerpc64le" for
+/// little-endian POWER, see `host_arch`). On error, the returned message explains
+/// why no artifact matches.
+pub fn uv_target_triple(os: &str, arch: &str, libc: Option<Libc>) -> Result<&'static str, String> {
+    let triple = match (os, arch, libc) {
+        ("windows", "x86_64", _) => Some("x86_64-pc-windows-msvc"),
+        ("windows", "aarch64", _) => Some("aarch64-pc-windows-msvc"),
+        ("windows", "x86", _) => Some("i686-pc-windows-msvc"),
+        ("macos", "x86_64", _) => Some("x86_64-apple-darwin"),
+        ("macos", "aarch64", _) => Some("aarch64-apple-darwin"),
+        ("linux", "x86_64", Some(Libc::Gnu)) => Some("x86_64-unknown-linux-gnu"),
+        ("linux", "x86_64", Some(Libc::Musl)) => Some("x86_64-unknown-linux-musl"),
+        ("linux", "aarch64", Some(Libc::Gnu)) => Some("aarch64-unknown-linux-gnu"),
+        ("linux", "aarch64", Some(Libc::Musl)) => Some("aarch64-unknown-linux-musl"),
+        ("linux", "arm", Some(Libc::Gnu)) => Some("armv7-unknown-linux-gnueabihf"),
+        ("linux", "arm", Some(Libc::Musl)) => Some("armv7-unknown-linux-musleabihf"),
+        ("linux", "x86", Some(Libc::Gnu)) => Some("i686-unknown-linux-gnu"),
+        ("linux", "x86", Some(Libc::Musl)) => Some("i686-unknown-linux-musl"),
+        // uv only publishes glibc builds for these architectures
+        ("linux", "powerpc64le", Some(Libc::Gnu)) => Some("powerpc64le-unknown-linux-gnu"),
+        ("linux", "s390x", Some(Libc::Gnu)) => Some("s390x-unknown-linux-gnu"),
+        _ => None,
+    };
+
+    triple.ok_or_else(|| {
+        let platform = match libc {
+            Some(libc) => format!("{}/{} ({})", os, arch, libc),
+            None => format!("{}/{}", os, arch),
+        };
+        format!(
+            "No uv {} build is published for {}. Install uv manually (https://github.com/astral-sh/uv) and place the binary in {}",
+            UV_VERSION,
+            platform,
+            get_pytron_home().display()
+        )
+    })
+}
+
+/// Get the download URL for the current platform, explaining why when there is none
+pub fn resolve_uv_download_url() -> io::Result<String> {
+    let os = env::consts::OS;
+    let triple = uv_target_triple(os, host_arch(), detect_libc())
+        .map_err(|msg| io::Error::new(io::ErrorKind::Unsupported, msg))?;
+    let extension = if os == "windows" { "zip" } else { "tar.gz" };
+
+    Ok(format!(
+        "https://github.com/astral-sh/uv/releases/download/{}/uv-{}.{}",
+        UV_VERSION, triple, extension
+    ))
+}
+
+/// Get the download URL for the current platform
+pub fn get_uv_download_url() -> Option<String> {
+    resolve_uv_download_url().ok()
+}
+
 /// Checks if uv is installed in PYTRON_HOME
 pub fn is_uv_installed() -> bool {
     // Only check if it exists in PYTRON_HOME
@@ -326,6 +951,60 @@ pub fn get_pytron_home() -> PathBuf {
     home.join("pytron_home")
 }
 
+/// Environment variable pointing the system configuration directory elsewhere, for tests
+pub const SYSTEM_CONFIG_DIR_ENV: &str = "PYTRON_SYSTEM_CONFIG_DIR";
+
+/// Directory of machine-wide pytron configuration managed by administrators.
+///
+/// Debug builds, which the tests run, read it from PYTRON_SYSTEM_CONFIG_DIR if
+/// set. Release builds ignore the variable, so whoever runs pytron cannot
+/// replace the system trust policy with their own.
+pub fn get_system_config_dir() -> PathBuf {
+    if cfg!(debug_assertions) {
+        if let Some(dir) = env::var_os(SYSTEM_CONFIG_DIR_ENV) {
+            return PathBuf::from(dir);
+        }
+    }
+
+    #[cfg(windows)]
+    {
+        let program_data =
+            env::var("ProgramData").unwrap_or_else(|_| String::from(r"C:\ProgramData"));
+        PathBuf::from(program_data).join("pytron")
+    }
+    #[cfg(not(windows))]
+    {
+        PathBuf::from("/etc/pytron")
+    }
+}
+
+/// Total size in bytes of all files below `path` (0 if it does not exist)
+pub fn dir_size(path: &Path) -> u64 {
+    walkdir::WalkDir::new(path)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter_map(|entry| entry.metadata().ok())
+        .filter(|metadata| metadata.is_file())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+/// Format a byte count for display, e.g. "1.5 MiB"
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
 /// Get the path to the uv executable
 pub fn get_uv_path() -> PathBuf {
     let pytron_home = get_pytron_home();
@@ -350,10 +1029,230 @@ pub fn get_uv_path() -> PathBuf {
     }
 }
 
+/// Directories uv keeps state in. pytron points each of them into PYTRON_HOME
+/// so runs do not share state with a developer's own uv usage; setting the
+/// environment variable yourself overrides this.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UvStateDir {
+    /// Downloaded and built packages
+    Cache,
+    /// Managed Python interpreters
+    PythonInstalls,
+    /// Tools installed with `uv tool install`
+    Tools,
+}
+
+impl UvStateDir {
+    pub const ALL: [UvStateDir; 3] = [
+        UvStateDir::Cache,
+        UvStateDir::PythonInstalls,
+        UvStateDir::Tools,
+    ];
+
+    /// Environment variable uv reads the directory from
+    pub fn env_var(self) -> &'static str {
+        match self {
+            UvStateDir::Cache => "UV_CACHE_DIR",
+            UvStateDir::PythonInstalls => "UV_PYTHON_INSTALL_DIR",
+            UvStateDir::Tools => "UV_TOOL_DIR",
+        }
+    }
+
+    /// Human-readable name, as shown by `pytron cache info`
+    pub fn label(self) -> &'static str {
+        match self {
+            UvStateDir::Cache => "uv cache",
+            UvStateDir::PythonInstalls => "python installs",
+            UvStateDir::Tools => "uv tools",
+        }
+    }
+
+    /// Location inside PYTRON_HOME used unless the variable is set
+    fn default_path(self) -> PathBuf {
+        let pytron_home = get_pytron_home();
+        match self {
+            UvStateDir::Cache => pytron_home.join("cache").join("uv"),
+            UvStateDir::PythonInstalls => pytron_home.join("python"),
+            UvStateDir::Tools => pytron_home.join("tools"),
+        }
+    }
+
+    /// The directory uv uses
+    pub fn path(self) -> PathBuf {
+        match env::var_os(self.env_var()) {
+            Some(path) if !path.is_empty() => PathBuf::from(path),
+            _ => self.default_path(),
+        }
+    }
+}
+
+/// Get the uv cache directory used by pytron
+pub fn get_uv_cache_dir() -> PathBuf {
+    UvStateDir::Cache.path()
+}
+
 /// Creates a command for uv, always using the version in PYTRON_HOME
 pub fn get_uv_command() -> Command {
     // Always use our own copy from PYTRON_HOME
-    Command::new(get_uv_path())
+    let mut cmd = Command::new(get_uv_path());
+
+    // Keep uv's cache, interpreters and tools inside PYTRON_HOME as well
+    for dir in UvStateDir::ALL {
+        cmd.env(dir.env_var(), dir.path());
+    }
+
+    cmd
+}
+
+/// Environment variable pointing at a pre-staged uv release archive
+pub const UV_ARCHIVE_ENV: &str = "PYTRON_UV_ARCHIVE";
+
+/// Environment variable holding the expected SHA-256 of `PYTRON_UV_ARCHIVE`
+pub const UV_ARCHIVE_SHA256_ENV: &str = "PYTRON_UV_ARCHIVE_SHA256";
+
+/// Get the path uv is installed to inside PYTRON_HOME
+fn uv_install_target() -> PathBuf {
+    let pytron_home = get_pytron_home();
+    if cfg!(windows) {
+        pytron_home.join("uv.exe")
+    } else {
+        pytron_home.join("uv")
+    }
+}
+
+/// Make sure uv is available in PYTRON_HOME, installing it if necessary.
+///
+/// If `PYTRON_UV_ARCHIVE` points at a local uv release archive, it is installed
+/// from there instead of downloading, so air-gapped hosts can pre-stage uv.
+pub fn ensure_uv() -> io::Result<PathBuf> {
+    if is_uv_installed() {
+        return Ok(get_uv_path());
+    }
+
+    let result = match env::var_os(UV_ARCHIVE_ENV) {
+        Some(archive) => {
+            let archive = PathBuf::from(archive);
+            status!("uv not found. Installing from local archive: {}", archive.display());
+            let expected = env::var(UV_ARCHIVE_SHA256_ENV).ok();
+            install_uv_from_archive(&archive, expected.as_deref(), false)
+        }
+        None => {
+            status!("uv not found. Attempting to download...");
+            download_uv()
+        }
+    };
+
+    match result {
+        Ok(path) => {
+            status!("Installed uv to: {}", path.display());
+            Ok(path)
+        }
+        Err(err) => Err(io::Error::new(
+            err.kind(),
+            format!("Failed to install uv: {}. Please install uv manually (https://github.com/astral-sh/uv)", err),
+        )),
+    }
+}
+
+/// Compute the hex-encoded SHA-256 digest of a file
+pub fn sha256_file(path: &Path) -> io::Result<String> {
+    use sha2::{Digest, Sha256};
+
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher)?;
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Find the expected checksum for a uv archive.
+///
+/// An explicit value wins; otherwise a `<archive>.sha256` file next to the archive
+/// is used, which is the format uv publishes alongside its release artifacts.
+fn expected_uv_checksum(archive_path: &Path, explicit: Option<&str>) -> io::Result<Option<String>> {
+    if let Some(sum) = explicit {
+        return Ok(Some(sum.trim().to_lowercase()));
+    }
+
+    let mut sidecar = archive_path.as_os_str().to_owned();
+    sidecar.push(".sha256");
+    let sidecar = PathBuf::from(sidecar);
+    if !sidecar.exists() {
+        return Ok(None);
+    }
+
+    // Format: "<hex digest> *<file name>" or just the digest
+    let content = fs::read_to_string(&sidecar)?;
+    let sum = content.split_whitespace().next().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Checksum file {} is empty", sidecar.display()),
+        )
+    })?;
+    Ok(Some(sum.to_lowercase()))
+}
+
+/// Install uv into PYTRON_HOME from a local release archive (.zip or .tar.gz).
+///
+/// The archive is verified against `expected_sha256` or a `<archive>.sha256`
+/// file next to it. Installing without a checksum requires `skip_verify`.
+/// An existing uv installation in PYTRON_HOME is replaced.
+pub fn install_uv_from_archive(
+    archive_path: &Path,
+    expected_sha256: Option<&str>,
+    skip_verify: bool,
+) -> io::Result<PathBuf> {
+    if !archive_path.is_file() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("uv archive {} not found", archive_path.display()),
+        ));
+    }
+
+    match expected_uv_checksum(archive_path, expected_sha256)? {
+        Some(expected) => {
+            let actual = sha256_file(archive_path)?;
+            if actual != expected {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "Checksum mismatch for {}: expected {}, got {}",
+                        archive_path.display(),
+                        expected,
+                        actual
+                    ),
+                ));
+            }
+            status!("Checksum verified: {}", actual);
+        }
+        None if skip_verify => {
+            status!("Warning: installing {} without checksum verification", archive_path.display());
+        }
+        None => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "No checksum for {}. Pass --sha256, place {}.sha256 next to it or set {}",
+                    archive_path.display(),
+                    archive_path.display(),
+                    UV_ARCHIVE_SHA256_ENV
+                ),
+            ));
+        }
+    }
+
+    let pytron_home = get_pytron_home();
+    fs::create_dir_all(&pytron_home)?;
+
+    let temp_dir = tempfile::Builder::new()
+        .prefix("pytron_install_")
+        .tempdir_in(&pytron_home)?;
+
+    // Detect the format from the content rather than the file name
+    let mut magic = [0u8; 2];
+    File::open(archive_path)?.read_exact(&mut magic)?;
+    let is_zip = &magic == b"PK";
+
+    extract_uv_binary(archive_path, is_zip, temp_dir.path(), &uv_install_target())
 }
 
 /// Download and install uv
@@ -364,11 +1263,7 @@ pub fn download_uv() -> io::Result<PathBuf> {
     fs::create_dir_all(&pytron_home)?;
     
     // Determine the target path
-    let target_path = if cfg!(windows) {
-        pytron_home.join("uv.exe")
-    } else {
-        pytron_home.join("uv")
-    };
+    let target_path = uv_install_target();
     
     // If uv is already downloaded, just return the path
     if target_path.exists() {
@@ -376,14 +1271,9 @@ pub fn download_uv() -> io::Result<PathBuf> {
     }
     
     // Get download URL for current platform
-    let download_url = get_uv_download_url().ok_or_else(|| {
-        io::Error::new(
-            io::ErrorKind::Unsupported,
-            "Unsupported platform for uv download",
-        )
-    })?;
+    let download_url = resolve_uv_download_url()?;
     
-    println!("Downloading uv {} from: {}", UV_VERSION, download_url);
+    status!("Downloading uv {} from: {}", UV_VERSION, download_url);
     
     // Create a temporary file for the download
     let temp_dir = tempfile::Builder::new()
@@ -399,40 +1289,57 @@ pub fn download_uv() -> io::Result<PathBuf> {
     // Download the file
     let client = Client::new();
     let response = client.get(&download_url).send().map_err(|e| {
-        io::Error::new(io::ErrorKind::Other, format!("Failed to download uv: {}", e))
+        io::Error::other(format!("Failed to download uv: {}", e))
     })?;
     
     if !response.status().is_success() {
-        return Err(io::Error::new(
-            io::ErrorKind::Other,
-            format!("Failed to download uv: HTTP {}", response.status()),
-        ));
+        return Err(io::Error::other(format!(
+            "Failed to download uv: HTTP {}",
+            response.status()
+        )));
     }
     
     // Save the file
     let content = response.bytes().map_err(|e| {
-        io::Error::new(io::ErrorKind::Other, format!("Failed to read response body: {}", e))
+        io::Error::other(format!("Failed to read response body: {}", e))
     })?;
     
     let mut file = File::create(&archive_path)?;
     file.write_all(&content)?;
     
-    // Extract the binary
-    if download_url.ends_with(".zip") {
+    extract_uv_binary(
+        &archive_path,
+        download_url.ends_with(".zip"),
+        temp_dir.path(),
+        &target_path,
+    )
+}
+
+/// Extract the uv binary from a release archive into `target_path`.
+/// `work_dir` is a scratch directory on the same filesystem as the target.
+fn extract_uv_binary(
+    archive_path: &Path,
+    is_zip: bool,
+    work_dir: &Path,
+    target_path: &Path,
+) -> io::Result<PathBuf> {
+    let binary_name = if cfg!(windows) { "uv.exe" } else { "uv" };
+
+    if is_zip {
         // Extract zip file
-        let file = File::open(&archive_path)?;
+        let file = File::open(archive_path)?;
         let mut archive = ZipArchive::new(file)?;
         
-        // Find the uv binary in the archive
-        let binary_path = if cfg!(windows) { "uv.exe" } else { "uv" };
-        
         // Try to extract uv binary
         for i in 0..archive.len() {
             let mut file = archive.by_index(i)?;
-            let outpath = temp_dir.path().join(file.name());
+            let is_binary = Path::new(file.name())
+                .file_name()
+                .is_some_and(|name| name == binary_name);
             
-            if file.name().ends_with(binary_path) {
+            if is_binary {
                 // Found the binary, extract it
+                let outpath = work_dir.join(binary_name);
                 let mut outfile = File::create(&outpath)?;
                 io::copy(&mut file, &mut outfile)?;
                 
@@ -446,33 +1353,31 @@ pub fn download_uv() -> io::Result<PathBuf> {
                 }
                 
                 // Move to final location
-                fs::rename(&outpath, &target_path)?;
+                fs::rename(&outpath, target_path)?;
                 
-                return Ok(target_path);
+                return Ok(target_path.to_path_buf());
             }
         }
         
         Err(io::Error::new(
             io::ErrorKind::NotFound,
-            "Could not find uv binary in downloaded archive",
+            "Could not find uv binary in archive",
         ))
     } else {
         // Extract tar.gz file
-        let file = File::open(&archive_path)?;
+        let file = File::open(archive_path)?;
         let decompressed = flate2::read::GzDecoder::new(file);
         let mut archive = tar::Archive::new(decompressed);
         
-        // Extract to temp directory
-        archive.unpack(temp_dir.path())?;
-        
-        // Find the uv binary in the extracted files
-        let binary_name = if cfg!(windows) { "uv.exe" } else { "uv" };
+        // Extract to a separate directory so the archive itself is never matched
+        let unpack_dir = work_dir.join("unpacked");
+        archive.unpack(&unpack_dir)?;
         
         // Search for the binary in extracted files
-        let binary_path = walkdir::WalkDir::new(temp_dir.path())
+        let binary_path = walkdir::WalkDir::new(&unpack_dir)
             .into_iter()
             .filter_map(Result::ok)
-            .find(|entry| entry.file_name() == binary_name);
+            .find(|entry| entry.file_type().is_file() && entry.file_name() == binary_name);
         
         if let Some(binary_path) = binary_path {
             // Make it executable on Unix
@@ -485,19 +1390,18 @@ pub fn download_uv() -> io::Result<PathBuf> {
             }
             
             // Move to final location
-            fs::rename(binary_path.path(), &target_path)?;
+            fs::rename(binary_path.path(), target_path)?;
             
-            Ok(target_path)
+            Ok(target_path.to_path_buf())
         } else {
             Err(io::Error::new(
                 io::ErrorKind::NotFound,
-                "Could not find uv binary in downloaded archive",
+                "Could not find uv binary in archive",
             ))
         }
     }
 }
 
-
 /// Checks if Windows long path support is enabled and enables it if needed.
 /// Returns true if long path support is enabled after the function call,
 /// false if it couldn't be enabled (and would require a system reboot).
@@ -517,12 +1421,12 @@ pub fn check_and_enable_long_path_support() -> io::Result<bool> {
     
     if long_paths_enabled == 1 {
         // Long path support is already enabled
-        println!("Windows long path support is enabled.");
+        status!("Windows long path support is enabled.");
         return Ok(true);
     }
     
     // Long path support is not enabled, try to enable it
-    println!("Windows long path support is not enabled. Attempting to enable it...");
+    status!("Windows long path support is not enabled. Attempting to enable it...");
     
     // Try to open the key with write permissions
     match hklm.open_subkey_with_flags(
@@ -533,25 +1437,25 @@ pub fn check_and_enable_long_path_support() -> io::Result<bool> {
             // Set LongPathsEnabled to 1
             match filesystem_key_write.set_value("LongPathsEnabled", &1u32) {
                 Ok(_) => {
-                    println!("Successfully enabled Windows long path support.");
-                    println!("NOTE: You may need to reboot your system for the change to take effect.");
+                    status!("Successfully enabled Windows long path support.");
+                    status!("NOTE: You may need to reboot your system for the change to take effect.");
                     // Return false because a reboot is needed
                     Ok(false)
                 }
                 Err(e) => {
-                    println!("Failed to enable Windows long path support: {}", e);
-                    println!("To enable it manually, run PowerShell as Administrator and execute:");
-                    println!("Set-ItemProperty -Path \"HKLM:\\SYSTEM\\CurrentControlSet\\Control\\FileSystem\" -Name \"LongPathsEnabled\" -Value 1");
-                    println!("Then reboot your system.");
+                    status!("Failed to enable Windows long path support: {}", e);
+                    status!("To enable it manually, run PowerShell as Administrator and execute:");
+                    status!("Set-ItemProperty -Path \"HKLM:\\SYSTEM\\CurrentControlSet\\Control\\FileSystem\" -Name \"LongPathsEnabled\" -Value 1");
+                    status!("Then reboot your system.");
                     Err(io::Error::new(io::ErrorKind::PermissionDenied, e))
                 }
             }
         }
         Err(e) => {
-            println!("Failed to access registry with write permissions: {}", e);
-            println!("To enable long path support manually, run PowerShell as Administrator and execute:");
-            println!("Set-ItemProperty -Path \"HKLM:\\SYSTEM\\CurrentControlSet\\Control\\FileSystem\" -Name \"LongPathsEnabled\" -Value 1");
-            println!("Then reboot your system.");
+            status!("Failed to access registry with write permissions: {}", e);
+            status!("To enable long path support manually, run PowerShell as Administrator and execute:");
+            status!("Set-ItemProperty -Path \"HKLM:\\SYSTEM\\CurrentControlSet\\Control\\FileSystem\" -Name \"LongPathsEnabled\" -Value 1");
+            status!("Then reboot your system.");
             Err(io::Error::new(io::ErrorKind::PermissionDenied, e))
         }
     }
@@ -563,6 +1467,35 @@ pub fn check_and_enable_long_path_support() -> io::Result<bool> {
     Ok(true)
 }
 
+/// Options controlling how `run_from_zip_with_options` treats an archive
+#[derive(Clone, Default)]
+pub struct RunOptions {
+    /// AES password for encrypted archives
+    pub password: Option<String>,
+    /// Refuse archives that are unsigned or not signed by a key in PYTRON_HOME/trusted_keys
+    pub require_signature: bool,
+    /// Private key files for recipient-encrypted archives (default: PYTRON_HOME/keys)
+    pub identities: Vec<PathBuf>,
+    /// Run the script in a sandbox (Linux only)
+    pub sandbox: Option<sandbox::SandboxOptions>,
+    /// Run a command from `[tool.pytron.commands]` instead of the script when
+    /// the first script argument names one
+    pub named_commands: bool,
+}
+
+// Keep the password out of debug output and logs
+impl std::fmt::Debug for RunOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RunOptions")
+            .field("password", &self.password.as_ref().map(|_| "<redacted>"))
+            .field("require_signature", &self.require_signature)
+            .field("identities", &self.identities)
+            .field("sandbox", &self.sandbox)
+            .field("named_commands", &self.named_commands)
+            .finish()
+    }
+}
+
 pub fn run_from_zip(
     zipfile: &str,
     password: Option<&String>,
@@ -570,6 +1503,35 @@ pub fn run_from_zip(
     uv_args: &[String],
     script_args: &[String],
 ) -> io::Result<i32> {
+    let options = RunOptions {
+        password: password.cloned(),
+        ..Default::default()
+    };
+    run_from_zip_with_options(zipfile, script_path, uv_args, script_args, &options)
+        .map(|outcome| outcome.exit_code)
+}
+
+/// What `run_from_zip_with_options` ran
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunOutcome {
+    /// Script inside the archive, or the `module:function` of a named command
+    pub entry: String,
+    /// The named command that selected the entry, if any
+    pub command: Option<String>,
+    /// Exit code of the script
+    pub exit_code: i32,
+}
+
+/// Like `run_from_zip`, with additional checks configured through `RunOptions`
+pub fn run_from_zip_with_options(
+    zipfile: &str,
+    script_path: &str,
+    uv_args: &[String],
+    script_args: &[String],
+    options: &RunOptions,
+) -> io::Result<RunOutcome> {
+    let password = options.password.as_ref();
+
     // On Windows, check for long path support
     #[cfg(windows)]
     {
@@ -579,12 +1541,12 @@ pub fn run_from_zip(
             }
             Ok(false) => {
                 // Long path support was enabled, but requires reboot
-                println!("Long path support has been enabled, but you need to reboot for it to take effect.");
-                println!("After rebooting, run this command again.");
+                status!("Long path support has been enabled, but you need to reboot for it to take effect.");
+                status!("After rebooting, run this command again.");
             }
             Err(e) => {
-                println!("Warning: Could not check or enable long path support: {}", e);
-                println!("You may encounter issues with long file paths.");
+                status!("Warning: Could not check or enable long path support: {}", e);
+                status!("You may encounter issues with long file paths.");
             }
         }
     }
@@ -601,97 +1563,146 @@ pub fn run_from_zip(
     let temp_dir = tempfile::Builder::new()
         .prefix("pytron_")
         .tempdir_in(temp_path)?;
+    // Keep `pytron cache prune` away from the directory while the script runs
+    let _in_use = cache::mark_in_use(temp_dir.path())?;
 
-    println!("Extracting {} to temporary directory: {}", zipfile, temp_dir.path().display());
+    status!("Extracting {} to temporary directory: {}", zipfile, temp_dir.path().display());
 
     // Open the zip file
-    let file = File::open(zipfile)?;
-    let mut archive = ZipArchive::new(file)?;
+    let mut archive = archive::open_archive_with_keys(
+        Path::new(zipfile),
+        password.map(String::as_str),
+        &options.identities,
+    )?;
 
-    
-    
-    // Extract all files
-    for i in 0..archive.len() {
-        let mut file = if let Some(pwd) = password {
-            archive.by_index_decrypt(i, pwd.as_bytes())?
-        } else {
-            archive.by_index(i)?
-        };
-        // Normalize file path for cross-platform compatibility
-        let normalized_name = file
-            .name()
-            .replace('/', std::path::MAIN_SEPARATOR_STR);
-        let outpath = temp_dir.path().join(normalized_name);
-
-        if file.is_dir() {
-            std::fs::create_dir_all(&outpath)?;
-        } else {
-            // Ensure parent directory exists
-            if let Some(parent) = outpath.parent() {
-                if !parent.exists() {
-                    std::fs::create_dir_all(parent)?;
-                }
-            }
+    trust::enforce_policy(Path::new(zipfile), &mut archive, options.require_signature)?;
 
-            let mut outfile = File::create(&outpath)?;
-            io::copy(&mut file, &mut outfile)?;
+    // Fail early with a precise error if the password is missing or wrong
+    let encrypted = archive::check_password(&mut archive, password.map(String::as_str))?;
+    if !encrypted.is_empty() {
+        status!(
+            "Archive has {} encrypted of {} entries",
+            encrypted.len(),
+            archive.len()
+        );
+    }
 
-            // Set executable permissions on Unix
-            #[cfg(unix)]
-            {
-                use std::os::unix::fs::PermissionsExt;
-                if file.name().ends_with(".py") || !file.name().contains('.') {
-                    let metadata = outpath.metadata()?;
-                    let mut perms = metadata.permissions();
-                    perms.set_mode(0o755);
-                    std::fs::set_permissions(&outpath, perms)?;
-                }
+    // Extract all files, secrets stay in memory and are never written to the extraction directory
+    let extracted = extract::extract_entries(
+        &mut archive,
+        temp_dir.path(),
+        password.map(String::as_str),
+        &extract::ExtractOptions::default(),
+    )?;
+    let secrets_data = extracted.secrets;
+
+    // Check the extracted files against the manifest written by zip_directory
+    match manifest::read_manifest(&mut archive, password.map(String::as_str))? {
+        Some(expected) => {
+            let mut actual = manifest::hash_directory(temp_dir.path())?;
+            if let Some(data) = &secrets_data {
+                actual.add(secrets::SECRETS_ENTRY, data);
+            }
+            let mismatches = manifest::compare(&expected, &actual);
+            if !mismatches.is_empty() {
+                return Err(manifest::mismatch_error(&mismatches));
             }
+            status!("Verified {} file(s) against the content manifest", expected.files.len());
         }
+        None => status!("Archive has no content manifest, skipping the content check"),
     }
 
-    // Construct the full path to the script
-    let script_full_path = temp_dir.path().join(script_path);
+    let secret_vars = match secrets_data {
+        Some(data) => secrets::decrypt_secrets(
+            &data,
+            password.map(String::as_str),
+            &options.identities,
+        )?,
+        None => Vec::new(),
+    };
 
-    if !script_full_path.exists() {
-        return Err(io::Error::new(
-            io::ErrorKind::NotFound,
-            format!("Script {} not found in archive", script_path),
-        ));
+    // A named command replaces the script and adds its default arguments
+    let mut target = commands::CommandTarget::Script(script_path.to_string());
+    let mut command_name = script_path.to_string();
+    let mut named_command = None;
+    let mut script_args = script_args.to_vec();
+    if options.named_commands && !script_args.is_empty() {
+        let declared = commands::load_commands(temp_dir.path())?;
+        if let Some(command) = declared.into_iter().find(|c| c.name == script_args[0]) {
+            status!("Running command {}: {}", command.name, command.target);
+            script_args.splice(0..1, command.args);
+            target = command.target;
+            named_command = Some(command.name.clone());
+            command_name = command.name;
+        }
     }
 
-    // Arguments are now passed separately, no need to separate them here
-
     // Prepare the command
     let mut cmd_args = vec!["run".to_string()];
 
+    // Without network uv must not try to resolve or download anything either
+    if options.sandbox.as_ref().is_some_and(|sandbox| sandbox.no_network) {
+        cmd_args.push("--offline".to_string());
+    }
+
     // Add uv flags/options
     cmd_args.extend_from_slice(uv_args);
 
-    // Add script path
-    cmd_args.push(script_full_path.to_string_lossy().to_string());
-
-    // Add script arguments
-    cmd_args.extend_from_slice(script_args);
-
-    println!("Running: uv {}", cmd_args.join(" "));
-
-    // Check if uv is installed or download it
-    if !is_uv_installed() {
-        println!("uv not found. Attempting to download...");
-        match download_uv() {
-            Ok(path) => println!("Downloaded uv to: {}", path.display()),
-            Err(err) => {
+    match &target {
+        commands::CommandTarget::Script(script) => {
+            // Construct the full path to the script
+            let script_full_path = temp_dir.path().join(script);
+            if !script_full_path.exists() {
                 return Err(io::Error::new(
                     io::ErrorKind::NotFound,
-                    format!("Failed to download uv: {}. Please install uv manually (https://github.com/astral-sh/uv)", err)
+                    format!("Script {} not found in archive", script),
                 ));
             }
+            cmd_args.push(script_full_path.to_string_lossy().to_string());
+        }
+        commands::CommandTarget::Module { module, function } => {
+            // Called like a console script, with the archive on the import path
+            cmd_args.push("python".to_string());
+            cmd_args.push("-c".to_string());
+            cmd_args.push(commands::module_runner(&command_name, module, function));
         }
     }
 
+    // Add script arguments
+    cmd_args.extend_from_slice(&script_args);
+
+    // Check if uv is installed or install it
+    ensure_uv()?;
+
+    // The project environment is synced before entering the sandbox
+    if let Some(sandbox_options) = &options.sandbox {
+        if sandbox::prepare(temp_dir.path(), sandbox_options)? {
+            cmd_args.insert(1, "--no-sync".to_string());
+        }
+    }
+
+    status!("Running: uv {}", cmd_args.join(" "));
+
     // Run the script using uv (using our helper function)
-    let status = get_uv_command().args(&cmd_args).status()?;
+    let mut cmd = get_uv_command();
+    cmd.args(&cmd_args).stdout(output::child_stdout());
+    if matches!(target, commands::CommandTarget::Module { .. }) {
+        let mut python_path = vec![temp_dir.path().to_path_buf()];
+        python_path.extend(env::var_os("PYTHONPATH").iter().flat_map(env::split_paths));
+        cmd.env("PYTHONPATH", env::join_paths(python_path).map_err(io::Error::other)?);
+    }
+    if !secret_vars.is_empty() {
+        status!("Injecting {} secret(s) into the environment", secret_vars.len());
+        cmd.envs(secret_vars);
+    }
+    if let Some(sandbox_options) = &options.sandbox {
+        sandbox::apply(&mut cmd, temp_dir.path(), sandbox_options)?;
+    }
+    let status = cmd.status()?;
 
-    Ok(status.code().unwrap_or(1))
+    Ok(RunOutcome {
+        entry: target.to_string(),
+        command: named_command,
+        exit_code: status.code().unwrap_or(1),
+    })
 }
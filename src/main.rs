@@ -1,6 +1,273 @@
-use clap::Parser;
-use pytron::{Cli, Commands};
-use std::{env, process::exit};
+use clap::parser::ValueSource;
+use clap::{FromArgMatches, Parser};
+use pytron::{CacheCommands, Cli, Commands, ConfigCommands, TrustCommands, UvCommands};
+use std::time::Instant;
+use std::{env, io, process::exit};
+
+/// Print an error from running an archive and return the exit code for it.
+/// Archive problems (password required, wrong password, corrupt archive) get
+/// their own exit codes; everything else exits with 1.
+fn report_run_error(err: &io::Error) -> i32 {
+    match pytron::archive::archive_error(err) {
+        Some(archive_err) => {
+            eprintln!("Error: {}", archive_err);
+            archive_err.exit_code()
+        }
+        None => {
+            eprintln!("Error running from zip: {}", err);
+            1
+        }
+    }
+}
+
+/// Open an archive for reading with the password and key options of a command,
+/// prompting for the password if the archive needs one. Returns the archive and
+/// the password for its encrypted entries.
+fn open_archive_for_reading(
+    path: &std::path::Path,
+    password_input: &pytron::password::PasswordInput,
+    identity: Option<&String>,
+) -> io::Result<(
+    zip::ZipArchive<pytron::archive::ArchiveReader>,
+    Option<String>,
+)> {
+    let password = match pytron::password::resolve_password(password_input, false)? {
+        Some(password) => Some(password),
+        None => pytron::password::prompt_for_archive(path)?,
+    };
+    let identities: Vec<std::path::PathBuf> =
+        identity.iter().map(std::path::PathBuf::from).collect();
+    let archive =
+        pytron::archive::open_archive_with_keys(path, password.as_deref(), &identities)?;
+    Ok((archive, password))
+}
+
+/// Print the named commands of the archive for `pytron run --list` and return the exit code
+fn list_commands(run: &pytron::run_args::RunArgs, started: Instant) -> i32 {
+    if !run.is_archive() {
+        let err = io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("--list needs an archive, {} is not a .zip file", run.target),
+        );
+        return fail("run", started, "Error", &err);
+    }
+    let path = std::path::Path::new(&run.target);
+    let result = pytron::password::resolve_password(&run.password, false)
+        .and_then(|password| match password {
+            Some(password) => Ok(Some(password)),
+            None => pytron::password::prompt_for_archive(path),
+        })
+        .and_then(|password| {
+            let mut zip =
+                pytron::archive::open_archive_with_keys(path, password.as_deref(), &run.identities)?;
+            pytron::commands::read_commands(&mut zip, password.as_deref())
+        });
+    match result {
+        Ok(commands) => {
+            let list = pytron::output::CommandList {
+                archive: run.target.clone(),
+                commands,
+            };
+            succeed("run", started, 0, &list, |list| {
+                if list.commands.is_empty() {
+                    println!("{} declares no commands, `pytron run {}` runs {}", run.target, run.target, run.entry_script());
+                } else {
+                    println!("Commands in {}:", run.target);
+                    print!("{}", pytron::commands::format_commands(&list.commands));
+                }
+            })
+        }
+        Err(err) if pytron::output::is_json() => fail("run", started, "Error", &err),
+        Err(err) => report_run_error(&err),
+    }
+}
+
+/// Print the result of a successful command: as a JSON result document with
+/// `--json`, otherwise with `print_text`. Returns `exit_code`.
+fn succeed<T: serde::Serialize>(
+    command: &str,
+    started: Instant,
+    exit_code: i32,
+    result: &T,
+    print_text: impl FnOnce(&T),
+) -> i32 {
+    if pytron::output::is_json() {
+        pytron::output::CommandResult::ok(command, started, exit_code, result).print();
+    } else {
+        print_text(result);
+    }
+    exit_code
+}
+
+/// Report a failed command: as a JSON result document with `--json`,
+/// otherwise as `context: error` on stderr. Returns the exit code.
+fn fail(command: &str, started: Instant, context: &str, err: &io::Error) -> i32 {
+    if pytron::output::is_json() {
+        let document = pytron::output::CommandResult::error(command, started, err);
+        document.print();
+        return document.exit_code;
+    }
+    eprintln!("{}: {}", context, err);
+    pytron::output::exit_code(err)
+}
+
+/// `succeed` or `fail` depending on `result`
+fn finish<T: serde::Serialize>(
+    command: &str,
+    started: Instant,
+    result: io::Result<T>,
+    context: &str,
+    print_text: impl FnOnce(&T),
+) -> i32 {
+    match result {
+        Ok(value) => succeed(command, started, 0, &value, print_text),
+        Err(err) => fail(command, started, context, &err),
+    }
+}
+
+/// Load the layered configuration for a project directory, exiting on invalid files
+fn load_config(project_dir: &std::path::Path) -> pytron::config::Config {
+    match pytron::config::Config::load(project_dir) {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            exit(1);
+        }
+    }
+}
+
+/// Whether an argument of the subcommand was given on the command line rather
+/// than left at its default, so the configuration may supply it
+fn given(matches: &clap::ArgMatches, id: &str) -> bool {
+    matches
+        .subcommand()
+        .is_some_and(|(_, sub)| sub.value_source(id) == Some(ValueSource::CommandLine))
+}
+
+/// Name of the subcommand for result documents, e.g. "zip" or "cache prune"
+fn command_name(matches: &clap::ArgMatches) -> String {
+    let mut names = Vec::new();
+    let mut current = matches;
+    while let Some((name, sub)) = current.subcommand() {
+        names.push(name);
+        current = sub;
+    }
+    names.join(" ")
+}
+
+/// Run an archive or a script from the parsed `run` arguments and return the exit code
+fn run_command(mut run: pytron::run_args::RunArgs) -> i32 {
+    let started = Instant::now();
+    if run.json {
+        pytron::output::set_json(true);
+    }
+    // Project configuration is read from the current directory
+    let configured = pytron::config::Config::load_lenient(std::path::Path::new("."))
+        .and_then(|config| run.apply_config(&config));
+    if let Err(err) = configured {
+        return fail("run", started, "Error", &err);
+    }
+    if run.list_commands {
+        return list_commands(&run, started);
+    }
+
+    // Check if uv is installed or install it if needed, archives check once they are extracted
+    if !run.is_archive() || run.uv_help {
+        if let Err(err) = pytron::ensure_uv() {
+            return fail("run", started, "Error", &err);
+        }
+    }
+
+    // Skip running anything when the help of uv run is requested
+    if run.uv_help {
+        pytron::status!("Running: uv run --help");
+        return match pytron::get_uv_command()
+            .args(["run", "--help"])
+            .stdout(pytron::output::child_stdout())
+            .status()
+        {
+            Ok(status) => {
+                let code = status.code().unwrap_or(1);
+                succeed("run", started, code, &serde_json::json!({}), |_| {})
+            }
+            Err(err) => fail("run", started, "Error running uv", &err),
+        };
+    }
+
+    pytron::status!("Running: {}", run.entry_script());
+    pytron::status!("UV args: {:?}", run.uv_args);
+    pytron::status!("Script args: {:?}", run.script_args);
+
+    let report = |outcome: pytron::RunOutcome| pytron::output::RunReport {
+        target: run.target.clone(),
+        archive: run.is_archive(),
+        entry_script: outcome.entry,
+        command: outcome.command,
+        script_exit_code: outcome.exit_code,
+    };
+    if run.is_archive() {
+        pytron::status!("Running from zip: {}", run.target);
+        let script = run.entry_script();
+
+        let result = pytron::password::resolve_password(&run.password, false)
+            .and_then(|password| match password {
+                Some(password) => Ok(Some(password)),
+                None => pytron::password::prompt_for_archive(std::path::Path::new(&run.target)),
+            })
+            .and_then(|password| {
+                let options = pytron::RunOptions {
+                    password,
+                    require_signature: run.require_signature,
+                    identities: run.identities.clone(),
+                    sandbox: run.sandbox.clone(),
+                    // An entry selected explicitly always runs as a script, and
+                    // arguments after `--` never name a command
+                    named_commands: run.entry.is_none() && run.may_name_command,
+                };
+                pytron::run_from_zip_with_options(
+                    &run.target,
+                    script,
+                    &run.uv_args,
+                    &run.script_args,
+                    &options,
+                )
+            });
+        match result {
+            Ok(outcome) => succeed("run", started, outcome.exit_code, &report(outcome), |_| {}),
+            Err(err) if pytron::output::is_json() => fail("run", started, "Error", &err),
+            Err(err) => report_run_error(&err),
+        }
+    } else {
+        // It's a script, run directly
+        pytron::status!("Running script directly: {}", run.target);
+        if run.sandbox.is_some() {
+            eprintln!("Warning: --sandbox only applies to archives, running the script without it");
+        }
+
+        let mut cmd_args = vec!["run".to_string()];
+        cmd_args.extend_from_slice(&run.uv_args);
+        cmd_args.push(run.target.clone());
+        cmd_args.extend_from_slice(&run.script_args);
+
+        pytron::status!("Running: uv {}", cmd_args.join(" "));
+
+        match pytron::get_uv_command()
+            .args(&cmd_args)
+            .stdout(pytron::output::child_stdout())
+            .status()
+        {
+            Ok(status) => {
+                let outcome = pytron::RunOutcome {
+                    entry: run.target.clone(),
+                    command: None,
+                    exit_code: status.code().unwrap_or(1),
+                };
+                succeed("run", started, outcome.exit_code, &report(outcome), |_| {})
+            }
+            Err(err) => fail("run", started, "Error running script", &err),
+        }
+    }
+}
 
 fn main() {
     // On Windows, check for long path support at startup
@@ -11,17 +278,42 @@ fn main() {
                 // Long path support is enabled, continue normally
             }
             Ok(false) => {
-                println!("Long path support has been enabled, but you need to reboot for it to take effect.");
-                println!("After rebooting, run this command again.");
+                eprintln!("Long path support has been enabled, but you need to reboot for it to take effect.");
+                eprintln!("After rebooting, run this command again.");
             }
             Err(e) => {
-                println!("Warning: Could not check or enable long path support: {}", e);
-                println!("You may encounter issues with long file paths.");
+                eprintln!("Warning: Could not check or enable long path support: {}", e);
+                eprintln!("You may encounter issues with long file paths.");
             }
         }
     }
     
-    let args: Vec<String> = env::args().collect();
+    let mut args: Vec<String> = env::args().collect();
+
+    // `--json` is global, also when it comes before the hand-parsed `run`
+    if args.len() > 2 && args[1] == "--json" && args[2] == "run" {
+        args.remove(1);
+        pytron::output::set_json(true);
+    }
+
+    // Completion scripts ask for the completions of `run` with
+    // `pytron __complete --current=<word> -- <words before it>`. It is not a
+    // clap subcommand so it does not show up in the completions themselves.
+    if args.len() > 1 && args[1] == pytron::completions::COMPLETE_COMMAND {
+        let rest = &args[2..];
+        let current = rest
+            .first()
+            .and_then(|arg| arg.strip_prefix("--current="))
+            .unwrap_or("");
+        let words = rest
+            .iter()
+            .position(|arg| arg == "--")
+            .map_or(&[][..], |pos| &rest[pos + 1..]);
+        for candidate in pytron::completions::complete(words, current) {
+            println!("{}", candidate);
+        }
+        return;
+    }
 
     // Check if we're using the run command
     if args.len() > 1 && args[1] == "run" {
@@ -32,159 +324,29 @@ fn main() {
             return; // This line won't be reached as clap will exit after showing help
         }
 
-        // Default values
-        let mut zipfile = "robot.zip".to_string();
-        let script = "main.py".to_string();
-        let mut script_args = Vec::new();
-        let mut uv_args = Vec::new();
-
-        // Parse custom args using double-dash as separator
-        let mut i = 2;
-        let mut found_separator = false;
-        let mut found_script_name = false;
-        // Password for decrypting the ZIP file
-        let mut password = None;
-
-        while i < args.len() {
-
-            if args[i] == "--password" || args[i] == "-p" {
-                // next element must be the password
-                if i + 1 < args.len() {
-                    password = Some(args[i + 1].clone());
-                    i += 2;
-                    continue;
-                } else {
-                    eprintln!("Error: `{}` requires a value", args[i]);
-                    std::process::exit(1);
-                }
-            }
-            // Check for the double-dash separator
-            if args[i] == "--" && !found_separator {
-                found_separator = true;
-                i += 1;
-                continue;
-            }
-
-            // After separator or script name, everything goes to script
-            if found_separator || found_script_name {
-                script_args.push(args[i].clone());
-                i += 1;
-                continue;
-            }
-
-            // If not a flag and no script name found yet, treat as script/zipfile name
-            if !args[i].starts_with('-') {
-                zipfile = args[i].clone();
-                found_script_name = true;
-                i += 1;
-                continue;
-            }
-
-            // Handle special case for --uv-run-help/-hh flags
-            if args[i] == "--uv-run-help" || args[i] == "-hh" {
-                // Convert to standard help flag for uv
-                uv_args.push("--help".to_string());
-                // Skip zip file execution completely when -hh is used
-                println!("Running: uv run --help");
-                // Check if uv is installed or download it if needed
-                if !pytron::is_uv_installed() {
-                    println!("uv not found. Attempting to download...");
-                    match pytron::download_uv() {
-                        Ok(path) => println!("Downloaded uv to: {}", path.display()),
-                        Err(err) => {
-                            eprintln!("Failed to download uv: {}. Please install uv manually (https://github.com/astral-sh/uv)", err);
-                            exit(1);
-                        }
-                    }
-                }
-                // Run uv directly with help flag
-                let status = pytron::get_uv_command().args(&["run", "--help"]).status();
-                match status {
-                    Ok(status) => exit(status.code().unwrap_or(1)),
-                    Err(err) => {
-                        eprintln!("Error running uv: {}", err);
-                        exit(1);
-                    }
-                }
-            }
-
-            // Everything else before separator or script name is a uv flag
-            uv_args.push(args[i].clone());
-            i += 1;
-        }
-
-        println!("Running: {}", script);
-        println!("UV args: {:?}", uv_args);
-        println!("Script args: {:?}", script_args);
-
-        // Check if the first arg is a zipfile or a direct script
-        let path = std::path::Path::new(&zipfile);
-        let exit_code = if path
-            .extension().is_some_and(|ext| ext == "zip" || ext == "ZIP")
-        {
-            // It's a zipfile, run from zip
-            println!("Running from zip: {}", zipfile);
-
-            // Don't pass the script as an argument again, it will be handled by run_from_zip
-            // If script is in script_args, remove it
-            let filtered_script_args: Vec<String> = script_args
-                .iter()
-                .filter(|&arg| arg != &script)
-                .cloned()
-                .collect();
-
-            // Pass uv_args and script_args separately
-            match pytron::run_from_zip(&zipfile, password.as_ref(), &script, &uv_args, &filtered_script_args) {
-                Ok(code) => code,
-                Err(err) => {
-                    eprintln!("Error running from zip: {}", err);
-                    1
-                }
-            }
-        } else {
-            // It's a script, run directly
-            println!("Running script directly: {}", zipfile);
-
-            // Check if uv is installed or download it if needed
-            if !pytron::is_uv_installed() {
-                println!("uv not found. Attempting to download...");
-                match pytron::download_uv() {
-                    Ok(path) => println!("Downloaded uv to: {}", path.display()),
-                    Err(err) => {
-                        eprintln!("Failed to download uv: {}. Please install uv manually (https://github.com/astral-sh/uv)", err);
-                        exit(1);
-                    }
-                }
-            }
-
-            // In this case, zipfile is actually the script path
-            let mut cmd_args = vec!["run".to_string()];
-
-            // Add uv args
-            cmd_args.extend_from_slice(&uv_args);
-
-            // Add script path
-            cmd_args.push(zipfile.clone());
-
-            // Add script args
-            cmd_args.extend_from_slice(&script_args);
-
-            println!("Running: uv {}", cmd_args.join(" "));
-
-            // Run the script using uv with our helper function
-            match pytron::get_uv_command().args(&cmd_args).status() {
-                Ok(status) => status.code().unwrap_or(1),
-                Err(err) => {
-                    eprintln!("Error running script: {}", err);
-                    1
-                }
-            }
+        let run = match pytron::run_args::parse_run_args(&args[2..]) {
+            Ok(run) => run,
+            Err(err) => exit(fail("run", Instant::now(), "Error", &err)),
         };
-
+        let exit_code = run_command(run);
         exit(exit_code);
     } else {
         // Use clap for all other commands
-        let cli = Cli::parse();
+        let mut cli_command = pytron::cli_command();
+        let matches = cli_command.get_matches_mut();
+        let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+        let started = Instant::now();
+        let command = &command_name(&matches);
+        let top_level = matches.subcommand_name().unwrap_or_default();
+        if cli.json && !pytron::JSON_COMMANDS.contains(&top_level) {
+            cli_command
+                .error(
+                    clap::error::ErrorKind::ArgumentConflict,
+                    format!("`pytron {}` has no JSON output", command),
+                )
+                .exit();
+        }
+        pytron::output::set_json(cli.json);
 
         match &cli.command {
             Commands::Zip {
@@ -192,42 +354,637 @@ fn main() {
                 output,
                 ignore_patterns,
                 password,
+                password_file,
+                password_env,
+                ask_password,
+                seal,
+                recipients,
+                secrets,
+                sbom,
+                sign,
+                scan,
+                allow,
+                scan_rules,
+                dry_run,
+                explain,
             } => {
-                if let Err(err) =
-                    pytron::zip_directory(directory, output, ignore_patterns.as_ref(), password.as_ref())
-                {
-                    eprintln!("Error zipping directory: {}", err);
-                    exit(1);
+                let config = match pytron::config::Config::load(std::path::Path::new(directory)) {
+                    Ok(config) => config,
+                    Err(err) => exit(finish(command, started, Err::<(), _>(err), "Error", |_| {})),
+                };
+                // Values from the command line win over the configured ones
+                let output = &match config.get_str("zip.output") {
+                    Some(configured) if !given(&matches, "output") => configured,
+                    _ => output.clone(),
+                };
+                let ignore_patterns = ignore_patterns
+                    .clone()
+                    .or_else(|| config.get_list("zip.ignore_patterns"));
+                let scan = &match config.get_enum("zip.scan") {
+                    Ok(Some(configured)) if !given(&matches, "scan") => configured,
+                    Ok(_) => *scan,
+                    Err(err) => exit(finish(command, started, Err::<(), _>(err), "Error", |_| {})),
+                };
+                if *dry_run || explain.is_some() {
+                    let dir_path = std::path::Path::new(directory);
+                    let exclude: Vec<std::path::PathBuf> =
+                        secrets.iter().map(std::path::PathBuf::from).collect();
+                    let result = pytron::selection::select_files(
+                        dir_path,
+                        std::path::Path::new(output),
+                        ignore_patterns.as_ref(),
+                        &exclude,
+                    )
+                    .and_then(|selection| {
+                        let explanation = match explain {
+                            // Accept the path relative to DIRECTORY or as typed from the current directory
+                            Some(path) => {
+                                let name = std::path::Path::new(path)
+                                    .strip_prefix(dir_path)
+                                    .unwrap_or(std::path::Path::new(path))
+                                    .to_string_lossy()
+                                    .replace('\\', "/");
+                                Some(selection.explain(&name).ok_or_else(|| {
+                                    io::Error::new(
+                                        io::ErrorKind::NotFound,
+                                        format!("{} is not in {}", path, directory),
+                                    )
+                                })?)
+                            }
+                            None => None,
+                        };
+                        Ok(pytron::output::ZipPlan { selection, explanation })
+                    });
+                    exit(finish(command, started, result, "Error", |plan| {
+                        if *dry_run {
+                            print!("{}", plan.selection.to_text());
+                        }
+                        if let Some(explanation) = &plan.explanation {
+                            print!("{}", explanation.to_text());
+                        }
+                    }));
                 }
+                let mut password_input = pytron::password::PasswordInput {
+                    literal: password.clone(),
+                    file: password_file.as_ref().map(std::path::PathBuf::from),
+                    env: password_env.clone(),
+                    ask: *ask_password,
+                };
+                config.apply_password("zip", &mut password_input);
+                let result = pytron::password::resolve_password(&password_input, true).and_then(|password| {
+                    if *seal && password.is_none() {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            "--seal needs a password (--password-file, --password-env, --ask-password or PYTRON_PASSWORD)",
+                        ));
+                    }
+                    // A sealed archive is encrypted as a whole, so its entries are stored in the clear
+                    let entry_password = if *seal { None } else { password.clone() };
+                    let parsed = pytron::recipients::parse_recipients(recipients)?;
+                    let mut zip_options = pytron::ZipOptions {
+                        ignore_patterns: ignore_patterns.clone(),
+                        password: entry_password,
+                        sbom: sbom.clone(),
+                        ..Default::default()
+                    };
+                    if let Some(key) = sign {
+                        zip_options.signing_key =
+                            Some(pytron::signing::read_signing_key(std::path::Path::new(key))?);
+                    }
+                    if let (Some(password), true) = (&password, *seal) {
+                        zip_options.encryption = pytron::ArchiveEncryption::Sealed(password.clone());
+                    } else if !parsed.is_empty() {
+                        zip_options.encryption = pytron::ArchiveEncryption::Recipients(parsed.clone());
+                    }
+                    if *scan != pytron::scan::ScanMode::Off {
+                        let rules_file = match scan_rules {
+                            Some(path) => Some(std::path::PathBuf::from(path)),
+                            None => Some(std::path::Path::new(directory).join(pytron::scan::PROJECT_RULES_FILE))
+                                .filter(|path| path.is_file()),
+                        };
+                        zip_options.scanner = Some(pytron::scan::Scanner::new(
+                            *scan,
+                            rules_file.as_deref(),
+                            allow,
+                        )?);
+                    }
+                    let mut secret_count = 0;
+                    if let Some(secrets_file) = secrets {
+                        let secrets_path = std::path::Path::new(secrets_file);
+                        let (data, count) = pytron::secrets::encrypt_secrets_file(
+                            secrets_path,
+                            password.as_deref(),
+                            &parsed,
+                        )?;
+                        pytron::status!("Adding {} encrypted secret(s) from {}", count, secrets_file);
+                        secret_count = count;
+                        zip_options.exclude.push(secrets_path.to_path_buf());
+                        zip_options
+                            .extra_entries
+                            .push((pytron::secrets::SECRETS_ENTRY.to_string(), data));
+                    }
+                    let selection = pytron::zip_directory_with_options(directory, output, &zip_options)?;
+                    let output_path = std::path::Path::new(output);
+                    let encryption = if matches!(zip_options.encryption, pytron::ArchiveEncryption::Sealed(_)) {
+                        "sealed"
+                    } else if !parsed.is_empty() {
+                        "recipients"
+                    } else if password.is_some() {
+                        "entries"
+                    } else {
+                        "none"
+                    };
+                    Ok(pytron::output::ZipReport {
+                        archive: output.clone(),
+                        size: std::fs::metadata(output_path)?.len(),
+                        sha256: pytron::sha256_file(output_path)?,
+                        files: selection.included.len(),
+                        uncompressed_size: selection.total_size,
+                        excluded: selection.excluded.len(),
+                        encryption,
+                        recipients: parsed.len(),
+                        secrets: secret_count,
+                        sbom: sbom.iter().map(|format| format.entry_name().to_string()).collect(),
+                        signed_by: zip_options
+                            .signing_key
+                            .as_ref()
+                            .map(|key| pytron::signing::key_id(&key.verifying_key())),
+                    })
+                });
+                exit(finish(command, started, result, "Error zipping directory", |report| {
+                    match report.encryption {
+                        "sealed" => println!("Sealed {}", output),
+                        "recipients" => println!("Encrypted {} to {} recipient(s)", output, report.recipients),
+                        _ => {}
+                    }
+                    if let Some(key_id) = &report.signed_by {
+                        println!("Signed {} with key {}", output, key_id);
+                    }
+                }));
             }
             Commands::Run {
                 zipfile,
                 password,
+                password_file,
+                password_env,
+                ask_password,
                 script,
+                entry,
+                list,
                 uv_args,
                 script_args,
+                require_signature,
+                identity,
+                sandbox,
+                allow_write,
+                no_network,
             } => {
-                // Check if uv is installed or download it if needed
-                if !pytron::is_uv_installed() {
-                    println!("uv not found. Attempting to download...");
-                    match pytron::download_uv() {
-                        Ok(path) => println!("Downloaded uv to: {}", path.display()),
-                        Err(err) => {
-                            eprintln!("Failed to download uv: {}. Please install uv manually (https://github.com/astral-sh/uv)", err);
-                            exit(1);
+                // This branch is for when using clap with -- to pass args
+                let mut run = pytron::run_args::RunArgs {
+                    target: zipfile.clone(),
+                    target_given: given(&matches, "zipfile"),
+                    uv_args: uv_args.clone(),
+                    script_args: script_args.clone(),
+                    password: pytron::password::PasswordInput {
+                        literal: password.clone(),
+                        file: password_file.as_ref().map(std::path::PathBuf::from),
+                        env: password_env.clone(),
+                        ask: *ask_password,
+                    },
+                    require_signature: *require_signature,
+                    identities: identity.iter().map(std::path::PathBuf::from).collect(),
+                    list_commands: *list,
+                    sandbox: sandbox.then(|| pytron::sandbox::SandboxOptions {
+                        allow_write: allow_write.iter().map(std::path::PathBuf::from).collect(),
+                        no_network: *no_network,
+                    }),
+                    ..Default::default()
+                };
+                // A script path selects the entry, anything else may name a command
+                let mut entry = entry.clone();
+                if script != pytron::run_args::DEFAULT_ENTRY {
+                    if script.ends_with(".py") {
+                        entry = Some(script.clone());
+                    } else {
+                        run.script_args.insert(0, script.clone());
+                        run.may_name_command = true;
+                    }
+                }
+                if let Err(err) = pytron::run_args::set_entry(&mut run, entry) {
+                    exit(fail(command, started, "Error", &err));
+                }
+                exit(run_command(run));
+            }
+            Commands::Keygen { output, encryption, force } => {
+                let result = if *encryption {
+                    let stem = output.as_deref().unwrap_or("pytron_encryption");
+                    pytron::recipients::generate_identity(std::path::Path::new(stem), *force)
+                } else {
+                    let stem = output.as_deref().unwrap_or("pytron_signing");
+                    pytron::signing::generate_keypair(std::path::Path::new(stem), *force)
+                };
+                match result {
+                    Ok((private_path, public_path)) => {
+                        println!("Private key: {}", private_path.display());
+                        println!("Public key:  {}", public_path.display());
+                        if *encryption {
+                            println!("Keep the private key secret and copy it into PYTRON_HOME/keys on the host that runs the archives. Pass the public key to `pytron zip --recipient`.");
+                        } else {
+                            println!("Keep the private key secret. Copy the public key into PYTRON_HOME/trusted_keys on hosts that should trust it.");
                         }
                     }
+                    Err(err) => {
+                        eprintln!("Error generating key pair: {}", err);
+                        exit(1);
+                    }
                 }
-                
-                // This branch is for when using clap with -- to pass args
-                let exit_code = match pytron::run_from_zip(zipfile, password.as_ref(), script, uv_args, script_args) {
-                    Ok(code) => code,
+            }
+            Commands::Sign { archive, key } => {
+                let result = pytron::signing::read_signing_key(std::path::Path::new(key))
+                    .and_then(|signing_key| {
+                        pytron::signing::sign_archive(std::path::Path::new(archive), &signing_key)
+                    });
+                match result {
+                    Ok(key_id) => println!("Signed {} with key {}", archive, key_id),
+                    Err(err) => {
+                        eprintln!("Error signing archive: {}", err);
+                        exit(1);
+                    }
+                }
+            }
+            Commands::Verify {
+                contents: true,
+                archive,
+                password_file,
+                password_env,
+                ask_password,
+                identity,
+                ..
+            } => {
+                let password_input = pytron::password::PasswordInput {
+                    literal: None,
+                    file: password_file.as_ref().map(std::path::PathBuf::from),
+                    env: password_env.clone(),
+                    ask: *ask_password,
+                };
+                let result = open_archive_for_reading(
+                    std::path::Path::new(archive),
+                    &password_input,
+                    identity.as_ref(),
+                )
+                .and_then(|(mut zip, password)| {
+                    pytron::manifest::verify_archive(&mut zip, password.as_deref())
+                });
+                match result {
+                    Ok(count) => println!("Contents OK: {} file(s) match the manifest of {}", count, archive),
+                    Err(err) => {
+                        eprintln!("Contents INVALID: {}", err);
+                        exit(pytron::archive::archive_error(&err).map_or(1, |e| e.exit_code()));
+                    }
+                }
+            }
+            Commands::Verify {
+                archive,
+                key,
+                password_file,
+                password_env,
+                ask_password,
+                identity,
+                ..
+            } => {
+                let path = std::path::Path::new(archive);
+                let password_input = pytron::password::PasswordInput {
+                    literal: None,
+                    file: password_file.as_ref().map(std::path::PathBuf::from),
+                    env: password_env.clone(),
+                    ask: *ask_password,
+                };
+                let identities: Vec<std::path::PathBuf> =
+                    identity.iter().map(std::path::PathBuf::from).collect();
+                // Only a sealed archive needs its password to check the signature
+                let status = pytron::password::resolve_password(&password_input, false)
+                    .and_then(|password| match password {
+                        None if pytron::sealed::is_sealed(path)? => {
+                            pytron::password::prompt_for_archive(path)
+                        }
+                        password => Ok(password),
+                    })
+                    .and_then(|password| {
+                        pytron::signing::verify_archive(
+                            path,
+                            key.as_ref().map(std::path::Path::new),
+                            password.as_deref(),
+                            &identities,
+                        )
+                    });
+                match status {
+                    Ok(pytron::signing::SignatureStatus::Valid {
+                        key_id,
+                        trusted_key: Some(path),
+                    }) => println!("Signature OK: {} signed by trusted key {} ({})", archive, key_id, path.display()),
+                    Ok(pytron::signing::SignatureStatus::Valid { key_id, .. }) => {
+                        println!("Signature valid but key {} is not trusted", key_id);
+                        exit(1);
+                    }
+                    Ok(pytron::signing::SignatureStatus::Unsigned) => {
+                        println!("{} is not signed", archive);
+                        exit(1);
+                    }
+                    Ok(pytron::signing::SignatureStatus::Invalid(reason)) => {
+                        println!("Signature INVALID: {}", reason);
+                        exit(1);
+                    }
+                    Err(err) => {
+                        eprintln!("Error verifying archive: {}", err);
+                        exit(1);
+                    }
+                }
+            }
+            Commands::Sbom {
+                archive,
+                format,
+                output,
+                password_file,
+                password_env,
+                ask_password,
+                identity,
+            } => {
+                let path = std::path::Path::new(archive);
+                let password_input = pytron::password::PasswordInput {
+                    literal: None,
+                    file: password_file.as_ref().map(std::path::PathBuf::from),
+                    env: password_env.clone(),
+                    ask: *ask_password,
+                };
+                let result = open_archive_for_reading(path, &password_input, identity.as_ref())
+                    .and_then(|(mut zip, password)| {
+                        pytron::sbom::collect_from_archive(&mut zip, password.as_deref(), path)
+                    })
+                    .map(|input| pytron::sbom::render(&input, *format))
+                    .and_then(|document| match output {
+                        Some(output) => {
+                            std::fs::write(output, document)?;
+                            println!("SBOM written to {}", output);
+                            Ok(())
+                        }
+                        None => {
+                            print!("{}", document);
+                            Ok(())
+                        }
+                    });
+                if let Err(err) = result {
+                    eprintln!("Error creating SBOM: {}", err);
+                    exit(pytron::archive::archive_error(&err).map_or(1, |e| e.exit_code()));
+                }
+            }
+            Commands::Config { command } => match command {
+                ConfigCommands::Show { dir } => {
+                    print!("{}", load_config(std::path::Path::new(dir)).to_text());
+                }
+            },
+            Commands::Completions { shell } => {
+                print!("{}", pytron::completions::generate_script(*shell));
+            }
+            Commands::Uv { command: uv_command } => match uv_command {
+                UvCommands::Install {
+                    from,
+                    sha256,
+                    no_verify,
+                } => {
+                    let result = match from {
+                        Some(archive) => pytron::install_uv_from_archive(
+                            std::path::Path::new(archive),
+                            sha256.as_deref(),
+                            *no_verify,
+                        ),
+                        None => pytron::download_uv(),
+                    }
+                    .and_then(|path| {
+                        Ok(pytron::output::UvInstallReport {
+                            sha256: pytron::sha256_file(&path)?,
+                            path: path.display().to_string(),
+                            source: if from.is_some() { "archive" } else { "download" },
+                            archive: from.clone(),
+                        })
+                    });
+                    exit(finish(command, started, result, "Error installing uv", |report| {
+                        println!("uv installed at: {}", report.path)
+                    }));
+                }
+            },
+            Commands::Cache { command: cache_command } => {
+                let result = match cache_command {
+                    CacheCommands::Info => Ok(None),
+                    CacheCommands::Prune { older_than_hours } => pytron::cache::prune_cache(
+                        std::time::Duration::from_secs(older_than_hours * 3600),
+                    )
+                    .map(Some),
+                    CacheCommands::Clear { all } => pytron::cache::clear_cache(*all).map(Some),
+                }
+                .map(pytron::output::CacheReport::new);
+                exit(finish(command, started, result, "Error managing cache", |report| {
+                    match report.freed {
+                        Some(freed) => println!("Freed {}", pytron::format_size(freed)),
+                        None => print!("{}", pytron::cache::format_cache_info(&report.areas)),
+                    }
+                }));
+            }
+            Commands::Inspect {
+                archive,
+                tree,
+                password_file,
+                password_env,
+                ask_password,
+                identity,
+            } => {
+                let path = std::path::Path::new(archive);
+                let password_input = pytron::password::PasswordInput {
+                    literal: None,
+                    file: password_file.as_ref().map(std::path::PathBuf::from),
+                    env: password_env.clone(),
+                    ask: *ask_password,
+                };
+                let result = open_archive_for_reading(path, &password_input, identity.as_ref())
+                    .and_then(|(mut zip, password)| {
+                        pytron::inspect::inspect_archive(&mut zip, path, password.as_deref())
+                    });
+                exit(finish(command, started, result, "Error inspecting archive", |info| {
+                    if *tree {
+                        print!("{}", info.to_tree());
+                    } else {
+                        print!("{}", info.to_text());
+                    }
+                }));
+            }
+            Commands::Extract {
+                archive,
+                dest,
+                overwrite,
+                only,
+                strip_components,
+                password_file,
+                password_env,
+                ask_password,
+                identity,
+            } => {
+                let path = std::path::Path::new(archive);
+                let dest = match dest {
+                    Some(dest) => std::path::PathBuf::from(dest),
+                    None => std::path::PathBuf::from(path.file_stem().unwrap_or(path.as_os_str())),
+                };
+                let password_input = pytron::password::PasswordInput {
+                    literal: None,
+                    file: password_file.as_ref().map(std::path::PathBuf::from),
+                    env: password_env.clone(),
+                    ask: *ask_password,
+                };
+                let options = pytron::extract::ExtractOptions {
+                    overwrite: *overwrite,
+                    only: only.clone(),
+                    strip_components: *strip_components,
+                };
+                let result = open_archive_for_reading(path, &password_input, identity.as_ref())
+                    .and_then(|(mut zip, password)| {
+                        let password = password.as_deref();
+                        pytron::archive::check_password(&mut zip, password)?;
+                        let extracted =
+                            pytron::extract::extract_entries(&mut zip, &dest, password, &options)?;
+                        println!("Extracted {} file(s) to {}", extracted.files.len(), dest.display());
+                        if extracted.secrets.is_some() {
+                            println!("Skipped {}, secrets are only decrypted by `pytron run`", pytron::secrets::SECRETS_ENTRY);
+                        }
+                        match pytron::manifest::read_manifest(&mut zip, password)? {
+                            Some(expected) => {
+                                let count = pytron::extract::verify_extracted(&expected, &extracted)?;
+                                println!("Verified {} file(s) against the content manifest", count);
+                            }
+                            None => println!("Archive has no content manifest, skipping the content check"),
+                        }
+                        Ok(())
+                    });
+                if let Err(err) = result {
+                    eprintln!("Error extracting archive: {}", err);
+                    exit(pytron::archive::archive_error(&err).map_or(1, |e| e.exit_code()));
+                }
+            }
+            Commands::Diff {
+                old,
+                new,
+                password_file,
+                password_env,
+                ask_password,
+                identity,
+            } => {
+                let password_input = pytron::password::PasswordInput {
+                    literal: None,
+                    file: password_file.as_ref().map(std::path::PathBuf::from),
+                    env: password_env.clone(),
+                    ask: *ask_password,
+                };
+                let snapshot = |archive: &String| {
+                    let path = std::path::Path::new(archive);
+                    let (zip, password) =
+                        open_archive_for_reading(path, &password_input, identity.as_ref())?;
+                    pytron::diff::ArchiveSnapshot::read(zip, path, password.as_deref())
+                };
+                let result = snapshot(old).and_then(|mut old| {
+                    let mut new = snapshot(new)?;
+                    pytron::diff::diff_archives(&mut old, &mut new)
+                });
+                exit(finish(command, started, result, "Error comparing archives", |diff| {
+                    print!("{}", diff.to_text())
+                }));
+            }
+            Commands::Init {
+                dir,
+                template,
+                name,
+                python,
+                force,
+                list,
+            } => {
+                use pytron::init;
+                let templates = match init::list_templates() {
+                    Ok(templates) => templates,
                     Err(err) => {
-                        eprintln!("Error running from zip: {}", err);
-                        1
+                        eprintln!("Error reading templates: {}", err);
+                        exit(1);
                     }
                 };
-                exit(exit_code);
+                if *list {
+                    for t in &templates {
+                        let origin = match &t.source {
+                            init::TemplateSource::Builtin => "builtin".to_string(),
+                            init::TemplateSource::User(path) => path.display().to_string(),
+                        };
+                        println!("{:<16} {} ({})", t.name, t.description, origin);
+                    }
+                    return;
+                }
+                let Some(selected) = templates.iter().find(|t| &t.name == template) else {
+                    let names: Vec<&str> = templates.iter().map(|t| t.name.as_str()).collect();
+                    eprintln!(
+                        "Error: unknown template '{}', available: {}",
+                        template,
+                        names.join(", ")
+                    );
+                    exit(1);
+                };
+                let dir = std::path::Path::new(dir);
+                let name = match name {
+                    Some(name) => name.clone(),
+                    None => std::path::absolute(dir)
+                        .ok()
+                        .and_then(|path| path.file_name().map(|n| n.to_string_lossy().to_string()))
+                        .unwrap_or_default(),
+                };
+                let vars = init::TemplateVars::new(&name, python);
+                match init::init_project(dir, selected, &vars, *force) {
+                    Ok(written) => {
+                        for path in &written {
+                            println!("Created {}", path.display());
+                        }
+                        println!(
+                            "Initialized {} from the {} template, package it with `pytron zip {}`",
+                            vars.name,
+                            selected.name,
+                            dir.display()
+                        );
+                    }
+                    Err(err) => {
+                        eprintln!("Error initializing project: {}", err);
+                        exit(1);
+                    }
+                }
+            }
+            Commands::Doctor => {
+                let report = pytron::doctor::run_doctor();
+                let code = if report.healthy { 0 } else { 1 };
+                exit(succeed(command, started, code, &report, |report| {
+                    print!("{}", report.to_text())
+                }));
+            }
+            Commands::Trust { command } => {
+                use pytron::trust::{self, PolicyScope};
+                let scope = |system: bool| if system { PolicyScope::System } else { PolicyScope::User };
+                let result = match command {
+                    TrustCommands::Add { target, name, system } => {
+                        trust::add_entry(scope(*system), &target.entry(), name.as_deref())
+                    }
+                    TrustCommands::Remove { target, system } => {
+                        trust::remove_entry(scope(*system), &target.entry())
+                    }
+                    TrustCommands::List => trust::format_policies().map(|text| text.trim_end().to_string()),
+                    TrustCommands::Mode { mode, system } => trust::set_mode(scope(*system), *mode)
+                        .map(|path| format!("Set trust mode to {} in {}", mode, path.display())),
+                };
+                match result {
+                    Ok(message) => println!("{}", message),
+                    Err(err) => {
+                        eprintln!("Error updating trust policy: {}", err);
+                        exit(1);
+                    }
+                }
             }
         }
     }
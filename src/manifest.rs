@@ -0,0 +1,190 @@
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::{self, Read, Seek};
+use std::path::Path;
+use walkdir::WalkDir;
+use zip::ZipArchive;
+
+use crate::archive::{self, ArchiveError};
+use crate::sha256_file;
+
+/// Archive entry holding the SHA-256 digest of every other file entry
+pub const MANIFEST_ENTRY: &str = ".pytron/manifest.sha256";
+
+/// SHA-256 digests of the files in an archive, keyed by entry name.
+///
+/// Stored in `sha256sum` format (`<digest>  <name>` per line), so it can also
+/// be checked with standard tools after extracting an archive.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Manifest {
+    pub files: BTreeMap<String, String>,
+}
+
+impl Manifest {
+    /// Record the digest of `data` under `name`
+    pub fn add(&mut self, name: &str, data: &[u8]) {
+        self.files
+            .insert(name.to_string(), hex::encode(Sha256::digest(data)));
+    }
+
+    pub fn to_text(&self) -> String {
+        self.files
+            .iter()
+            .map(|(name, digest)| format!("{}  {}\n", digest, name))
+            .collect()
+    }
+
+    pub fn parse(text: &str) -> io::Result<Self> {
+        let mut files = BTreeMap::new();
+        for line in text.lines().filter(|line| !line.trim().is_empty()) {
+            let (digest, name) = line
+                .split_once("  ")
+                .filter(|(digest, name)| {
+                    digest.len() == 64
+                        && digest.chars().all(|c| c.is_ascii_hexdigit())
+                        && !name.is_empty()
+                })
+                .ok_or_else(|| {
+                    ArchiveError::Corrupt(format!(
+                        "{} has an invalid line: {}",
+                        MANIFEST_ENTRY, line
+                    ))
+                })?;
+            files.insert(name.to_string(), digest.to_ascii_lowercase());
+        }
+        Ok(Manifest { files })
+    }
+}
+
+/// A difference between the manifest and the actual contents
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Mismatch {
+    /// Listed in the manifest but not present
+    Missing(String),
+    /// Present but not listed in the manifest
+    Extra(String),
+    /// Present with a different digest
+    Modified(String),
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Mismatch::Missing(name) => write!(f, "missing: {}", name),
+            Mismatch::Extra(name) => write!(f, "extra: {}", name),
+            Mismatch::Modified(name) => write!(f, "modified: {}", name),
+        }
+    }
+}
+
+/// List every difference between `expected` and `actual`, sorted by name
+pub fn compare(expected: &Manifest, actual: &Manifest) -> Vec<Mismatch> {
+    let mut mismatches = Vec::new();
+    for (name, digest) in &expected.files {
+        match actual.files.get(name) {
+            None => mismatches.push(Mismatch::Missing(name.clone())),
+            Some(found) if found != digest => mismatches.push(Mismatch::Modified(name.clone())),
+            Some(_) => {}
+        }
+    }
+    for name in actual.files.keys() {
+        if !expected.files.contains_key(name) {
+            mismatches.push(Mismatch::Extra(name.clone()));
+        }
+    }
+    mismatches.sort_by(|a, b| mismatch_name(a).cmp(mismatch_name(b)));
+    mismatches
+}
+
+fn mismatch_name(mismatch: &Mismatch) -> &str {
+    match mismatch {
+        Mismatch::Missing(name) | Mismatch::Extra(name) | Mismatch::Modified(name) => name,
+    }
+}
+
+/// Turn a failed content check into an error for the corrupt-archive exit code
+pub fn mismatch_error(mismatches: &[Mismatch]) -> io::Error {
+    let listed = mismatches
+        .iter()
+        .map(Mismatch::to_string)
+        .collect::<Vec<_>>()
+        .join(", ");
+    ArchiveError::Corrupt(format!("content check failed ({})", listed)).into()
+}
+
+/// Hash the files below `dir`, named relative to it with forward slashes.
+/// The manifest entry itself is left out.
+pub fn hash_directory(dir: &Path) -> io::Result<Manifest> {
+    let mut manifest = Manifest::default();
+    for entry in WalkDir::new(dir) {
+        let entry = entry.map_err(io::Error::other)?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let name = entry
+            .path()
+            .strip_prefix(dir)
+            .map_err(io::Error::other)?
+            .to_string_lossy()
+            .replace('\\', "/");
+        if name != MANIFEST_ENTRY {
+            manifest.files.insert(name, sha256_file(entry.path())?);
+        }
+    }
+    Ok(manifest)
+}
+
+/// Read the manifest of an archive, or None for archives created without one
+pub fn read_manifest<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    password: Option<&str>,
+) -> io::Result<Option<Manifest>> {
+    let Some(index) = archive.index_for_name(MANIFEST_ENTRY) else {
+        return Ok(None);
+    };
+    let mut entry = archive::read_entry(archive, index, password)?;
+    let mut text = Vec::new();
+    archive::copy_entry(&mut entry, &mut text)?;
+    let text = String::from_utf8(text)
+        .map_err(|_| ArchiveError::Corrupt(format!("{} is not valid UTF-8", MANIFEST_ENTRY)))?;
+    Manifest::parse(&text).map(Some)
+}
+
+/// Hash the file entries of an archive without extracting them
+pub fn hash_archive<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    password: Option<&str>,
+) -> io::Result<Manifest> {
+    let mut manifest = Manifest::default();
+    for index in 0..archive.len() {
+        let mut entry = archive::read_entry(archive, index, password)?;
+        if entry.is_dir() || entry.name() == MANIFEST_ENTRY {
+            continue;
+        }
+        let name = entry.name().to_string();
+        let mut hasher = Sha256::new();
+        archive::copy_entry(&mut entry, &mut hasher)?;
+        manifest.files.insert(name, hex::encode(hasher.finalize()));
+    }
+    Ok(manifest)
+}
+
+/// Check the entries of an archive against its manifest. Returns the number of
+/// files checked; archives without a manifest and mismatches are errors.
+pub fn verify_archive<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    password: Option<&str>,
+) -> io::Result<usize> {
+    let expected = read_manifest(archive, password)?.ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("Archive has no content manifest ({})", MANIFEST_ENTRY),
+        )
+    })?;
+    let mismatches = compare(&expected, &hash_archive(archive, password)?);
+    if !mismatches.is_empty() {
+        return Err(mismatch_error(&mismatches));
+    }
+    Ok(expected.files.len())
+}
@@ -0,0 +1,248 @@
+use serde::Serialize;
+use std::io;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
+
+use crate::archive::{self, ArchiveError};
+use crate::cache::{self, CacheArea};
+use crate::commands::NamedCommand;
+use crate::selection::{Explanation, Selection};
+
+/// Version of the result document printed with `--json`. Bumped when fields
+/// are removed or change meaning; new fields may be added within a version.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Value of the `schema` field identifying pytron result documents
+pub const SCHEMA_NAME: &str = "pytron.result";
+
+static JSON: AtomicBool = AtomicBool::new(false);
+
+/// Switch to JSON output: the result document is the only thing on stdout,
+/// progress messages and the output of child processes go to stderr
+pub fn set_json(enabled: bool) {
+    JSON.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether `--json` was given
+pub fn is_json() -> bool {
+    JSON.load(Ordering::Relaxed)
+}
+
+/// Print a progress message: to stdout normally, to stderr with `--json`
+#[macro_export]
+macro_rules! status {
+    ($($arg:tt)*) => {
+        if $crate::output::is_json() {
+            eprintln!($($arg)*)
+        } else {
+            println!($($arg)*)
+        }
+    };
+}
+
+/// Where child processes (uv and the scripts it runs) write their stdout
+pub fn child_stdout() -> Stdio {
+    if is_json() {
+        io::stderr().into()
+    } else {
+        Stdio::inherit()
+    }
+}
+
+/// Stable identifier of an error for scripts, next to the human-readable message
+pub fn error_code(err: &io::Error) -> &'static str {
+    if let Some(archive_err) = archive::archive_error(err) {
+        return match archive_err {
+            ArchiveError::PasswordRequired { .. } => "password_required",
+            ArchiveError::WrongPassword { .. } => "wrong_password",
+            ArchiveError::Corrupt(_) => "corrupt_archive",
+            ArchiveError::NoMatchingIdentity { .. } => "no_matching_identity",
+        };
+    }
+    match err.kind() {
+        io::ErrorKind::NotFound => "not_found",
+        io::ErrorKind::PermissionDenied => "permission_denied",
+        io::ErrorKind::AlreadyExists => "already_exists",
+        io::ErrorKind::InvalidInput => "invalid_input",
+        io::ErrorKind::InvalidData => "invalid_data",
+        io::ErrorKind::Unsupported => "unsupported",
+        _ => "io_error",
+    }
+}
+
+/// Process exit code for an error: the archive error codes, otherwise 1
+pub fn exit_code(err: &io::Error) -> i32 {
+    archive::archive_error(err).map_or(1, |e| e.exit_code())
+}
+
+/// Whether the command did what was asked
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Ok,
+    Error,
+}
+
+/// The error of a failed command
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorInfo {
+    /// One of the identifiers returned by `error_code`
+    pub code: String,
+    pub message: String,
+}
+
+/// The document printed by `pytron --json <command>`
+#[derive(Debug, Clone, Serialize)]
+pub struct CommandResult {
+    pub schema: &'static str,
+    pub schema_version: u32,
+    pub pytron_version: &'static str,
+    /// Subcommand, e.g. "zip" or "uv install"
+    pub command: String,
+    pub status: Status,
+    /// Exit code of the pytron process
+    pub exit_code: i32,
+    pub duration_ms: u64,
+    /// Command-specific details, absent on errors
+    pub result: Option<serde_json::Value>,
+    pub error: Option<ErrorInfo>,
+}
+
+impl CommandResult {
+    fn new(command: &str, started: Instant) -> Self {
+        CommandResult {
+            schema: SCHEMA_NAME,
+            schema_version: SCHEMA_VERSION,
+            pytron_version: env!("CARGO_PKG_VERSION"),
+            command: command.to_string(),
+            status: Status::Ok,
+            exit_code: 0,
+            duration_ms: started.elapsed().as_millis() as u64,
+            result: None,
+            error: None,
+        }
+    }
+
+    /// A successful command with its details
+    pub fn ok<T: Serialize>(command: &str, started: Instant, exit_code: i32, result: &T) -> Self {
+        let mut document = CommandResult::new(command, started);
+        document.exit_code = exit_code;
+        match serde_json::to_value(result) {
+            Ok(value) => document.result = Some(value),
+            Err(err) => document.fail(&io::Error::other(err)),
+        }
+        document
+    }
+
+    /// A command that failed with `err`
+    pub fn error(command: &str, started: Instant, err: &io::Error) -> Self {
+        let mut document = CommandResult::new(command, started);
+        document.fail(err);
+        document
+    }
+
+    fn fail(&mut self, err: &io::Error) {
+        self.status = Status::Error;
+        self.exit_code = exit_code(err);
+        self.error = Some(ErrorInfo {
+            code: error_code(err).to_string(),
+            message: err.to_string(),
+        });
+    }
+
+    /// Print the document to stdout
+    pub fn print(&self) {
+        match serde_json::to_string_pretty(self) {
+            Ok(text) => println!("{}", text),
+            Err(err) => eprintln!("Error serializing result: {}", err),
+        }
+    }
+}
+
+/// Result of `pytron zip`
+#[derive(Debug, Clone, Serialize)]
+pub struct ZipReport {
+    pub archive: String,
+    /// Size of the archive file
+    pub size: u64,
+    pub sha256: String,
+    /// Number of project files packed
+    pub files: usize,
+    /// Uncompressed size of the packed project files
+    pub uncompressed_size: u64,
+    /// Number of paths left out by ignore rules
+    pub excluded: usize,
+    /// "none", "entries" (AES), "sealed" or "recipients"
+    pub encryption: &'static str,
+    pub recipients: usize,
+    /// Number of secrets stored with `--secrets`
+    pub secrets: usize,
+    /// SBOM entries embedded in `.pytron/`
+    pub sbom: Vec<String>,
+    /// Id of the key the archive was signed with by `--sign`
+    pub signed_by: Option<String>,
+}
+
+/// Result of `pytron zip --dry-run` and `--explain`
+#[derive(Debug, Clone, Serialize)]
+pub struct ZipPlan {
+    #[serde(flatten)]
+    pub selection: Selection,
+    pub explanation: Option<Explanation>,
+}
+
+/// Result of `pytron run`
+#[derive(Debug, Clone, Serialize)]
+pub struct RunReport {
+    pub target: String,
+    /// Whether the target is an archive rather than a script run directly
+    pub archive: bool,
+    /// Script that ran, or the `module:function` of a named command
+    pub entry_script: String,
+    /// Named command that selected the entry
+    pub command: Option<String>,
+    /// Exit code of the script, also the exit code of pytron
+    pub script_exit_code: i32,
+}
+
+/// Result of `pytron run --list`
+#[derive(Debug, Clone, Serialize)]
+pub struct CommandList {
+    pub archive: String,
+    pub commands: Vec<NamedCommand>,
+}
+
+/// Result of `pytron uv install`
+#[derive(Debug, Clone, Serialize)]
+pub struct UvInstallReport {
+    /// Installed uv binary
+    pub path: String,
+    /// SHA-256 of the installed binary
+    pub sha256: String,
+    /// "download" or "archive"
+    pub source: &'static str,
+    /// Release archive installed with `--from`
+    pub archive: Option<String>,
+}
+
+/// Result of `pytron cache info|prune|clear`
+#[derive(Debug, Clone, Serialize)]
+pub struct CacheReport {
+    /// Storage areas after the command ran
+    pub areas: Vec<CacheArea>,
+    pub total_size: u64,
+    /// Bytes freed by `prune` and `clear`
+    pub freed: Option<u64>,
+}
+
+impl CacheReport {
+    pub fn new(freed: Option<u64>) -> Self {
+        let areas = cache::cache_info();
+        CacheReport {
+            total_size: areas.iter().map(|a| a.size).sum(),
+            areas,
+            freed,
+        }
+    }
+}
@@ -0,0 +1,172 @@
+use std::env;
+use std::fs;
+use std::io::{self, IsTerminal, Read};
+use std::path::{Path, PathBuf};
+
+/// Environment variable read for the archive password when no other source is given
+pub const PASSWORD_ENV: &str = "PYTRON_PASSWORD";
+
+/// Where the AES password for `zip` and `run` comes from. At most one source may be set.
+#[derive(Clone, Default)]
+pub struct PasswordInput {
+    /// Given directly with `-p/--password` (visible in process lists)
+    pub literal: Option<String>,
+    /// `--password-file`: first line of the file
+    pub file: Option<PathBuf>,
+    /// `--password-env`: name of an environment variable holding the password
+    pub env: Option<String>,
+    /// `--ask-password`: prompt on the terminal without echo
+    pub ask: bool,
+}
+
+// Never print the password itself
+impl std::fmt::Debug for PasswordInput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PasswordInput")
+            .field("literal", &self.literal.as_ref().map(|_| "<redacted>"))
+            .field("file", &self.file)
+            .field("env", &self.env)
+            .field("ask", &self.ask)
+            .finish()
+    }
+}
+
+impl PasswordInput {
+    /// Whether no password option was given
+    pub fn is_empty(&self) -> bool {
+        self.sources() == 0
+    }
+
+    fn sources(&self) -> usize {
+        [
+            self.literal.is_some(),
+            self.file.is_some(),
+            self.env.is_some(),
+            self.ask,
+        ]
+        .iter()
+        .filter(|set| **set)
+        .count()
+    }
+}
+
+fn invalid_input(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, msg)
+}
+
+/// Resolve the password from the configured source, falling back to
+/// `PYTRON_PASSWORD`. Returns `None` when no password is configured.
+///
+/// With `confirm` set, an interactive prompt asks twice, as when creating an
+/// encrypted archive.
+pub fn resolve_password(input: &PasswordInput, confirm: bool) -> io::Result<Option<String>> {
+    if input.sources() > 1 {
+        return Err(invalid_input(
+            "Use only one of --password, --password-file, --password-env and --ask-password"
+                .to_string(),
+        ));
+    }
+
+    if let Some(password) = &input.literal {
+        eprintln!(
+            "Warning: a password given on the command line is visible to other users and kept in shell history. Prefer --password-file, --password-env or --ask-password."
+        );
+        return non_empty(password.clone(), "--password").map(Some);
+    }
+
+    if let Some(path) = &input.file {
+        let text = fs::read_to_string(path).map_err(|e| {
+            io::Error::new(
+                e.kind(),
+                format!("Failed to read password file {}: {}", path.display(), e),
+            )
+        })?;
+        let password = text.lines().next().unwrap_or("").to_string();
+        return non_empty(password, &format!("password file {}", path.display())).map(Some);
+    }
+
+    if let Some(var) = &input.env {
+        let password = env::var(var)
+            .map_err(|_| invalid_input(format!("Environment variable {} is not set", var)))?;
+        return non_empty(password, &format!("environment variable {}", var)).map(Some);
+    }
+
+    if input.ask {
+        return prompt_password(confirm).map(Some);
+    }
+
+    match env::var(PASSWORD_ENV) {
+        Ok(password) if !password.is_empty() => Ok(Some(password)),
+        _ => Ok(None),
+    }
+}
+
+fn non_empty(password: String, source: &str) -> io::Result<String> {
+    if password.is_empty() {
+        return Err(invalid_input(format!(
+            "The password from {} is empty",
+            source
+        )));
+    }
+    Ok(password)
+}
+
+/// Read the password from the terminal without echoing it
+fn prompt_password(confirm: bool) -> io::Result<String> {
+    if !io::stdin().is_terminal() {
+        return Err(invalid_input(
+            "--ask-password needs an interactive terminal; use --password-file or --password-env instead"
+                .to_string(),
+        ));
+    }
+
+    let password = rpassword::prompt_password("Archive password: ")?;
+    if password.is_empty() {
+        return Err(invalid_input("The password must not be empty".to_string()));
+    }
+    if confirm && rpassword::prompt_password("Repeat password: ")? != password {
+        return Err(invalid_input("Passwords do not match".to_string()));
+    }
+    Ok(password)
+}
+
+/// Prompt for the password of an encrypted archive when none was configured.
+/// Returns `None` without prompting if the archive has no encrypted entries or
+/// stdin is not a terminal.
+pub fn prompt_for_archive(path: &Path) -> io::Result<Option<String>> {
+    if !io::stdin().is_terminal() {
+        return Ok(None);
+    }
+    // Recipient-encrypted archives are opened with a private key, not a password
+    if crate::recipients::is_recipient_encrypted(path)? {
+        return Ok(None);
+    }
+    if crate::sealed::is_sealed(path)? {
+        eprintln!("{} is a sealed archive", path.display());
+        return prompt_password(false).map(Some);
+    }
+    let mut archive = crate::archive::open_archive(path)?;
+    let encrypted = crate::archive::encrypted_entries(&mut archive)?;
+    if encrypted.is_empty() {
+        // Secrets sealed with a password need it even when no entry is encrypted
+        let mut magic = [0u8; 8];
+        let sealed_secrets =
+            archive
+                .by_name(crate::secrets::SECRETS_ENTRY)
+                .is_ok_and(|mut entry| {
+                    entry.read_exact(&mut magic).is_ok() && &magic == crate::sealed::SEALED_MAGIC
+                });
+        if !sealed_secrets {
+            return Ok(None);
+        }
+        eprintln!("{} contains password-protected secrets", path.display());
+        return prompt_password(false).map(Some);
+    }
+    eprintln!(
+        "{} is encrypted ({} of {} entries)",
+        path.display(),
+        encrypted.len(),
+        archive.len()
+    );
+    prompt_password(false).map(Some)
+}
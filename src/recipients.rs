@@ -0,0 +1,186 @@
+use age::secrecy::ExposeSecret;
+use age::{x25519, Decryptor, Encryptor, Identity, IdentityFile};
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+use crate::archive::ArchiveError;
+use crate::get_pytron_home;
+use crate::signing::write_private_file;
+
+/// First line of every age-encrypted file
+pub const AGE_MAGIC: &[u8] = b"age-encryption.org/v1\n";
+
+/// Directory holding the private keys `pytron run` tries on recipient-encrypted archives
+pub fn get_keys_dir() -> PathBuf {
+    get_pytron_home().join("keys")
+}
+
+/// True if the file at `path` is encrypted to age recipients
+pub fn is_recipient_encrypted(path: &Path) -> io::Result<bool> {
+    let mut magic = [0u8; AGE_MAGIC.len()];
+    let mut file = File::open(path)?;
+    match file.read_exact(&mut magic) {
+        Ok(()) => Ok(magic == AGE_MAGIC),
+        Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => Ok(false),
+        Err(err) => Err(err),
+    }
+}
+
+fn invalid_input(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, msg)
+}
+
+/// Parse recipients given on the command line. Each value is either an
+/// `age1...` public key or a file with one public key per line (blank lines
+/// and `#` comments are ignored).
+pub fn parse_recipients(specs: &[String]) -> io::Result<Vec<x25519::Recipient>> {
+    let mut recipients = Vec::new();
+    for spec in specs {
+        if spec.starts_with("age1") {
+            recipients.push(
+                spec.parse()
+                    .map_err(|e| invalid_input(format!("Invalid recipient {}: {}", spec, e)))?,
+            );
+            continue;
+        }
+
+        let text = fs::read_to_string(spec).map_err(|e| {
+            io::Error::new(
+                e.kind(),
+                format!("Failed to read recipients file {}: {}", spec, e),
+            )
+        })?;
+        let before = recipients.len();
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            recipients.push(line.parse().map_err(|e| {
+                invalid_input(format!("Invalid recipient in {}: {}: {}", spec, line, e))
+            })?);
+        }
+        if recipients.len() == before {
+            return Err(invalid_input(format!("No recipients found in {}", spec)));
+        }
+    }
+    Ok(recipients)
+}
+
+/// Encrypt `data` so that any of `recipients` can decrypt it
+pub fn encrypt(data: &[u8], recipients: &[x25519::Recipient]) -> io::Result<Vec<u8>> {
+    let encryptor = Encryptor::with_recipients(recipients.iter().map(|r| r as &dyn age::Recipient))
+        .map_err(|e| invalid_input(format!("Cannot encrypt to these recipients: {}", e)))?;
+    let mut out = Vec::new();
+    let mut writer = encryptor.wrap_output(&mut out)?;
+    writer.write_all(data)?;
+    writer.finish()?;
+    Ok(out)
+}
+
+/// Load identities from `identity_files`, or from every key file in
+/// PYTRON_HOME/keys when none are given. Files in the keys directory that are
+/// not age identities (such as signing keys) are skipped.
+pub fn load_identities(identity_files: &[PathBuf]) -> io::Result<Vec<Box<dyn Identity>>> {
+    let explicit = !identity_files.is_empty();
+    let files = if explicit {
+        identity_files.to_vec()
+    } else {
+        match fs::read_dir(get_keys_dir()) {
+            Ok(entries) => {
+                let mut files = entries
+                    .filter_map(Result::ok)
+                    .map(|entry| entry.path())
+                    .filter(|path| {
+                        path.is_file() && path.extension().is_none_or(|ext| ext != "pub")
+                    })
+                    .collect::<Vec<_>>();
+                files.sort();
+                files
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(err) => return Err(err),
+        }
+    };
+
+    let mut identities = Vec::new();
+    for path in files {
+        let parsed = IdentityFile::from_file(path.to_string_lossy().to_string())
+            .map_err(|e| e.to_string())
+            .and_then(|file| file.into_identities().map_err(|e| e.to_string()));
+        match parsed {
+            Ok(mut found) => identities.append(&mut found),
+            Err(err) if explicit => {
+                return Err(invalid_input(format!(
+                    "Invalid identity file {}: {}",
+                    path.display(),
+                    err
+                )))
+            }
+            Err(_) => {}
+        }
+    }
+    Ok(identities)
+}
+
+/// Decrypt a recipient-encrypted archive with the given identity files, or the
+/// keys in PYTRON_HOME/keys
+pub fn decrypt(data: &[u8], identity_files: &[PathBuf]) -> io::Result<Vec<u8>> {
+    let searched = if identity_files.is_empty() {
+        get_keys_dir().display().to_string()
+    } else {
+        identity_files
+            .iter()
+            .map(|p| p.display().to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let identities = load_identities(identity_files)?;
+
+    let decryptor = Decryptor::new_buffered(data)
+        .map_err(|e| ArchiveError::Corrupt(format!("invalid encrypted archive: {}", e)))?;
+    let mut reader = decryptor
+        .decrypt(identities.iter().map(|i| i.as_ref() as &dyn Identity))
+        .map_err(|e| match e {
+            age::DecryptError::NoMatchingKeys => ArchiveError::NoMatchingIdentity { searched },
+            other => ArchiveError::Corrupt(format!("cannot decrypt archive: {}", other)),
+        })?;
+
+    let mut plain = Vec::new();
+    reader
+        .read_to_end(&mut plain)
+        .map_err(|e| ArchiveError::Corrupt(format!("encrypted archive is damaged: {}", e)))?;
+    Ok(plain)
+}
+
+/// Generate an X25519 key pair as `<stem>.key` (identity) and `<stem>.pub`
+/// (recipient). Returns the paths of both files.
+pub fn generate_identity(stem: &Path, overwrite: bool) -> io::Result<(PathBuf, PathBuf)> {
+    let private_path = stem.with_extension("key");
+    let public_path = stem.with_extension("pub");
+    if !overwrite {
+        for path in [&private_path, &public_path] {
+            if path.exists() {
+                return Err(io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    format!(
+                        "{} already exists, use --force to overwrite",
+                        path.display()
+                    ),
+                ));
+            }
+        }
+    }
+
+    let identity = x25519::Identity::generate();
+    let recipient = identity.to_public();
+    let private_text = format!(
+        "# public key: {}\n{}\n",
+        recipient,
+        identity.to_string().expose_secret()
+    );
+    write_private_file(&private_path, private_text.as_bytes())?;
+    fs::write(&public_path, format!("{}\n", recipient))?;
+
+    Ok((private_path, public_path))
+}
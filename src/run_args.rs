@@ -0,0 +1,312 @@
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+use crate::config::Config;
+use crate::password::PasswordInput;
+use crate::sandbox::SandboxOptions;
+
+/// Script run from an archive when no entry is selected
+pub const DEFAULT_ENTRY: &str = "main.py";
+
+/// A parsed `pytron run` command line.
+///
+/// `run` is parsed by hand so that unknown options before the archive reach
+/// `uv run` and everything after it reaches the script. The clap definition of
+/// `Commands::Run` only documents the options and is turned into the same
+/// structure, so both paths behave alike.
+#[derive(Debug, Clone)]
+pub struct RunArgs {
+    /// Archive or script to run
+    pub target: String,
+    /// Whether the target was given on the command line rather than defaulted
+    pub target_given: bool,
+    /// Script inside the archive, from `--entry` or `robot.zip:path`
+    pub entry: Option<String>,
+    pub uv_args: Vec<String>,
+    pub script_args: Vec<String>,
+    /// Whether the first script argument came before `--`, so that it may name
+    /// a command declared in the archive
+    pub may_name_command: bool,
+    pub password: PasswordInput,
+    pub require_signature: bool,
+    pub identities: Vec<PathBuf>,
+    pub sandbox: Option<SandboxOptions>,
+    /// `--uv-run-help`/`-hh`: show the help of `uv run` instead of running anything
+    pub uv_help: bool,
+    /// `--list`: print the named commands of the archive instead of running anything
+    pub list_commands: bool,
+    /// `--json`: print a result document instead of pytron's progress messages
+    pub json: bool,
+}
+
+impl Default for RunArgs {
+    fn default() -> Self {
+        RunArgs {
+            target: "robot.zip".to_string(),
+            target_given: false,
+            entry: None,
+            uv_args: Vec::new(),
+            script_args: Vec::new(),
+            may_name_command: false,
+            password: PasswordInput::default(),
+            require_signature: false,
+            identities: Vec::new(),
+            sandbox: None,
+            uv_help: false,
+            list_commands: false,
+            json: false,
+        }
+    }
+}
+
+impl RunArgs {
+    /// Whether the target is an archive rather than a script run directly
+    pub fn is_archive(&self) -> bool {
+        is_archive_path(&self.target)
+    }
+
+    /// The script to run from the archive
+    pub fn entry_script(&self) -> &str {
+        self.entry.as_deref().unwrap_or(DEFAULT_ENTRY)
+    }
+
+    /// Use the configured `[run]` settings for everything not given on the
+    /// command line. Lists from the command line replace the configured ones.
+    pub fn apply_config(&mut self, config: &Config) -> io::Result<()> {
+        if !self.target_given {
+            if let Some(archive) = config.get_str("run.archive") {
+                let entry = self.entry.take();
+                self.target = archive;
+                set_entry(self, entry)?;
+            }
+        }
+        if self.uv_args.is_empty() {
+            self.uv_args = config.get_list("run.uv_args").unwrap_or_default();
+        }
+        if self.identities.is_empty() {
+            self.identities = config
+                .get_list("run.identity")
+                .unwrap_or_default()
+                .into_iter()
+                .map(PathBuf::from)
+                .collect();
+        }
+        self.require_signature |= config.get_bool("run.require_signature");
+        config.apply_password("run", &mut self.password);
+        Ok(())
+    }
+}
+
+fn invalid_input(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, msg)
+}
+
+fn is_archive_path(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("zip"))
+}
+
+/// Split `robot.zip:tools/report.py` into the archive and the entry inside it.
+/// Only a colon directly after `.zip` separates the two, so drive letters such
+/// as `C:\robots\robot.zip` are left alone.
+pub fn split_target(target: &str) -> (String, Option<String>) {
+    let lower = target.to_ascii_lowercase();
+    match lower.rfind(".zip:") {
+        Some(pos) if pos + 5 < target.len() => (
+            target[..pos + 4].to_string(),
+            Some(target[pos + 5..].to_string()),
+        ),
+        _ => (target.to_string(), None),
+    }
+}
+
+/// Check that an entry names a file inside the archive: relative, without `..`
+pub fn validate_entry(entry: &str) -> io::Result<()> {
+    let valid = !entry.is_empty()
+        && Path::new(entry)
+            .components()
+            .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+    if valid {
+        Ok(())
+    } else {
+        Err(invalid_input(format!(
+            "Invalid entry `{}`: use a relative path inside the archive",
+            entry
+        )))
+    }
+}
+
+/// Options of `run` that take a value. Each may be given as `--flag value` or `--flag=value`.
+pub(crate) const VALUE_FLAGS: &[&str] = &[
+    "--password",
+    "-p",
+    "--password-file",
+    "--password-env",
+    "--identity",
+    "--entry",
+    "--allow-write",
+];
+
+/// Options of `run` that take no value
+pub(crate) const SWITCH_FLAGS: &[&str] = &[
+    "--ask-password",
+    "--sandbox",
+    "--no-network",
+    "--require-signature",
+    "--list",
+    "--json",
+];
+
+/// Options that still belong to pytron directly after the archive, before any
+/// script argument; every other option after it is passed on to the script
+pub(crate) const TRAILING_FLAGS: &[&str] = &["--list"];
+
+/// Parse the arguments following `pytron run`.
+///
+/// - Options before the archive are pytron's own; unknown ones are passed to `uv run`.
+/// - The first argument not starting with `-` is the archive (or script), optionally
+///   with the entry attached as `robot.zip:tools/report.py`.
+/// - Only `--list` is also recognised directly after the archive; any other
+///   argument after it goes to the script, where the first one may name a command
+///   declared in the archive.
+/// - After `--` every argument goes to the script and never names a command.
+pub fn parse_run_args(args: &[String]) -> io::Result<RunArgs> {
+    let mut run = RunArgs::default();
+    let mut target = None;
+    let mut entry = None;
+    let mut allow_write = Vec::new();
+    let mut no_network = false;
+    let mut sandbox = false;
+    let mut found_separator = false;
+
+    let mut i = 0;
+    while i < args.len() {
+        let arg = &args[i];
+        i += 1;
+
+        if found_separator {
+            run.script_args.push(arg.clone());
+            continue;
+        }
+        if arg == "--" {
+            found_separator = true;
+            continue;
+        }
+
+        // Accept `--flag=value` as well as `--flag value`
+        let (flag, inline_value) = match arg.split_once('=') {
+            Some((flag, value)) if VALUE_FLAGS.contains(&flag) => (flag, Some(value.to_string())),
+            _ => (arg.as_str(), None),
+        };
+        let ours = target.is_none()
+            || (run.script_args.is_empty() && TRAILING_FLAGS.contains(&flag));
+
+        if ours && VALUE_FLAGS.contains(&flag) {
+            let value = match inline_value {
+                Some(value) => value,
+                None if i < args.len() => {
+                    i += 1;
+                    args[i - 1].clone()
+                }
+                None => return Err(invalid_input(format!("`{}` requires a value", flag))),
+            };
+            match flag {
+                "--password-file" => run.password.file = Some(value.into()),
+                "--password-env" => run.password.env = Some(value),
+                "--identity" => run.identities.push(value.into()),
+                "--allow-write" => allow_write.push(PathBuf::from(value)),
+                "--entry" => {
+                    if entry.replace(value).is_some() {
+                        return Err(invalid_input("`--entry` given more than once".to_string()));
+                    }
+                }
+                _ => run.password.literal = Some(value),
+            }
+            continue;
+        }
+        if ours {
+            match flag {
+                "--ask-password" => {
+                    run.password.ask = true;
+                    continue;
+                }
+                "--sandbox" => {
+                    sandbox = true;
+                    continue;
+                }
+                "--no-network" => {
+                    no_network = true;
+                    continue;
+                }
+                "--require-signature" => {
+                    run.require_signature = true;
+                    continue;
+                }
+                "--list" => {
+                    run.list_commands = true;
+                    continue;
+                }
+                "--json" => {
+                    run.json = true;
+                    continue;
+                }
+                _ => {}
+            }
+        }
+
+        // After the archive, everything else goes to the script
+        if target.is_some() {
+            run.may_name_command |= run.script_args.is_empty();
+            run.script_args.push(arg.clone());
+        } else if !arg.starts_with('-') {
+            target = Some(arg.clone());
+        } else if arg == "--uv-run-help" || arg == "-hh" {
+            run.uv_help = true;
+        } else {
+            // Everything else before the archive is a uv flag
+            run.uv_args.push(arg.clone());
+        }
+    }
+
+    if !sandbox && (no_network || !allow_write.is_empty()) {
+        return Err(invalid_input(
+            "--allow-write and --no-network require --sandbox".to_string(),
+        ));
+    }
+    if sandbox {
+        run.sandbox = Some(SandboxOptions {
+            allow_write,
+            no_network,
+        });
+    }
+    if let Some(target) = target {
+        run.target = target;
+        run.target_given = true;
+    }
+    set_entry(&mut run, entry)?;
+    Ok(run)
+}
+
+/// Combine an entry from `--entry` with one attached to the target and check it
+pub fn set_entry(run: &mut RunArgs, entry: Option<String>) -> io::Result<()> {
+    let (target, attached) = split_target(&run.target);
+    if attached.is_some() && entry.is_some() {
+        return Err(invalid_input(format!(
+            "Select the entry either with `--entry` or as `{}:<path>`, not both",
+            target
+        )));
+    }
+    run.target = target;
+    run.entry = attached.or(entry);
+    if let Some(entry) = &run.entry {
+        if !run.is_archive() {
+            return Err(invalid_input(format!(
+                "An entry can only be selected inside a .zip archive, not in {}",
+                run.target
+            )));
+        }
+        validate_entry(entry)?;
+    }
+    Ok(())
+}
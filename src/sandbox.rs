@@ -0,0 +1,741 @@
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::trust::{self, PolicyScope};
+use crate::{get_uv_command, get_uv_path, output, recipients, signing, status, UvStateDir};
+
+/// Options for `pytron run --sandbox`
+#[derive(Debug, Clone, Default)]
+pub struct SandboxOptions {
+    /// Paths the script may write to besides the extraction directory
+    pub allow_write: Vec<PathBuf>,
+    /// Run the script without network access
+    pub no_network: bool,
+}
+
+/// Which sandboxing mechanisms the running kernel offers
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SandboxSupport {
+    /// Unprivileged user namespaces, needed for the mount and network namespaces
+    pub user_namespaces: bool,
+    /// Landlock ABI version, if Landlock is enabled
+    pub landlock_abi: Option<i32>,
+    /// Seccomp filters
+    pub seccomp: bool,
+}
+
+/// Detect the sandboxing mechanisms available on this host
+pub fn probe() -> SandboxSupport {
+    #[cfg(target_os = "linux")]
+    {
+        linux::probe()
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        SandboxSupport::default()
+    }
+}
+
+/// Paths the sandboxed script may write to: the extraction directory and the
+/// paths allowed with `--allow-write`. uv's shared cache, Python installs and
+/// tools are not among them. A missing allowed path is an error.
+pub fn writable_paths(workdir: &Path, options: &SandboxOptions) -> io::Result<Vec<PathBuf>> {
+    let mut paths = vec![workdir.to_path_buf()];
+    for path in &options.allow_write {
+        if !path.exists() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("--allow-write path {} does not exist", path.display()),
+            ));
+        }
+        paths.push(path.clone());
+    }
+    paths
+        .iter()
+        .map(|path| path.canonicalize())
+        .collect::<io::Result<Vec<_>>>()
+}
+
+/// Paths that stay visible when `$HOME` is hidden: the uv binary, the managed
+/// Python installs (read-only) and every writable path inside `home`. The rest
+/// of PYTRON_HOME, including the private keys and the trust policy, is hidden.
+/// Paths nested in another kept path are dropped.
+pub fn kept_home_paths(home: &Path, writable: &[PathBuf]) -> Vec<PathBuf> {
+    let mut candidates: Vec<PathBuf> = [get_uv_path(), UvStateDir::PythonInstalls.path()]
+        .iter()
+        .filter_map(|path| path.canonicalize().ok())
+        .collect();
+    candidates.extend(writable.iter().cloned());
+
+    let mut kept: Vec<PathBuf> = Vec::new();
+    candidates.sort();
+    for path in candidates {
+        if path.starts_with(home) && !kept.iter().any(|k| path.starts_with(k)) {
+            kept.push(path);
+        }
+    }
+    kept
+}
+
+/// Files and directories in PYTRON_HOME a sandboxed script must never read:
+/// the private keys, the trusted keys and the user's trust policy. They are
+/// covered with empty ones wherever they are still visible.
+pub fn hidden_paths() -> Vec<PathBuf> {
+    [
+        recipients::get_keys_dir(),
+        signing::get_trusted_keys_dir(),
+        trust::policy_path(PolicyScope::User),
+    ]
+    .iter()
+    .filter_map(|path| path.canonicalize().ok())
+    .collect()
+}
+
+/// Install the dependencies of the project in `workdir` into `workdir/.venv`
+/// before the sandbox is entered. Inside the sandbox uv's shared state is
+/// read-only, so uv runs there with `--no-sync` and a private cache. Packages
+/// are copied rather than linked, so the script cannot modify the shared cache
+/// through them either. As this runs outside the sandbox, only the wheels
+/// pinned in the archive's `uv.lock` are installed: nothing is built, neither
+/// the project nor its dependencies, and the lock is never re-resolved.
+/// Returns whether an environment was prepared.
+pub fn prepare(workdir: &Path, options: &SandboxOptions) -> io::Result<bool> {
+    if !workdir.join("pyproject.toml").is_file() {
+        return Ok(false);
+    }
+    if !workdir.join("uv.lock").is_file() {
+        return Err(io::Error::other(
+            "Preparing the sandbox environment failed: the archive has a pyproject.toml \
+             but no uv.lock, and the sandbox only installs locked dependencies",
+        ));
+    }
+    let mut cmd = get_uv_command();
+    cmd.args(sync_args(options));
+    status!("Preparing the sandbox environment: uv sync");
+    let status = cmd
+        .current_dir(workdir)
+        .stdout(output::child_stdout())
+        .status()?;
+    if !status.success() {
+        return Err(io::Error::other(format!(
+            "Preparing the sandbox environment failed: uv sync exited with {}. \
+             The sandbox only installs prebuilt wheels from uv.lock, so a dependency \
+             without a wheel for this platform, or a path or git dependency, cannot be \
+             installed",
+            status
+        )));
+    }
+    Ok(true)
+}
+
+/// Arguments of the `uv sync` that prepares the sandbox environment
+fn sync_args(options: &SandboxOptions) -> Vec<&'static str> {
+    let mut args = vec![
+        "sync",
+        "--frozen",
+        "--no-build",
+        "--no-install-project",
+        "--link-mode",
+        "copy",
+    ];
+    if options.no_network {
+        args.push("--offline");
+    }
+    args
+}
+
+/// Configure `cmd` to run inside the sandbox. The script may only write to
+/// `workdir` and the writable paths, the rest of `$HOME` is replaced by an
+/// empty directory and the network is optionally disabled. Mechanisms the
+/// kernel lacks are skipped with a warning.
+pub fn apply(cmd: &mut Command, workdir: &Path, options: &SandboxOptions) -> io::Result<()> {
+    #[cfg(target_os = "linux")]
+    {
+        linux::apply(cmd, workdir, options)
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = (cmd, workdir, options);
+        eprintln!("Warning: --sandbox is only supported on Linux, running without a sandbox");
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::collections::BTreeMap;
+    use std::ffi::CString;
+    use std::io;
+    use std::os::unix::ffi::OsStrExt;
+    use std::os::unix::process::CommandExt;
+    use std::path::{Path, PathBuf};
+    use std::process::Command;
+
+    use landlock::{
+        path_beneath_rules, AccessFs, Ruleset, RulesetAttr, RulesetCreatedAttr, RulesetStatus, ABI,
+    };
+    use seccompiler::{BpfProgram, SeccompAction, SeccompFilter, TargetArch};
+
+    use crate::{status, UvStateDir};
+
+    use super::{
+        hidden_paths, kept_home_paths, writable_paths, SandboxOptions, SandboxSupport,
+    };
+
+    /// Newest Landlock ABI requested; older kernels enforce what they support
+    const LANDLOCK_ABI: ABI = ABI::V5;
+
+    /// Syscalls a sandboxed script has no business making
+    const DENIED_SYSCALLS: &[libc::c_long] = &[
+        libc::SYS_ptrace,
+        libc::SYS_process_vm_readv,
+        libc::SYS_process_vm_writev,
+        libc::SYS_mount,
+        libc::SYS_umount2,
+        libc::SYS_pivot_root,
+        libc::SYS_chroot,
+        libc::SYS_unshare,
+        libc::SYS_setns,
+        libc::SYS_swapon,
+        libc::SYS_swapoff,
+        libc::SYS_reboot,
+        libc::SYS_kexec_load,
+        libc::SYS_init_module,
+        libc::SYS_finit_module,
+        libc::SYS_delete_module,
+        libc::SYS_bpf,
+        libc::SYS_perf_event_open,
+        libc::SYS_userfaultfd,
+        libc::SYS_keyctl,
+        libc::SYS_add_key,
+        libc::SYS_request_key,
+        libc::SYS_acct,
+        libc::SYS_quotactl,
+    ];
+
+    fn read_flag(path: &str) -> Option<i64> {
+        std::fs::read_to_string(path).ok()?.trim().parse().ok()
+    }
+
+    pub fn probe() -> SandboxSupport {
+        let user_namespaces = read_flag("/proc/sys/user/max_user_namespaces").unwrap_or(0) > 0
+            && read_flag("/proc/sys/kernel/unprivileged_userns_clone").unwrap_or(1) == 1
+            && read_flag("/proc/sys/kernel/apparmor_restrict_unprivileged_userns").unwrap_or(0)
+                == 0;
+
+        // landlock_create_ruleset(NULL, 0, LANDLOCK_CREATE_RULESET_VERSION) returns the ABI version
+        let abi = unsafe {
+            libc::syscall(
+                libc::SYS_landlock_create_ruleset,
+                std::ptr::null::<libc::c_void>(),
+                0usize,
+                1u32,
+            )
+        };
+        let landlock_abi = (abi > 0).then_some(abi as i32);
+
+        let seccomp = std::fs::read_to_string("/proc/self/status")
+            .map(|status| status.lines().any(|line| line.starts_with("Seccomp:")))
+            .unwrap_or(false);
+
+        SandboxSupport {
+            user_namespaces,
+            landlock_abi,
+            seccomp,
+        }
+    }
+
+    fn cstring(path: &Path) -> io::Result<CString> {
+        CString::new(path.as_os_str().as_bytes()).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Path {} contains a NUL byte", path.display()),
+            )
+        })
+    }
+
+    /// A path under `$HOME` that is bind-mounted back on top of the empty home
+    struct KeptPath {
+        /// Directory containing the path
+        parent: CString,
+        /// File name of the path, the bind mount source relative to `parent`
+        name: CString,
+        /// Directories to create in the empty home, starting below `$HOME`
+        dirs: Vec<CString>,
+        /// The path itself, the bind mount target
+        target: CString,
+        is_dir: bool,
+        /// Remount read-only, with these flags the source mount already has,
+        /// as a user namespace may not clear them
+        read_only: Option<libc::c_ulong>,
+    }
+
+    /// A secret in PYTRON_HOME covered with an empty directory or `/dev/null`
+    struct CoveredPath {
+        path: CString,
+        is_dir: bool,
+        /// Whether the empty home already hides the path
+        hidden_by_home: bool,
+    }
+
+    /// Everything the child needs, prepared before fork so that the pre_exec
+    /// hook only makes system calls
+    struct Plan {
+        namespaces: libc::c_int,
+        uid_map: Vec<u8>,
+        gid_map: Vec<u8>,
+        home: Option<CString>,
+        kept: Vec<KeptPath>,
+        /// Descriptors of the kept parents, filled in by the child
+        kept_fds: Vec<libc::c_int>,
+        covered: Vec<CoveredPath>,
+        workdir: CString,
+        ruleset: Option<landlock::RulesetCreated>,
+        filter: Option<BpfProgram>,
+    }
+
+    /// Write a warning from the child without allocating
+    fn warn(message: &[u8]) {
+        unsafe {
+            libc::write(2, message.as_ptr().cast(), message.len());
+        }
+    }
+
+    fn write_proc_file(path: &[u8], content: &[u8]) -> io::Result<()> {
+        // `path` is NUL terminated
+        let fd = unsafe { libc::open(path.as_ptr().cast(), libc::O_WRONLY | libc::O_CLOEXEC) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let written = unsafe { libc::write(fd, content.as_ptr().cast(), content.len()) };
+        unsafe { libc::close(fd) };
+        if written != content.len() as isize {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    /// Bring up the loopback interface of a fresh network namespace, so that
+    /// scripts can still talk to themselves over localhost
+    fn loopback_up() {
+        unsafe {
+            let sock = libc::socket(libc::AF_INET, libc::SOCK_DGRAM | libc::SOCK_CLOEXEC, 0);
+            if sock < 0 {
+                return;
+            }
+            let mut req: libc::ifreq = std::mem::zeroed();
+            req.ifr_name[0] = b'l' as libc::c_char;
+            req.ifr_name[1] = b'o' as libc::c_char;
+            if libc::ioctl(sock, libc::SIOCGIFFLAGS as _, &mut req) == 0 {
+                req.ifr_ifru.ifru_flags |= libc::IFF_UP as libc::c_short;
+                libc::ioctl(sock, libc::SIOCSIFFLAGS as _, &req);
+            }
+            libc::close(sock);
+        }
+    }
+
+    fn enter_namespaces(plan: &Plan) -> io::Result<bool> {
+        if plan.namespaces == 0 {
+            return Ok(false);
+        }
+        if unsafe { libc::unshare(plan.namespaces) } != 0 {
+            warn(b"Warning: sandbox: cannot create user namespaces, $HOME stays visible and the network is not isolated\n");
+            return Ok(false);
+        }
+        // Keep our own uid and gid inside the namespace
+        write_proc_file(b"/proc/self/setgroups\0", b"deny")?;
+        write_proc_file(b"/proc/self/uid_map\0", &plan.uid_map)?;
+        write_proc_file(b"/proc/self/gid_map\0", &plan.gid_map)?;
+        if plan.namespaces & libc::CLONE_NEWNET != 0 {
+            loopback_up();
+        }
+        Ok(true)
+    }
+
+    /// Keep the mounts below from propagating back to the host
+    fn make_mounts_private(plan: &Plan) -> io::Result<bool> {
+        let root = b"/\0";
+        if unsafe {
+            libc::mount(
+                std::ptr::null(),
+                root.as_ptr().cast(),
+                std::ptr::null(),
+                libc::MS_REC | libc::MS_PRIVATE,
+                std::ptr::null(),
+            )
+        } == 0
+        {
+            return Ok(true);
+        }
+        if !plan.covered.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "Cannot make mounts private to hide the keys in PYTRON_HOME",
+            ));
+        }
+        warn(b"Warning: sandbox: cannot make mounts private, $HOME stays visible\n");
+        Ok(false)
+    }
+
+    /// Replace `$HOME` with an empty directory and bind the kept paths back.
+    /// Returns whether `$HOME` is hidden.
+    fn hide_home(plan: &mut Plan) -> io::Result<bool> {
+        let Some(home) = &plan.home else {
+            return Ok(false);
+        };
+
+        // Bind mounts need a source in this mount namespace, so the kept paths
+        // are opened here before the empty home covers them
+        for (kept, fd) in plan.kept.iter().zip(plan.kept_fds.iter_mut()) {
+            *fd = unsafe {
+                libc::open(
+                    kept.parent.as_ptr(),
+                    libc::O_PATH | libc::O_DIRECTORY | libc::O_CLOEXEC,
+                )
+            };
+            if *fd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+
+        let tmpfs = b"tmpfs\0";
+        let options = b"mode=0755\0";
+        if unsafe {
+            libc::mount(
+                tmpfs.as_ptr().cast(),
+                home.as_ptr(),
+                tmpfs.as_ptr().cast(),
+                libc::MS_NOSUID | libc::MS_NODEV,
+                options.as_ptr().cast(),
+            )
+        } != 0
+        {
+            warn(b"Warning: sandbox: cannot mount an empty $HOME, it stays visible\n");
+            return Ok(false);
+        }
+
+        // Put uv, the Python installs and the allowed paths back in place
+        for (kept, fd) in plan.kept.iter().zip(plan.kept_fds.iter()) {
+            for dir in &kept.dirs {
+                unsafe { libc::mkdir(dir.as_ptr(), 0o755) };
+            }
+            if kept.is_dir {
+                unsafe { libc::mkdir(kept.target.as_ptr(), 0o755) };
+            } else {
+                let file = unsafe {
+                    libc::open(
+                        kept.target.as_ptr(),
+                        libc::O_WRONLY | libc::O_CREAT | libc::O_CLOEXEC,
+                        0o644,
+                    )
+                };
+                if file >= 0 {
+                    unsafe { libc::close(file) };
+                }
+            }
+            let bound = unsafe {
+                libc::fchdir(*fd) == 0
+                    && libc::mount(
+                        kept.name.as_ptr(),
+                        kept.target.as_ptr(),
+                        std::ptr::null(),
+                        libc::MS_BIND | libc::MS_REC,
+                        std::ptr::null(),
+                    ) == 0
+            };
+            if !bound {
+                return Err(io::Error::last_os_error());
+            }
+            unsafe { libc::close(*fd) };
+            if let Some(flags) = kept.read_only {
+                let remounted = unsafe {
+                    libc::mount(
+                        std::ptr::null(),
+                        kept.target.as_ptr(),
+                        std::ptr::null(),
+                        libc::MS_BIND | libc::MS_REMOUNT | libc::MS_RDONLY | flags,
+                        std::ptr::null(),
+                    )
+                };
+                if remounted != 0 {
+                    warn(b"Warning: sandbox: cannot make uv's Python installs read-only\n");
+                }
+            }
+        }
+        Ok(true)
+    }
+
+    /// Cover the keys and the trust policy wherever they are still visible.
+    /// Unlike hiding `$HOME` this fails closed.
+    fn cover_secrets(plan: &Plan, home_hidden: bool) -> io::Result<()> {
+        let tmpfs = b"tmpfs\0";
+        let options = b"mode=0\0";
+        let dev_null = b"/dev/null\0";
+        for covered in &plan.covered {
+            if home_hidden && covered.hidden_by_home {
+                continue;
+            }
+            let result = unsafe {
+                if covered.is_dir {
+                    libc::mount(
+                        tmpfs.as_ptr().cast(),
+                        covered.path.as_ptr(),
+                        tmpfs.as_ptr().cast(),
+                        libc::MS_NOSUID | libc::MS_NODEV | libc::MS_NOEXEC,
+                        options.as_ptr().cast(),
+                    )
+                } else {
+                    libc::mount(
+                        dev_null.as_ptr().cast(),
+                        covered.path.as_ptr(),
+                        std::ptr::null(),
+                        libc::MS_BIND,
+                        std::ptr::null(),
+                    )
+                }
+            };
+            if result != 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        Ok(())
+    }
+
+    fn restrict(plan: &mut Plan) -> io::Result<()> {
+        // The working directory was set before the mounts, enter it again
+        if unsafe { libc::chdir(plan.workdir.as_ptr()) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        match plan.ruleset.take().map(|ruleset| ruleset.restrict_self()) {
+            Some(Ok(status)) => match status.ruleset {
+                RulesetStatus::FullyEnforced => {}
+                RulesetStatus::PartiallyEnforced => warn(
+                    b"Warning: sandbox: Landlock is only partially supported, some writes are not restricted\n",
+                ),
+                RulesetStatus::NotEnforced => warn(
+                    b"Warning: sandbox: Landlock is not available, writes are not restricted\n",
+                ),
+            },
+            Some(Err(_)) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::PermissionDenied,
+                    "Failed to apply the Landlock ruleset",
+                ))
+            }
+            None => {}
+        }
+
+        if let Some(filter) = &plan.filter {
+            if seccompiler::apply_filter(filter).is_err() {
+                warn(b"Warning: sandbox: cannot install the seccomp filter\n");
+            }
+        }
+        Ok(())
+    }
+
+    fn landlock_ruleset(writable: &[PathBuf]) -> io::Result<landlock::RulesetCreated> {
+        let to_io = |err: landlock::RulesetError| io::Error::other(err.to_string());
+        Ruleset::default()
+            .handle_access(AccessFs::from_write(LANDLOCK_ABI))
+            .map_err(to_io)?
+            .create()
+            .map_err(to_io)?
+            .add_rules(path_beneath_rules(
+                writable,
+                AccessFs::from_write(LANDLOCK_ABI),
+            ))
+            .map_err(to_io)?
+            // Terminals and /dev/null stay writable
+            .add_rules(path_beneath_rules(["/dev"], AccessFs::WriteFile))
+            .map_err(to_io)
+    }
+
+    fn seccomp_filter() -> Option<BpfProgram> {
+        let arch = TargetArch::try_from(std::env::consts::ARCH).ok()?;
+        let rules = DENIED_SYSCALLS
+            .iter()
+            .map(|syscall| (*syscall, Vec::new()))
+            .collect::<BTreeMap<_, _>>();
+        SeccompFilter::new(
+            rules,
+            SeccompAction::Allow,
+            SeccompAction::Errno(libc::EPERM as u32),
+            arch,
+        )
+        .ok()?
+        .try_into()
+        .ok()
+    }
+
+    /// Mount flags of the filesystem holding `path` that a remount must keep
+    fn locked_flags(path: &Path) -> io::Result<libc::c_ulong> {
+        let path = cstring(path)?;
+        let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+        if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let mut flags = 0;
+        for (st, ms) in [
+            (libc::ST_NOSUID, libc::MS_NOSUID),
+            (libc::ST_NODEV, libc::MS_NODEV),
+            (libc::ST_NOEXEC, libc::MS_NOEXEC),
+            (libc::ST_NOATIME, libc::MS_NOATIME),
+            (libc::ST_NODIRATIME, libc::MS_NODIRATIME),
+            (libc::ST_RELATIME, libc::MS_RELATIME),
+        ] {
+            if stat.f_flag & st != 0 {
+                flags |= ms;
+            }
+        }
+        Ok(flags)
+    }
+
+    fn kept_path(home: &Path, path: &Path, writable: &[PathBuf]) -> io::Result<KeptPath> {
+        let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Cannot keep {} visible in the sandbox", path.display()),
+            ));
+        };
+        let mut dirs = Vec::new();
+        let mut current = home.to_path_buf();
+        for component in parent.strip_prefix(home).unwrap_or(parent).components() {
+            current.push(component);
+            dirs.push(cstring(&current)?);
+        }
+        Ok(KeptPath {
+            parent: cstring(parent)?,
+            name: cstring(Path::new(name))?,
+            dirs,
+            target: cstring(path)?,
+            is_dir: path.is_dir(),
+            read_only: if writable.iter().any(|w| path.starts_with(w)) {
+                None
+            } else {
+                Some(locked_flags(path)?)
+            },
+        })
+    }
+
+    pub fn apply(cmd: &mut Command, workdir: &Path, options: &SandboxOptions) -> io::Result<()> {
+        let support = super::probe();
+        let writable = writable_paths(workdir, options)?;
+        let uid = unsafe { libc::getuid() };
+        let gid = unsafe { libc::getgid() };
+
+        let mut namespaces = 0;
+        let mut home = None;
+        let mut kept = Vec::new();
+        let mut kept_paths = Vec::new();
+        if support.user_namespaces {
+            namespaces = libc::CLONE_NEWUSER | libc::CLONE_NEWNS;
+            if options.no_network {
+                namespaces |= libc::CLONE_NEWNET;
+            }
+            match dirs::home_dir().and_then(|h| h.canonicalize().ok()) {
+                Some(h) if !pytron_home_covers(&h) => {
+                    kept_paths = kept_home_paths(&h, &writable);
+                    for path in &kept_paths {
+                        kept.push(kept_path(&h, path, &writable)?);
+                    }
+                    home = Some(h);
+                }
+                _ => eprintln!(
+                    "Warning: sandbox: PYTRON_HOME contains the home directory, $HOME stays visible"
+                ),
+            }
+        } else {
+            eprintln!(
+                "Warning: sandbox: unprivileged user namespaces are disabled, $HOME and the keys in PYTRON_HOME stay visible"
+            );
+            if options.no_network {
+                eprintln!("Warning: sandbox: cannot isolate the network, only uv runs offline");
+            }
+        }
+
+        let mut covered = Vec::new();
+        if namespaces != 0 {
+            for path in hidden_paths() {
+                let hidden_by_home = home.as_ref().is_some_and(|h| path.starts_with(h))
+                    && !kept_paths.iter().any(|k| path.starts_with(k));
+                covered.push(CoveredPath {
+                    path: cstring(&path)?,
+                    is_dir: path.is_dir(),
+                    hidden_by_home,
+                });
+            }
+        }
+
+        let ruleset = match support.landlock_abi {
+            Some(_) => Some(landlock_ruleset(&writable)?),
+            None => {
+                eprintln!("Warning: sandbox: Landlock is not available, writes are not restricted");
+                None
+            }
+        };
+        let filter = if support.seccomp {
+            let filter = seccomp_filter();
+            if filter.is_none() {
+                eprintln!(
+                    "Warning: sandbox: no seccomp filter for {}, system calls are not filtered",
+                    std::env::consts::ARCH
+                );
+            }
+            filter
+        } else {
+            eprintln!("Warning: sandbox: seccomp is not available, system calls are not filtered");
+            None
+        };
+
+        status!(
+            "Sandbox: writes limited to {}",
+            writable
+                .iter()
+                .map(|p| p.display().to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+
+        let mut plan = Plan {
+            namespaces,
+            uid_map: format!("{} {} 1", uid, uid).into_bytes(),
+            gid_map: format!("{} {} 1", gid, gid).into_bytes(),
+            home: home.as_deref().map(cstring).transpose()?,
+            kept_fds: vec![-1; kept.len()],
+            kept,
+            covered,
+            workdir: cstring(&workdir.canonicalize()?)?,
+            ruleset,
+            filter,
+        };
+
+        cmd.current_dir(workdir);
+        // Temporary files go to the extraction directory as well
+        let tmp = workdir.join(".tmp");
+        std::fs::create_dir_all(&tmp)?;
+        cmd.env("TMPDIR", &tmp);
+        // uv's shared cache and tools are read-only, give uv private ones
+        cmd.env(UvStateDir::Cache.env_var(), workdir.join(".uv-cache"));
+        cmd.env(UvStateDir::Tools.env_var(), workdir.join(".uv-tools"));
+
+        unsafe {
+            cmd.pre_exec(move || {
+                if enter_namespaces(&plan)? && make_mounts_private(&plan)? {
+                    let home_hidden = hide_home(&mut plan)?;
+                    cover_secrets(&plan, home_hidden)?;
+                }
+                restrict(&mut plan)
+            });
+        }
+        Ok(())
+    }
+
+    /// Hiding `$HOME` would hide pytron itself when PYTRON_HOME is `$HOME` or above it
+    fn pytron_home_covers(home: &Path) -> bool {
+        let pytron_home = crate::get_pytron_home();
+        let pytron_home = pytron_home.canonicalize().unwrap_or(pytron_home);
+        home.starts_with(&pytron_home)
+    }
+}
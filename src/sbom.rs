@@ -0,0 +1,618 @@
+use clap::ValueEnum;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::env;
+use std::fs;
+use std::io::{self, Read, Seek};
+use std::path::Path;
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+use zip::ZipArchive;
+
+use crate::archive::{self, ArchiveError};
+use crate::manifest::{self, Manifest};
+use crate::get_uv_path;
+
+/// Prefix of the entries pytron reserves for its own metadata
+pub const RESERVED_PREFIX: &str = ".pytron/";
+
+/// SBOM document formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SbomFormat {
+    /// CycloneDX 1.5 JSON
+    Cyclonedx,
+    /// SPDX 2.3 JSON
+    Spdx,
+}
+
+impl SbomFormat {
+    /// Archive entry an embedded SBOM of this format is stored in
+    pub fn entry_name(&self) -> &'static str {
+        match self {
+            SbomFormat::Cyclonedx => ".pytron/sbom.cdx.json",
+            SbomFormat::Spdx => ".pytron/sbom.spdx.json",
+        }
+    }
+}
+
+/// A package pinned in `uv.lock`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockedPackage {
+    pub name: String,
+    pub version: String,
+    /// Index the package is resolved from, if it comes from a registry
+    pub registry: Option<String>,
+    /// Source distribution URL
+    pub download_url: Option<String>,
+    /// SHA-256 of the source distribution, or of the first wheel
+    pub sha256: Option<String>,
+    /// Names of the packages it depends on
+    pub dependencies: Vec<String>,
+    /// The project itself (`source = { virtual = "." }` or editable)
+    pub is_root: bool,
+}
+
+/// Everything an SBOM is built from, collected from the packaged files
+#[derive(Debug, Clone, Default)]
+pub struct SbomInput {
+    pub name: String,
+    pub version: Option<String>,
+    /// Packaged files and their SHA-256 digests, without pytron's own entries
+    pub files: Vec<(String, String)>,
+    pub packages: Vec<LockedPackage>,
+    /// Interpreter version pinned in `.python-version`
+    pub python_version: Option<String>,
+    /// `requires-python` from pyproject.toml or uv.lock
+    pub requires_python: Option<String>,
+    /// Version of the uv the archive was built with, if known
+    pub uv_version: Option<String>,
+}
+
+fn invalid(file: &str, err: impl std::fmt::Display) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Failed to parse {}: {}", file, err),
+    )
+}
+
+/// Read the packages of a `uv.lock` file
+pub fn parse_uv_lock(text: &str) -> io::Result<(Vec<LockedPackage>, Option<String>)> {
+    let lock: toml::Table = toml::from_str(text).map_err(|e| invalid("uv.lock", e))?;
+    let requires_python = lock
+        .get("requires-python")
+        .and_then(|v| v.as_str())
+        .map(str::to_string);
+
+    let mut packages = Vec::new();
+    let entries = lock.get("package").and_then(|v| v.as_array());
+    for entry in entries.into_iter().flatten() {
+        let field = |table: Option<&toml::Value>, key: &str| {
+            table
+                .and_then(|t| t.get(key))
+                .and_then(|v| v.as_str())
+                .map(str::to_string)
+        };
+        let (Some(name), Some(version)) =
+            (field(Some(entry), "name"), field(Some(entry), "version"))
+        else {
+            continue;
+        };
+        let source = entry.get("source");
+        let sdist = entry.get("sdist");
+        let first_wheel = entry
+            .get("wheels")
+            .and_then(|v| v.as_array())
+            .and_then(|wheels| wheels.first());
+        let sha256 = field(sdist, "hash")
+            .or_else(|| field(first_wheel, "hash"))
+            .and_then(|hash| hash.strip_prefix("sha256:").map(str::to_string));
+        let dependencies = entry
+            .get("dependencies")
+            .and_then(|v| v.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|dep| field(Some(dep), "name"))
+            .collect();
+        let is_root = ["virtual", "editable"]
+            .iter()
+            .any(|kind| field(source, kind).as_deref() == Some("."));
+
+        packages.push(LockedPackage {
+            name,
+            version,
+            registry: field(source, "registry"),
+            download_url: field(sdist, "url"),
+            sha256,
+            dependencies,
+            is_root,
+        });
+    }
+    Ok((packages, requires_python))
+}
+
+/// Version of the uv installed in PYTRON_HOME, as reported by `uv --version`.
+/// `None` if uv is missing or does not answer.
+fn installed_uv_version() -> Option<String> {
+    Command::new(get_uv_path())
+        .arg("--version")
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| {
+            // "uv 0.7.2 (481d05d8d 2025-04-30)"
+            String::from_utf8_lossy(&output.stdout)
+                .split_whitespace()
+                .nth(1)
+                .map(str::to_string)
+        })
+}
+
+/// Version of uv recorded in an SBOM embedded when the archive was built
+fn embedded_uv_version(read: &impl Fn(&str) -> io::Result<Option<String>>) -> io::Result<Option<String>> {
+    for format in [SbomFormat::Cyclonedx, SbomFormat::Spdx] {
+        let Some(text) = read(format.entry_name())? else {
+            continue;
+        };
+        let document: Value =
+            serde_json::from_str(&text).map_err(|e| invalid(format.entry_name(), e))?;
+        let version = match format {
+            SbomFormat::Cyclonedx => document["metadata"]["properties"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .find(|property| property["name"] == "pytron:uv-version")
+                .and_then(|property| property["value"].as_str())
+                .map(str::to_string),
+            SbomFormat::Spdx => document["creationInfo"]["creators"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+                .find_map(|creator| creator.strip_prefix("Tool: uv-"))
+                .map(str::to_string),
+        };
+        if version.is_some() {
+            return Ok(version);
+        }
+    }
+    Ok(None)
+}
+
+/// Collect the SBOM input from packaged files. `read` returns the contents of
+/// a packaged file by name, or None if it is not in the archive.
+pub fn collect(
+    default_name: &str,
+    manifest: &Manifest,
+    read: impl Fn(&str) -> io::Result<Option<String>>,
+) -> io::Result<SbomInput> {
+    let mut input = SbomInput {
+        name: default_name.to_string(),
+        files: manifest
+            .files
+            .iter()
+            .filter(|(name, _)| !name.starts_with(RESERVED_PREFIX))
+            .map(|(name, digest)| (name.clone(), digest.clone()))
+            .collect(),
+        ..Default::default()
+    };
+
+    if let Some(text) = read("pyproject.toml")? {
+        let pyproject: toml::Table =
+            toml::from_str(&text).map_err(|e| invalid("pyproject.toml", e))?;
+        let project = pyproject.get("project");
+        let field = |key: &str| {
+            project
+                .and_then(|p| p.get(key))
+                .and_then(|v| v.as_str())
+                .map(str::to_string)
+        };
+        if let Some(name) = field("name") {
+            input.name = name;
+        }
+        input.version = field("version");
+        input.requires_python = field("requires-python");
+    }
+
+    if let Some(text) = read("uv.lock")? {
+        let (packages, requires_python) = parse_uv_lock(&text)?;
+        input.packages = packages;
+        if input.requires_python.is_none() {
+            input.requires_python = requires_python;
+        }
+    }
+
+    input.python_version = read(".python-version")?.and_then(|text| {
+        text.lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(str::to_string)
+    });
+    Ok(input)
+}
+
+/// Collect the SBOM input from a project directory, for the given packed files
+pub fn collect_from_directory(dir: &Path, manifest: &Manifest) -> io::Result<SbomInput> {
+    let default_name = dir
+        .canonicalize()?
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| "project".to_string());
+    let mut input = collect(&default_name, manifest, |name| {
+        if manifest.files.contains_key(name) {
+            fs::read_to_string(dir.join(name)).map(Some)
+        } else {
+            Ok(None)
+        }
+    })?;
+    input.uv_version = installed_uv_version();
+    Ok(input)
+}
+
+/// Collect the SBOM input from an opened archive. The uv version comes from
+/// an SBOM embedded by `pytron zip --sbom` and is left out without one.
+pub fn collect_from_archive<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    password: Option<&str>,
+    archive_path: &Path,
+) -> io::Result<SbomInput> {
+    let manifest = manifest::hash_archive(archive, password)?;
+    let default_name = archive_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_else(|| "archive".to_string());
+
+    let archive = std::cell::RefCell::new(archive);
+    let read = |name: &str| -> io::Result<Option<String>> {
+        let mut archive = archive.borrow_mut();
+        let Some(index) = archive.index_for_name(name) else {
+            return Ok(None);
+        };
+        let mut entry = archive::read_entry(&mut archive, index, password)?;
+        let mut data = Vec::new();
+        archive::copy_entry(&mut entry, &mut data)?;
+        String::from_utf8(data)
+            .map(Some)
+            .map_err(|_| ArchiveError::Corrupt(format!("{} is not valid UTF-8", name)).into())
+    };
+    let mut input = collect(&default_name, &manifest, read)?;
+    input.uv_version = embedded_uv_version(&read)?;
+    Ok(input)
+}
+
+/// Creation time of the document: `SOURCE_DATE_EPOCH` for reproducible
+/// builds, otherwise now
+pub fn timestamp() -> String {
+    let seconds = env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0)
+        });
+    format_timestamp(seconds)
+}
+
+/// Format seconds since the Unix epoch as an RFC 3339 UTC timestamp
+pub fn format_timestamp(seconds: u64) -> String {
+    let days = (seconds / 86400) as i64;
+    let time = seconds % 86400;
+
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    )
+}
+
+/// Package URL of a PyPI package
+pub fn purl(package: &LockedPackage) -> String {
+    let name = package.name.to_lowercase().replace(['_', '.'], "-");
+    format!("pkg:pypi/{}@{}", name, package.version)
+}
+
+/// A stable UUID derived from the input, so the same archive always gets the
+/// same serial number
+fn content_uuid(input: &SbomInput) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(input.name.as_bytes());
+    for (name, digest) in &input.files {
+        hasher.update(name.as_bytes());
+        hasher.update(digest.as_bytes());
+    }
+    for package in &input.packages {
+        hasher.update(purl(package).as_bytes());
+    }
+    let mut bytes: [u8; 16] = hasher.finalize()[..16].try_into().expect("16 bytes");
+    // Version 8 (custom), RFC 4122 variant
+    bytes[6] = (bytes[6] & 0x0f) | 0x80;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex = hex::encode(bytes);
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+fn dependencies(input: &SbomInput) -> Vec<&LockedPackage> {
+    input.packages.iter().filter(|p| !p.is_root).collect()
+}
+
+fn root_dependencies(input: &SbomInput) -> Vec<&LockedPackage> {
+    match input.packages.iter().find(|p| p.is_root) {
+        Some(root) => input
+            .packages
+            .iter()
+            .filter(|p| !p.is_root && root.dependencies.contains(&p.name))
+            .collect(),
+        // Without a root entry every locked package counts as a direct dependency
+        None => dependencies(input),
+    }
+}
+
+/// Build a CycloneDX 1.5 JSON document
+pub fn cyclonedx(input: &SbomInput, timestamp: &str) -> Value {
+    let root_ref = format!("pytron:{}", input.name);
+    let mut properties = Vec::new();
+    if let Some(version) = &input.uv_version {
+        properties.push(json!({"name": "pytron:uv-version", "value": version}));
+    }
+    if let Some(version) = &input.python_version {
+        properties.push(json!({"name": "pytron:python-version", "value": version}));
+    }
+    if let Some(requires) = &input.requires_python {
+        properties.push(json!({"name": "pytron:requires-python", "value": requires}));
+    }
+
+    let mut root = json!({
+        "type": "application",
+        "bom-ref": root_ref,
+        "name": input.name,
+    });
+    if let Some(version) = &input.version {
+        root["version"] = json!(version);
+    }
+
+    let mut components = Vec::new();
+    for package in dependencies(input) {
+        let mut component = json!({
+            "type": "library",
+            "bom-ref": purl(package),
+            "name": package.name,
+            "version": package.version,
+            "purl": purl(package),
+        });
+        if let Some(sha256) = &package.sha256 {
+            component["hashes"] = json!([{"alg": "SHA-256", "content": sha256}]);
+        }
+        if let Some(url) = &package.download_url {
+            component["externalReferences"] = json!([{"type": "distribution", "url": url}]);
+        }
+        components.push(component);
+    }
+    if let Some(version) = &input.python_version {
+        components.push(json!({
+            "type": "platform",
+            "bom-ref": "pytron:python",
+            "name": "python",
+            "version": version,
+        }));
+    }
+    if let Some(version) = &input.uv_version {
+        components.push(json!({
+            "type": "application",
+            "bom-ref": "pytron:uv",
+            "name": "uv",
+            "version": version,
+        }));
+    }
+    for (name, digest) in &input.files {
+        components.push(json!({
+            "type": "file",
+            "bom-ref": format!("file:{}", name),
+            "name": name,
+            "hashes": [{"alg": "SHA-256", "content": digest}],
+        }));
+    }
+
+    let mut graph = vec![json!({
+        "ref": root_ref,
+        "dependsOn": root_dependencies(input).iter().map(|p| purl(p)).collect::<Vec<_>>(),
+    })];
+    for package in dependencies(input) {
+        let depends_on = input
+            .packages
+            .iter()
+            .filter(|p| !p.is_root && package.dependencies.contains(&p.name))
+            .map(purl)
+            .collect::<Vec<_>>();
+        graph.push(json!({"ref": purl(package), "dependsOn": depends_on}));
+    }
+
+    json!({
+        "bomFormat": "CycloneDX",
+        "specVersion": "1.5",
+        "serialNumber": format!("urn:uuid:{}", content_uuid(input)),
+        "version": 1,
+        "metadata": {
+            "timestamp": timestamp,
+            "tools": {"components": [{
+                "type": "application",
+                "name": "pytron",
+                "version": env!("CARGO_PKG_VERSION"),
+            }]},
+            "component": root,
+            "properties": properties,
+        },
+        "components": components,
+        "dependencies": graph,
+    })
+}
+
+/// SPDX identifiers only allow letters, digits, `.` and `-`
+fn spdx_safe(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect()
+}
+
+fn spdx_id(kind: &str, name: &str) -> String {
+    format!("SPDXRef-{}-{}", kind, spdx_safe(name))
+}
+
+/// Build an SPDX 2.3 JSON document
+pub fn spdx(input: &SbomInput, timestamp: &str) -> Value {
+    let root_id = spdx_id("Package", &input.name);
+    let mut root = json!({
+        "SPDXID": root_id,
+        "name": input.name,
+        "downloadLocation": "NOASSERTION",
+        "filesAnalyzed": false,
+        "primaryPackagePurpose": "APPLICATION",
+    });
+    if let Some(version) = &input.version {
+        root["versionInfo"] = json!(version);
+    }
+    if let Some(requires) = &input.requires_python {
+        root["comment"] = json!(format!("requires-python: {}", requires));
+    }
+
+    let mut packages = vec![root];
+    let mut relationships = vec![json!({
+        "spdxElementId": "SPDXRef-DOCUMENT",
+        "relationshipType": "DESCRIBES",
+        "relatedSpdxElement": root_id,
+    })];
+    let relate = |from: &str, kind: &str, to: &str| json!({"spdxElementId": from, "relationshipType": kind, "relatedSpdxElement": to});
+
+    let package_id = |p: &LockedPackage| spdx_id("Package", &format!("{}-{}", p.name, p.version));
+    for package in dependencies(input) {
+        let mut entry = json!({
+            "SPDXID": package_id(package),
+            "name": package.name,
+            "versionInfo": package.version,
+            "downloadLocation": package.download_url.as_deref().unwrap_or("NOASSERTION"),
+            "filesAnalyzed": false,
+            "primaryPackagePurpose": "LIBRARY",
+            "externalRefs": [{
+                "referenceCategory": "PACKAGE-MANAGER",
+                "referenceType": "purl",
+                "referenceLocator": purl(package),
+            }],
+        });
+        if let Some(sha256) = &package.sha256 {
+            entry["checksums"] = json!([{"algorithm": "SHA256", "checksumValue": sha256}]);
+        }
+        packages.push(entry);
+        for dependency in input
+            .packages
+            .iter()
+            .filter(|p| !p.is_root && package.dependencies.contains(&p.name))
+        {
+            relationships.push(relate(
+                &package_id(package),
+                "DEPENDS_ON",
+                &package_id(dependency),
+            ));
+        }
+    }
+    for package in root_dependencies(input) {
+        relationships.push(relate(&root_id, "DEPENDS_ON", &package_id(package)));
+    }
+
+    if let Some(version) = &input.python_version {
+        packages.push(json!({
+            "SPDXID": "SPDXRef-Python",
+            "name": "python",
+            "versionInfo": version,
+            "downloadLocation": "NOASSERTION",
+            "filesAnalyzed": false,
+            "primaryPackagePurpose": "OPERATING-SYSTEM",
+        }));
+        relationships.push(relate(&root_id, "DEPENDS_ON", "SPDXRef-Python"));
+    }
+    if let Some(version) = &input.uv_version {
+        packages.push(json!({
+            "SPDXID": "SPDXRef-uv",
+            "name": "uv",
+            "versionInfo": version,
+            "downloadLocation": "NOASSERTION",
+            "filesAnalyzed": false,
+            "primaryPackagePurpose": "APPLICATION",
+        }));
+        relationships.push(relate("SPDXRef-uv", "BUILD_TOOL_OF", &root_id));
+    }
+
+    let mut files = Vec::new();
+    for (index, (name, digest)) in input.files.iter().enumerate() {
+        let file_id = format!("SPDXRef-File-{}", index + 1);
+        files.push(json!({
+            "SPDXID": file_id,
+            "fileName": format!("./{}", name),
+            "checksums": [{"algorithm": "SHA256", "checksumValue": digest}],
+        }));
+        relationships.push(relate(&root_id, "CONTAINS", &file_id));
+    }
+
+    let mut creators = vec![format!("Tool: pytron-{}", env!("CARGO_PKG_VERSION"))];
+    if let Some(version) = &input.uv_version {
+        creators.push(format!("Tool: uv-{}", version));
+    }
+
+    json!({
+        "spdxVersion": "SPDX-2.3",
+        "dataLicense": "CC0-1.0",
+        "SPDXID": "SPDXRef-DOCUMENT",
+        "name": input.name,
+        "documentNamespace": format!(
+            "https://spdx.org/spdxdocs/pytron-{}-{}",
+            spdx_safe(&input.name),
+            content_uuid(input)
+        ),
+        "creationInfo": {
+            "created": timestamp,
+            "creators": creators,
+        },
+        "packages": packages,
+        "files": files,
+        "relationships": relationships,
+    })
+}
+
+/// Render the SBOM in the given format as pretty-printed JSON
+pub fn render(input: &SbomInput, format: SbomFormat) -> String {
+    let timestamp = timestamp();
+    let document = match format {
+        SbomFormat::Cyclonedx => cyclonedx(input, &timestamp),
+        SbomFormat::Spdx => spdx(input, &timestamp),
+    };
+    let mut text = serde_json::to_string_pretty(&document).expect("JSON values serialize");
+    text.push('\n');
+    text
+}
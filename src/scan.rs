@@ -0,0 +1,430 @@
+use clap::ValueEnum;
+use globset::{Glob, GlobBuilder, GlobMatcher};
+use regex::bytes::Regex;
+use serde::Deserialize;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::status;
+
+/// Rules file picked up from the project directory when `--scan-rules` is not given
+pub const PROJECT_RULES_FILE: &str = ".pytron-scan.toml";
+
+/// Files larger than this are only checked by name
+const MAX_CONTENT_SCAN: usize = 16 * 1024 * 1024;
+
+/// What `pytron zip` does when the scanner finds something
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ScanMode {
+    /// Do not scan
+    Off,
+    /// Print the findings and package anyway
+    #[default]
+    Warn,
+    /// Refuse to create the archive
+    Fail,
+}
+
+/// Built-in file name rules: id, description, patterns, exceptions
+const NAME_RULES: &[(&str, &str, &[&str], &[&str])] = &[
+    (
+        "dotenv",
+        "environment file",
+        &[".env", ".env.*", "*.env"],
+        &[".env.example", ".env.sample", ".env.template", ".env.dist"],
+    ),
+    (
+        "ssh-private-key",
+        "SSH private key",
+        &["id_rsa", "id_dsa", "id_ecdsa", "id_ed25519"],
+        &[],
+    ),
+    (
+        "key-file",
+        "private key or certificate bundle",
+        &["*.pem", "*.key", "*.p12", "*.pfx", "*.jks", "*.keystore"],
+        &[],
+    ),
+    (
+        "cloud-credentials",
+        "cloud credentials",
+        &[
+            "credentials.json",
+            "credentials",
+            "application_default_credentials.json",
+            "service-account*.json",
+            "kubeconfig",
+            "**/.kube/config",
+            "**/.docker/config.json",
+        ],
+        &[],
+    ),
+    (
+        "tool-credentials",
+        "credentials of a package or database tool",
+        &[".netrc", ".pgpass", ".pypirc", ".npmrc", ".git-credentials"],
+        &[],
+    ),
+    (
+        "password-store",
+        "password database",
+        &["*.kdbx", "*.keychain"],
+        &[],
+    ),
+    (
+        "terraform-state",
+        "Terraform state with resource secrets",
+        &["*.tfstate", "*.tfstate.backup"],
+        &[],
+    ),
+];
+
+/// Built-in content rules: id, description, regular expression
+const CONTENT_RULES: &[(&str, &str, &str)] = &[
+    (
+        "private-key",
+        "private key block",
+        r"-----BEGIN ([A-Z]+ )*PRIVATE KEY( BLOCK)?-----",
+    ),
+    (
+        "aws-access-key",
+        "AWS access key ID",
+        r"\b(AKIA|ASIA)[0-9A-Z]{16}\b",
+    ),
+    (
+        "github-token",
+        "GitHub token",
+        r"\b(gh[pousr]_[A-Za-z0-9]{36,}|github_pat_[A-Za-z0-9_]{22,})\b",
+    ),
+    (
+        "gitlab-token",
+        "GitLab token",
+        r"\bglpat-[A-Za-z0-9_\-]{20,}\b",
+    ),
+    (
+        "slack-token",
+        "Slack token",
+        r"\bxox[abposr]-[A-Za-z0-9\-]{10,}",
+    ),
+    (
+        "google-api-key",
+        "Google API key",
+        r"\bAIza[0-9A-Za-z_\-]{35}\b",
+    ),
+    (
+        "stripe-key",
+        "Stripe secret key",
+        r"\b(sk|rk)_live_[0-9A-Za-z]{24,}\b",
+    ),
+    (
+        "pypi-token",
+        "PyPI upload token",
+        r"\bpypi-AgEIcHlwaS5vcmc[A-Za-z0-9_\-]{50,}",
+    ),
+    (
+        "gcp-service-account",
+        "Google service account key",
+        r#""private_key_id"\s*:\s*"[0-9a-f]{40}""#,
+    ),
+];
+
+#[derive(Clone)]
+enum Matcher {
+    Name {
+        patterns: Vec<(GlobMatcher, bool)>,
+        unless: Vec<(GlobMatcher, bool)>,
+    },
+    Content(Regex),
+}
+
+/// A single scanner rule
+#[derive(Clone)]
+pub struct Rule {
+    pub id: String,
+    pub description: String,
+    matcher: Matcher,
+}
+
+/// Something the scanner flagged. The matched text is never kept, it may be a secret.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    pub path: String,
+    pub rule: String,
+    pub description: String,
+    /// Line of the first content match
+    pub line: Option<usize>,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(
+                f,
+                "{}:{}: {} [{}]",
+                self.path, line, self.description, self.rule
+            ),
+            None => write!(f, "{}: {} [{}]", self.path, self.description, self.rule),
+        }
+    }
+}
+
+fn invalid(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, msg)
+}
+
+/// Compile a gitignore-like pattern: without a `/` it matches the file name,
+/// with one it matches the path relative to the project (`**/` for any depth)
+fn compile_pattern(pattern: &str) -> io::Result<(GlobMatcher, bool)> {
+    let glob = GlobBuilder::new(pattern)
+        .literal_separator(true)
+        .build()
+        .map_err(|e| invalid(format!("Invalid pattern '{}': {}", pattern, e)))?;
+    Ok((glob.compile_matcher(), pattern.contains('/')))
+}
+
+fn pattern_matches(pattern: &(GlobMatcher, bool), rel_path: &str) -> bool {
+    let (matcher, full_path) = pattern;
+    if *full_path {
+        matcher.is_match(rel_path)
+    } else {
+        let name = rel_path.rsplit('/').next().unwrap_or(rel_path);
+        matcher.is_match(name)
+    }
+}
+
+impl Rule {
+    /// A rule flagging files by name
+    pub fn name(
+        id: &str,
+        description: &str,
+        patterns: &[&str],
+        unless: &[&str],
+    ) -> io::Result<Self> {
+        Ok(Rule {
+            id: id.to_string(),
+            description: description.to_string(),
+            matcher: Matcher::Name {
+                patterns: patterns
+                    .iter()
+                    .map(|p| compile_pattern(p))
+                    .collect::<io::Result<_>>()?,
+                unless: unless
+                    .iter()
+                    .map(|p| compile_pattern(p))
+                    .collect::<io::Result<_>>()?,
+            },
+        })
+    }
+
+    /// A rule flagging files whose content matches a regular expression
+    pub fn content(id: &str, description: &str, pattern: &str) -> io::Result<Self> {
+        let regex = Regex::new(pattern)
+            .map_err(|e| invalid(format!("Invalid content pattern for rule '{}': {}", id, e)))?;
+        Ok(Rule {
+            id: id.to_string(),
+            description: description.to_string(),
+            matcher: Matcher::Content(regex),
+        })
+    }
+
+    fn check(&self, rel_path: &str, data: &[u8]) -> Option<Finding> {
+        let line = match &self.matcher {
+            Matcher::Name { patterns, unless } => {
+                let flagged = patterns.iter().any(|p| pattern_matches(p, rel_path))
+                    && !unless.iter().any(|p| pattern_matches(p, rel_path));
+                if !flagged {
+                    return None;
+                }
+                None
+            }
+            Matcher::Content(regex) => {
+                if data.len() > MAX_CONTENT_SCAN {
+                    return None;
+                }
+                let found = regex.find(data)?;
+                Some(
+                    data[..found.start()]
+                        .iter()
+                        .filter(|b| **b == b'\n')
+                        .count()
+                        + 1,
+                )
+            }
+        };
+        Some(Finding {
+            path: rel_path.to_string(),
+            rule: self.id.clone(),
+            description: self.description.clone(),
+            line,
+        })
+    }
+}
+
+/// The rules shipped with pytron
+pub fn builtin_rules() -> Vec<Rule> {
+    let names = NAME_RULES
+        .iter()
+        .map(|(id, description, patterns, unless)| Rule::name(id, description, patterns, unless));
+    let contents = CONTENT_RULES
+        .iter()
+        .map(|(id, description, pattern)| Rule::content(id, description, pattern));
+    names
+        .chain(contents)
+        .collect::<io::Result<_>>()
+        .expect("built-in scan rules are valid")
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RulesFile {
+    /// Keep the built-in rules (default) or replace them
+    #[serde(default = "default_true")]
+    builtin: bool,
+    /// Paths that are never flagged
+    #[serde(default)]
+    allow: Vec<String>,
+    #[serde(default)]
+    rule: Vec<RuleSpec>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleSpec {
+    id: String,
+    description: Option<String>,
+    /// File name patterns
+    #[serde(default)]
+    name: Vec<String>,
+    /// Name patterns that are not flagged after all
+    #[serde(default)]
+    unless: Vec<String>,
+    /// Regular expression matched against the file content
+    content: Option<String>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Checks the files `pytron zip` packs for secrets and sensitive files
+#[derive(Clone)]
+pub struct Scanner {
+    pub mode: ScanMode,
+    rules: Vec<Rule>,
+    allow: Vec<GlobMatcher>,
+    allow_paths: Vec<String>,
+}
+
+impl fmt::Debug for Scanner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Scanner")
+            .field("mode", &self.mode)
+            .field(
+                "rules",
+                &self.rules.iter().map(|r| &r.id).collect::<Vec<_>>(),
+            )
+            .field("allow", &self.allow_paths)
+            .finish()
+    }
+}
+
+impl Scanner {
+    /// Build a scanner from the built-in rules, an optional rules file and
+    /// `--allow` paths (relative to the project, globs allowed)
+    pub fn new(mode: ScanMode, rules_file: Option<&Path>, allow: &[String]) -> io::Result<Self> {
+        let mut rules = Vec::new();
+        let mut allow_paths = allow.to_vec();
+        let mut builtin = true;
+
+        if let Some(path) = rules_file {
+            let text = fs::read_to_string(path).map_err(|e| {
+                io::Error::new(
+                    e.kind(),
+                    format!("Failed to read scan rules {}: {}", path.display(), e),
+                )
+            })?;
+            let file: RulesFile = toml::from_str(&text)
+                .map_err(|e| invalid(format!("Invalid scan rules {}: {}", path.display(), e)))?;
+            builtin = file.builtin;
+            allow_paths.extend(file.allow);
+            for spec in file.rule {
+                let description = spec.description.as_deref().unwrap_or(&spec.id);
+                match (&spec.content, spec.name.is_empty()) {
+                    (Some(pattern), true) => {
+                        rules.push(Rule::content(&spec.id, description, pattern)?)
+                    }
+                    (None, false) => {
+                        let names: Vec<&str> = spec.name.iter().map(String::as_str).collect();
+                        let unless: Vec<&str> = spec.unless.iter().map(String::as_str).collect();
+                        rules.push(Rule::name(&spec.id, description, &names, &unless)?);
+                    }
+                    _ => {
+                        return Err(invalid(format!(
+                            "Scan rule '{}' needs either `name` or `content`",
+                            spec.id
+                        )))
+                    }
+                }
+            }
+        }
+        if builtin {
+            let mut all = builtin_rules();
+            all.append(&mut rules);
+            rules = all;
+        }
+
+        let allow = allow_paths
+            .iter()
+            .map(|path| {
+                let path = path.trim_start_matches("./").replace('\\', "/");
+                // Allowed paths are always relative to the project root
+                Glob::new(&path)
+                    .map(|glob| glob.compile_matcher())
+                    .map_err(|e| invalid(format!("Invalid --allow path '{}': {}", path, e)))
+            })
+            .collect::<io::Result<_>>()?;
+
+        Ok(Scanner {
+            mode,
+            rules,
+            allow,
+            allow_paths,
+        })
+    }
+
+    /// Check one file, named by its path relative to the project root
+    pub fn scan(&self, rel_path: &str, data: &[u8]) -> Vec<Finding> {
+        if self.allow.iter().any(|matcher| matcher.is_match(rel_path)) {
+            return Vec::new();
+        }
+        self.rules
+            .iter()
+            .filter_map(|rule| rule.check(rel_path, data))
+            .collect()
+    }
+
+    /// Print the findings, and fail in `fail` mode
+    pub fn report(&self, findings: &[Finding]) -> io::Result<()> {
+        if findings.is_empty() {
+            return Ok(());
+        }
+        for finding in findings {
+            status!("Warning: possible secret: {}", finding);
+        }
+        match self.mode {
+            ScanMode::Fail => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "Refusing to package {} sensitive file finding(s); remove the files, ignore them or pass --allow <path>",
+                    findings.len()
+                ),
+            )),
+            _ => {
+                status!("Use --allow <path> for intended files, or --scan fail to stop the build");
+                Ok(())
+            }
+        }
+    }
+}
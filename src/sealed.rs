@@ -0,0 +1,144 @@
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use argon2::{Algorithm, Argon2, Params, Version};
+use rand::RngCore;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+use crate::archive::ArchiveError;
+
+/// Magic bytes at the start of a sealed archive
+pub const SEALED_MAGIC: &[u8; 8] = b"PYTRSEAL";
+/// Current version of the sealed container format
+const FORMAT_VERSION: u8 = 1;
+
+/// Argon2id memory cost in KiB (64 MiB)
+const ARGON2_MEMORY_KIB: u32 = 64 * 1024;
+/// Argon2id iterations
+const ARGON2_ITERATIONS: u32 = 3;
+/// Argon2id lanes
+const ARGON2_PARALLELISM: u32 = 1;
+/// Upper bounds accepted when reading a container (1 GiB, 64 iterations)
+const MAX_MEMORY_KIB: u32 = 1024 * 1024;
+const MAX_ITERATIONS: u32 = 64;
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+/// magic, version, three u32 KDF parameters, salt, nonce
+const HEADER_LEN: usize = 8 + 1 + 12 + SALT_LEN + NONCE_LEN;
+
+/// True if the file at `path` starts with the sealed container magic
+pub fn is_sealed(path: &Path) -> io::Result<bool> {
+    let mut magic = [0u8; 8];
+    let mut file = File::open(path)?;
+    match file.read_exact(&mut magic) {
+        Ok(()) => Ok(&magic == SEALED_MAGIC),
+        Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => Ok(false),
+        Err(err) => Err(err),
+    }
+}
+
+fn derive_key(
+    password: &str,
+    salt: &[u8],
+    memory: u32,
+    iterations: u32,
+    lanes: u32,
+) -> io::Result<[u8; 32]> {
+    let params = Params::new(memory, iterations, lanes, Some(32))
+        .map_err(|e| ArchiveError::Corrupt(format!("invalid key derivation parameters: {}", e)))?;
+    let mut key = [0u8; 32];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(password.as_bytes(), salt, &mut key)
+        .map_err(|e| io::Error::other(format!("Key derivation failed: {}", e)))?;
+    Ok(key)
+}
+
+/// Seal a complete zip archive with AES-256-GCM under an Argon2id-derived key.
+///
+/// The header (format version, KDF parameters, salt and nonce) is
+/// authenticated as associated data, so nothing about the contents, not even
+/// entry names, is readable without the password.
+pub fn seal(archive: &[u8], password: &str) -> io::Result<Vec<u8>> {
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    rand::rng().fill_bytes(&mut salt);
+    rand::rng().fill_bytes(&mut nonce);
+
+    let mut header = Vec::with_capacity(HEADER_LEN);
+    header.extend_from_slice(SEALED_MAGIC);
+    header.push(FORMAT_VERSION);
+    header.extend_from_slice(&ARGON2_MEMORY_KIB.to_le_bytes());
+    header.extend_from_slice(&ARGON2_ITERATIONS.to_le_bytes());
+    header.extend_from_slice(&ARGON2_PARALLELISM.to_le_bytes());
+    header.extend_from_slice(&salt);
+    header.extend_from_slice(&nonce);
+
+    let key = derive_key(
+        password,
+        &salt,
+        ARGON2_MEMORY_KIB,
+        ARGON2_ITERATIONS,
+        ARGON2_PARALLELISM,
+    )?;
+    let cipher = Aes256Gcm::new(&key.into());
+    let ciphertext = cipher
+        .encrypt(
+            Nonce::from_slice(&nonce),
+            Payload {
+                msg: archive,
+                aad: &header,
+            },
+        )
+        .map_err(|_| io::Error::other("Encryption failed"))?;
+
+    header.extend_from_slice(&ciphertext);
+    Ok(header)
+}
+
+/// Decrypt a sealed container back into the zip archive bytes.
+/// A wrong password and a tampered container are indistinguishable and both
+/// reported as `WrongPassword`.
+pub fn unseal(data: &[u8], password: &str) -> io::Result<Vec<u8>> {
+    if data.len() < HEADER_LEN || &data[..8] != SEALED_MAGIC {
+        return Err(ArchiveError::Corrupt("sealed archive header is truncated".to_string()).into());
+    }
+    let version = data[8];
+    if version != FORMAT_VERSION {
+        return Err(ArchiveError::Corrupt(format!(
+            "unsupported sealed archive version {}",
+            version
+        ))
+        .into());
+    }
+
+    let read_u32 = |pos: usize| u32::from_le_bytes(data[pos..pos + 4].try_into().unwrap());
+    let (memory, iterations, lanes) = (read_u32(9), read_u32(13), read_u32(17));
+    // Refuse parameters that would make opening the file exhaust the machine
+    if memory > MAX_MEMORY_KIB || iterations > MAX_ITERATIONS {
+        return Err(ArchiveError::Corrupt(
+            "sealed archive asks for excessive key derivation cost".to_string(),
+        )
+        .into());
+    }
+    let salt = &data[21..21 + SALT_LEN];
+    let nonce = &data[21 + SALT_LEN..HEADER_LEN];
+
+    let key = derive_key(password, salt, memory, iterations, lanes)?;
+    let cipher = Aes256Gcm::new(&key.into());
+    cipher
+        .decrypt(
+            Nonce::from_slice(nonce),
+            Payload {
+                msg: &data[HEADER_LEN..],
+                aad: &data[..HEADER_LEN],
+            },
+        )
+        .map_err(|_| {
+            ArchiveError::WrongPassword {
+                entry: "the sealed archive (or it was modified)".to_string(),
+            }
+            .into()
+        })
+}
@@ -0,0 +1,111 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::archive::ArchiveError;
+use crate::{recipients, sealed};
+
+/// Archive entry holding the encrypted secrets
+pub const SECRETS_ENTRY: &str = ".pytron/secrets.env.enc";
+
+/// Parse a dotenv-style file: `KEY=value` lines, optionally prefixed with
+/// `export`, with blank lines and `#` comments ignored and matching single or
+/// double quotes around the value removed.
+pub fn parse_env(text: &str) -> io::Result<Vec<(String, String)>> {
+    let mut vars = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let Some((key, value)) = line.split_once('=') else {
+            // Do not echo the line, it may contain a secret
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Line {} of the secrets file is not KEY=value", number + 1),
+            ));
+        };
+        let key = key.trim();
+        if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "Line {} of the secrets file has an invalid variable name",
+                    number + 1
+                ),
+            ));
+        }
+        let value = value.trim();
+        let value = ['"', '\'']
+            .iter()
+            .find_map(|q| {
+                value
+                    .strip_prefix(*q)
+                    .and_then(|rest| rest.strip_suffix(*q))
+            })
+            .unwrap_or(value);
+        vars.push((key.to_string(), value.to_string()));
+    }
+    Ok(vars)
+}
+
+/// Read and encrypt a secrets file for embedding in an archive. The secrets
+/// are encrypted to `recipients` if any, otherwise sealed with `password`.
+/// Returns the encrypted bytes and the number of variables.
+pub fn encrypt_secrets_file(
+    path: &Path,
+    password: Option<&str>,
+    recipients: &[age::x25519::Recipient],
+) -> io::Result<(Vec<u8>, usize)> {
+    let text = fs::read_to_string(path).map_err(|e| {
+        io::Error::new(
+            e.kind(),
+            format!("Failed to read secrets file {}: {}", path.display(), e),
+        )
+    })?;
+    let count = parse_env(&text)?.len();
+
+    let encrypted = if !recipients.is_empty() {
+        recipients::encrypt(text.as_bytes(), recipients)?
+    } else if let Some(password) = password {
+        sealed::seal(text.as_bytes(), password)?
+    } else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "--secrets needs a password or --recipient to encrypt the secrets with",
+        ));
+    };
+    Ok((encrypted, count))
+}
+
+/// Decrypt the secrets entry of an archive in memory
+pub fn decrypt_secrets(
+    data: &[u8],
+    password: Option<&str>,
+    identities: &[PathBuf],
+) -> io::Result<Vec<(String, String)>> {
+    let plain = if data.starts_with(recipients::AGE_MAGIC) {
+        recipients::decrypt(data, identities)?
+    } else if data.starts_with(sealed::SEALED_MAGIC) {
+        let password = password.ok_or(ArchiveError::PasswordRequired {
+            encrypted: vec![SECRETS_ENTRY.to_string()],
+            total: 1,
+        })?;
+        sealed::unseal(data, password).map_err(|err| match crate::archive::archive_error(&err) {
+            Some(ArchiveError::WrongPassword { .. }) => ArchiveError::WrongPassword {
+                entry: SECRETS_ENTRY.to_string(),
+            }
+            .into(),
+            _ => err,
+        })?
+    } else {
+        return Err(
+            ArchiveError::Corrupt(format!("{} has an unknown format", SECRETS_ENTRY)).into(),
+        );
+    };
+
+    let text = String::from_utf8(plain)
+        .map_err(|_| ArchiveError::Corrupt(format!("{} is not valid UTF-8", SECRETS_ENTRY)))?;
+    parse_env(&text)
+}
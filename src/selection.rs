@@ -0,0 +1,429 @@
+use ignore::gitignore::Gitignore;
+use ignore::{Match, WalkBuilder};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::format_size;
+use crate::sbom::RESERVED_PREFIX;
+
+/// Excluded unless `--ignore-patterns ""` overrides the defaults
+pub const DEFAULT_IGNORES: [&str; 1] = [".git"];
+
+/// Ignore files the walker reads in every directory, most specific first
+const IGNORE_FILES: [&str; 2] = [".ignore", ".gitignore"];
+
+/// Where the rule that excludes a path comes from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RuleSource {
+    /// A line of `.gitignore`, `.ignore` or `.git/info/exclude`, relative to the project
+    IgnoreFile { file: String, line: Option<usize> },
+    /// One of `DEFAULT_IGNORES`
+    Default,
+    /// `--ignore-patterns` or zip.ignore_patterns
+    IgnorePatterns,
+    /// The archive being written
+    Output,
+    /// A file that must never be stored in the clear, such as the `--secrets` file
+    Protected,
+    /// The `.pytron/` directory, reserved for the metadata pytron adds itself
+    Reserved,
+    /// Git excludes outside the project, such as the global excludes file
+    GitExcludes,
+}
+
+impl fmt::Display for RuleSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuleSource::IgnoreFile { file, line: Some(line) } => write!(f, "{} line {}", file, line),
+            RuleSource::IgnoreFile { file, line: None } => write!(f, "{}", file),
+            RuleSource::Default => write!(f, "default exclude"),
+            RuleSource::IgnorePatterns => write!(f, "--ignore-patterns"),
+            RuleSource::Output => write!(f, "output archive"),
+            RuleSource::Protected => write!(f, "never stored in plain text"),
+            RuleSource::Reserved => write!(f, "reserved for pytron metadata"),
+            RuleSource::GitExcludes => write!(f, "git excludes outside the project"),
+        }
+    }
+}
+
+/// A rule excluding files from the archive
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Rule {
+    /// The pattern as written, if the rule has one
+    pub pattern: Option<String>,
+    pub source: RuleSource,
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.pattern {
+            Some(pattern) => write!(f, "`{}` ({})", pattern, self.source),
+            None => write!(f, "{}", self.source),
+        }
+    }
+}
+
+/// A file that goes into the archive
+#[derive(Debug, Clone, Serialize)]
+pub struct IncludedFile {
+    /// Entry name, relative to the project with forward slashes
+    pub name: String,
+    #[serde(skip)]
+    pub path: PathBuf,
+    pub size: u64,
+}
+
+/// A file or directory left out, with the rule responsible. The contents of
+/// excluded directories are not listed separately.
+#[derive(Debug, Clone, Serialize)]
+pub struct ExcludedPath {
+    pub name: String,
+    pub is_dir: bool,
+    pub rule: Rule,
+}
+
+/// Which files of a project `pytron zip` packs, and why the others are left out
+#[derive(Debug, Clone, Serialize)]
+pub struct Selection {
+    pub included: Vec<IncludedFile>,
+    pub excluded: Vec<ExcludedPath>,
+    /// Uncompressed size of the included files
+    pub total_size: u64,
+    /// Whether the default excludes were overridden with `--ignore-patterns ""`
+    #[serde(skip)]
+    pub defaults_overridden: bool,
+    /// The patterns checked besides the walker's ignore files
+    #[serde(skip)]
+    pub rules: Vec<Rule>,
+}
+
+/// Whether an entry named `name` is `path` or lies below it
+fn is_within(name: &str, path: &str) -> bool {
+    name == path || name.strip_prefix(path).is_some_and(|rest| rest.starts_with('/'))
+}
+
+/// Why `name` is included or excluded, as reported by `pytron zip --explain`
+#[derive(Debug, Clone, Serialize)]
+pub struct Explanation {
+    pub path: String,
+    pub included: bool,
+    /// Excluded directory containing the path, if it is not excluded itself
+    pub excluded_by_parent: Option<String>,
+    pub rule: Option<Rule>,
+}
+
+impl Explanation {
+    pub fn to_text(&self) -> String {
+        match (&self.rule, &self.excluded_by_parent) {
+            (Some(rule), Some(parent)) => format!(
+                "{}: excluded, its directory {} is excluded by {}\n",
+                self.path, parent, rule
+            ),
+            (Some(rule), None) => format!("{}: excluded by {}\n", self.path, rule),
+            (None, _) => format!("{}: included\n", self.path),
+        }
+    }
+}
+
+impl Selection {
+    /// Explain why `name` (relative to the project) is included or excluded.
+    /// Returns None for paths that are not in the project.
+    pub fn explain(&self, name: &str) -> Option<Explanation> {
+        let name = name.trim_start_matches("./").trim_end_matches('/');
+        if let Some(excluded) = self.excluded.iter().find(|e| is_within(name, &e.name)) {
+            return Some(Explanation {
+                path: name.to_string(),
+                included: false,
+                excluded_by_parent: (excluded.name != name).then(|| excluded.name.clone()),
+                rule: Some(excluded.rule.clone()),
+            });
+        }
+        self.included
+            .iter()
+            .any(|file| is_within(&file.name, name))
+            .then(|| Explanation {
+                path: name.to_string(),
+                included: true,
+                excluded_by_parent: None,
+                rule: None,
+            })
+    }
+
+    /// List the included and excluded files with totals, for `pytron zip --dry-run`
+    pub fn to_text(&self) -> String {
+        let mut out = String::from("Included:\n");
+        for file in &self.included {
+            out.push_str(&format!("  {} ({})\n", file.name, format_size(file.size)));
+        }
+        if !self.excluded.is_empty() {
+            out.push_str("\nExcluded:\n");
+            for path in &self.excluded {
+                let slash = if path.is_dir { "/" } else { "" };
+                out.push_str(&format!("  {}{}  {}\n", path.name, slash, path.rule));
+            }
+        }
+        out.push_str(&format!(
+            "\n{} file(s), {} uncompressed; {} path(s) excluded\n",
+            self.included.len(),
+            format_size(self.total_size),
+            self.excluded.len()
+        ));
+        out
+    }
+}
+
+/// Non-comment lines of an ignore file with their line numbers
+fn read_patterns(path: &Path) -> Vec<(String, usize)> {
+    let Ok(content) = fs::read_to_string(path) else {
+        return Vec::new();
+    };
+    content
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (line.trim().to_string(), index + 1))
+        .filter(|(line, _)| !line.is_empty() && !line.starts_with('#'))
+        .collect()
+}
+
+/// The patterns checked against every walked path: the top-level `.gitignore`,
+/// the default excludes and `--ignore-patterns`. An empty string as the only
+/// pattern drops the defaults.
+fn explicit_rules(dir_path: &Path, ignore_patterns: Option<&Vec<String>>) -> (Vec<Rule>, bool) {
+    let overridden = matches!(ignore_patterns, Some(patterns) if patterns.len() == 1 && patterns[0].is_empty());
+    let mut rules: Vec<Rule> = read_patterns(&dir_path.join(".gitignore"))
+        .into_iter()
+        .map(|(pattern, line)| Rule {
+            pattern: Some(pattern),
+            source: RuleSource::IgnoreFile {
+                file: ".gitignore".to_string(),
+                line: Some(line),
+            },
+        })
+        .collect();
+    if overridden {
+        return (rules, true);
+    }
+    rules.extend(DEFAULT_IGNORES.iter().map(|pattern| Rule {
+        pattern: Some(pattern.to_string()),
+        source: RuleSource::Default,
+    }));
+    for pattern in ignore_patterns.into_iter().flatten() {
+        let pattern = pattern.trim();
+        if !pattern.is_empty() && !pattern.starts_with('#') {
+            rules.push(Rule {
+                pattern: Some(pattern.to_string()),
+                source: RuleSource::IgnorePatterns,
+            });
+        }
+    }
+    (rules, false)
+}
+
+/// Match a relative path against one of the explicit patterns
+fn matches_pattern(pattern: &str, rel_path: &Path) -> bool {
+    // Get filename for extension matching
+    let file_name = rel_path
+        .file_name()
+        .map(|f| f.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    // Get path string for full path matching and normalize to use forward slashes
+    let rel_path_str = rel_path.to_string_lossy().replace('\\', "/");
+
+    if pattern.starts_with("*.") {
+        // Handle extension patterns like "*.log"
+        let ext = &pattern[1..]; // Get ".log"
+        file_name.ends_with(ext)
+    } else if pattern.ends_with("*") && pattern.starts_with("*") && pattern.len() > 2 {
+        // Handle middle patterns like "*custom_ignore*"
+        let middle = &pattern[1..pattern.len() - 1];
+        rel_path_str.contains(middle)
+    } else if let Some(prefix) = pattern.strip_suffix("*") {
+        // Handle prefix patterns like "prefix*"
+        rel_path_str.starts_with(prefix)
+    } else if let Some(stripped) = pattern.strip_prefix("*") {
+        // Handle suffix patterns like "*suffix"
+        rel_path_str.ends_with(stripped)
+    } else {
+        // Exact match
+        rel_path_str == pattern
+    }
+}
+
+/// Find the ignore file rule that made the walker skip `rel_path`: the
+/// `.ignore`/`.gitignore` files from its directory up to the project, then
+/// `.git/info/exclude`
+fn ignore_file_rule(dir_path: &Path, rel_path: &Path, is_dir: bool) -> Rule {
+    let mut candidates = Vec::new();
+    for ancestor in rel_path.ancestors().skip(1) {
+        for file in IGNORE_FILES {
+            candidates.push(ancestor.join(file));
+        }
+    }
+    candidates.push(PathBuf::from(".git/info/exclude"));
+
+    for file in candidates {
+        let path = dir_path.join(&file);
+        if !path.is_file() {
+            continue;
+        }
+        let (matcher, _) = Gitignore::new(&path);
+        // Ignore files in .git/info apply to the whole project
+        let base = if file.starts_with(".git") { Path::new("") } else { file.parent().unwrap_or(Path::new("")) };
+        let Ok(relative) = rel_path.strip_prefix(base) else {
+            continue;
+        };
+        if let Match::Ignore(glob) = matcher.matched_path_or_any_parents(relative, is_dir) {
+            let line = read_patterns(&path)
+                .into_iter()
+                .find(|(pattern, _)| pattern == glob.original())
+                .map(|(_, line)| line);
+            return Rule {
+                pattern: Some(glob.original().to_string()),
+                source: RuleSource::IgnoreFile {
+                    file: file.to_string_lossy().replace('\\', "/"),
+                    line,
+                },
+            };
+        }
+    }
+    Rule {
+        pattern: None,
+        source: RuleSource::GitExcludes,
+    }
+}
+
+fn entry_name(rel_path: &Path) -> String {
+    rel_path.to_string_lossy().replace('\\', "/")
+}
+
+/// Decide which files of `dir_path` go into the archive.
+///
+/// Files are walked with the `ignore` crate, which applies `.gitignore` and
+/// `.ignore` files; the explicit rules are then checked against every walked
+/// path, and `output`, the `exclude` files and the reserved `.pytron/`
+/// directory are always left out.
+pub fn select_files(
+    dir_path: &Path,
+    output: &Path,
+    ignore_patterns: Option<&Vec<String>>,
+    exclude: &[PathBuf],
+) -> io::Result<Selection> {
+    let (rules, defaults_overridden) = explicit_rules(dir_path, ignore_patterns);
+    let output = output.canonicalize().ok();
+    let exclude: Vec<PathBuf> = exclude
+        .iter()
+        .filter_map(|path| path.canonicalize().ok())
+        .collect();
+    let mut selection = Selection {
+        included: Vec::new(),
+        excluded: Vec::new(),
+        total_size: 0,
+        defaults_overridden,
+        rules,
+    };
+
+    // Walk the directory using ignore, which respects .gitignore
+    let walker = WalkBuilder::new(dir_path)
+        .hidden(false) // Process hidden files too, but respect .gitignore
+        .git_ignore(true) // Use .gitignore rules
+        .build();
+    let mut walked = HashSet::new();
+    let mut excluded_dirs: Vec<PathBuf> = Vec::new();
+    for result in walker {
+        let entry = result.map_err(io::Error::other)?;
+        let path = entry.path();
+        let rel_path = path.strip_prefix(dir_path).map_err(io::Error::other)?.to_path_buf();
+        walked.insert(rel_path.clone());
+        if rel_path.as_os_str().is_empty()
+            || excluded_dirs.iter().any(|dir| rel_path.starts_with(dir))
+        {
+            continue;
+        }
+        let is_dir = entry.file_type().is_some_and(|t| t.is_dir());
+
+        let canonical = path.canonicalize().ok();
+        let rule = if canonical.is_some() && canonical == output {
+            Some(Rule {
+                pattern: None,
+                source: RuleSource::Output,
+            })
+        } else if canonical.is_some_and(|p| exclude.contains(&p)) {
+            Some(Rule {
+                pattern: None,
+                source: RuleSource::Protected,
+            })
+        } else if rel_path.starts_with(RESERVED_PREFIX.trim_end_matches('/')) {
+            Some(Rule {
+                pattern: Some(RESERVED_PREFIX.to_string()),
+                source: RuleSource::Reserved,
+            })
+        } else {
+            selection
+                .rules
+                .iter()
+                .find(|rule| rule.pattern.as_deref().is_some_and(|p| matches_pattern(p, &rel_path)))
+                .cloned()
+        };
+
+        if let Some(rule) = rule {
+            if is_dir {
+                excluded_dirs.push(rel_path.clone());
+            }
+            selection.excluded.push(ExcludedPath {
+                name: entry_name(&rel_path),
+                is_dir,
+                rule,
+            });
+        } else if path.is_file() {
+            let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+            selection.total_size += size;
+            selection.included.push(IncludedFile {
+                name: entry_name(&rel_path),
+                path: path.to_path_buf(),
+                size,
+            });
+        }
+    }
+
+    // Walk again without filters to find what the ignore files left out. Only
+    // directories the first walk entered and kept are descended into, so an
+    // ignored `.venv` or `node_modules` is reported once and never walked.
+    let entered: HashSet<PathBuf> = walked
+        .iter()
+        .filter(|rel_path| !excluded_dirs.iter().any(|dir| rel_path.starts_with(dir)))
+        .cloned()
+        .collect();
+    let root = dir_path.to_path_buf();
+    let unfiltered = WalkBuilder::new(dir_path)
+        .standard_filters(false)
+        .filter_entry(move |entry| {
+            entry.depth() == 0
+                || entry
+                    .path()
+                    .parent()
+                    .and_then(|parent| parent.strip_prefix(&root).ok())
+                    .is_some_and(|parent| entered.contains(parent))
+        })
+        .build();
+    for result in unfiltered {
+        let entry = result.map_err(io::Error::other)?;
+        let rel_path = entry.path().strip_prefix(dir_path).map_err(io::Error::other)?.to_path_buf();
+        if walked.contains(&rel_path) {
+            continue;
+        }
+        let is_dir = entry.file_type().is_some_and(|t| t.is_dir());
+        selection.excluded.push(ExcludedPath {
+            name: entry_name(&rel_path),
+            is_dir,
+            rule: ignore_file_rule(dir_path, &rel_path, is_dir),
+        });
+    }
+    selection.excluded.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(selection)
+}
@@ -0,0 +1,340 @@
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::{self, Cursor, Read, Seek, Write};
+use std::path::{Path, PathBuf};
+use zip::ZipArchive;
+
+use crate::{get_pytron_home, status};
+
+/// Prefix of public key files and of the key embedded in signatures
+pub const PUBLIC_KEY_PREFIX: &str = "pytron-ed25519";
+/// Prefix of private key files
+pub const PRIVATE_KEY_PREFIX: &str = "pytron-ed25519-secret";
+/// Prefix of the archive comment that carries the signature
+const SIGNATURE_PREFIX: &str = "pytron-signature:v1";
+/// Domain separator so archive digests can never be confused with other signed data
+const DIGEST_DOMAIN: &[u8] = b"pytron-archive-digest-v1\0";
+
+/// Result of checking an archive's embedded signature
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignatureStatus {
+    /// The archive carries no signature
+    Unsigned,
+    /// The signature matches the archive contents
+    Valid {
+        key_id: String,
+        /// Path of the trusted public key file that matched, if any
+        trusted_key: Option<PathBuf>,
+    },
+    /// A signature is present but does not match (tampered or corrupt archive)
+    Invalid(String),
+}
+
+/// Directory holding public keys whose signatures `pytron run` accepts
+pub fn get_trusted_keys_dir() -> PathBuf {
+    get_pytron_home().join("trusted_keys")
+}
+
+/// Short, stable identifier of a public key for display
+pub fn key_id(key: &VerifyingKey) -> String {
+    hex::encode(&Sha256::digest(key.as_bytes())[..8])
+}
+
+fn invalid_data(msg: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.into())
+}
+
+/// Parse `<prefix> <base64>` key text into its 32 raw bytes
+fn parse_key_text(text: &str, prefix: &str, source: &Path) -> io::Result<[u8; 32]> {
+    let mut parts = text.split_whitespace();
+    if parts.next() != Some(prefix) {
+        return Err(invalid_data(format!(
+            "{} is not a {} key file",
+            source.display(),
+            prefix
+        )));
+    }
+    let encoded = parts
+        .next()
+        .ok_or_else(|| invalid_data(format!("{} is missing key data", source.display())))?;
+    let bytes = BASE64
+        .decode(encoded)
+        .map_err(|e| invalid_data(format!("{}: {}", source.display(), e)))?;
+    bytes
+        .try_into()
+        .map_err(|_| invalid_data(format!("{}: key must be 32 bytes", source.display())))
+}
+
+/// Read a private signing key written by `generate_keypair`
+pub fn read_signing_key(path: &Path) -> io::Result<SigningKey> {
+    let text = fs::read_to_string(path)?;
+    let seed = parse_key_text(&text, PRIVATE_KEY_PREFIX, path)?;
+    Ok(SigningKey::from_bytes(&seed))
+}
+
+/// Read a public key written by `generate_keypair`
+pub fn read_public_key(path: &Path) -> io::Result<VerifyingKey> {
+    let text = fs::read_to_string(path)?;
+    let bytes = parse_key_text(&text, PUBLIC_KEY_PREFIX, path)?;
+    VerifyingKey::from_bytes(&bytes).map_err(|e| invalid_data(format!("{}: {}", path.display(), e)))
+}
+
+/// Parse a public key given inline in `.pub` file format
+pub fn parse_public_key(text: &str) -> io::Result<VerifyingKey> {
+    let source = Path::new("public key");
+    let bytes = parse_key_text(text, PUBLIC_KEY_PREFIX, source)?;
+    VerifyingKey::from_bytes(&bytes).map_err(|e| invalid_data(format!("public key: {}", e)))
+}
+
+/// Format a public key the way it is stored in `.pub` files
+pub fn format_public_key(key: &VerifyingKey) -> String {
+    format!("{} {}", PUBLIC_KEY_PREFIX, BASE64.encode(key.as_bytes()))
+}
+
+/// Generate a new signing key pair as `<stem>.key` and `<stem>.pub`.
+/// Returns the paths of the private and public key files.
+pub fn generate_keypair(stem: &Path, overwrite: bool) -> io::Result<(PathBuf, PathBuf)> {
+    let private_path = stem.with_extension("key");
+    let public_path = stem.with_extension("pub");
+    if !overwrite {
+        for path in [&private_path, &public_path] {
+            if path.exists() {
+                return Err(io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    format!(
+                        "{} already exists, use --force to overwrite",
+                        path.display()
+                    ),
+                ));
+            }
+        }
+    }
+
+    let mut seed = [0u8; 32];
+    rand::rng().fill_bytes(&mut seed);
+    let signing_key = SigningKey::from_bytes(&seed);
+
+    let private_text = format!("{} {}\n", PRIVATE_KEY_PREFIX, BASE64.encode(seed));
+    write_private_file(&private_path, private_text.as_bytes())?;
+    fs::write(
+        &public_path,
+        format!("{}\n", format_public_key(&signing_key.verifying_key())),
+    )?;
+
+    Ok((private_path, public_path))
+}
+
+/// Write a file readable only by the current user
+pub(crate) fn write_private_file(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    io::Write::write_all(&mut options.open(path)?, contents)
+}
+
+/// Compute the digest that signatures cover.
+///
+/// It hashes every entry's name, unix mode and raw (compressed, possibly
+/// encrypted) data in name order, so signing and verification never need the
+/// AES password. The header fields that decide how the raw data is decoded
+/// (compression method, encryption flag, extra field with the AES parameters,
+/// uncompressed size and CRC) are hashed too. The archive comment, where the
+/// signature lives, is excluded.
+pub fn archive_digest<R: Read + Seek>(archive: &mut ZipArchive<R>) -> io::Result<[u8; 32]> {
+    let mut indices: Vec<usize> = (0..archive.len()).collect();
+    let mut names = Vec::with_capacity(indices.len());
+    for &i in &indices {
+        names.push(archive.by_index_raw(i)?.name().to_string());
+    }
+    indices.sort_by(|&a, &b| names[a].cmp(&names[b]));
+
+    let mut hasher = Sha256::new();
+    hasher.update(DIGEST_DOMAIN);
+    for i in indices {
+        let mut file = archive.by_index_raw(i)?;
+        let name = file.name().as_bytes().to_vec();
+        hasher.update((name.len() as u64).to_le_bytes());
+        hasher.update(&name);
+        hasher.update(file.unix_mode().unwrap_or(0).to_le_bytes());
+        hasher.update(file.compressed_size().to_le_bytes());
+        #[allow(deprecated)]
+        hasher.update(file.compression().to_u16().to_le_bytes());
+        hasher.update([u8::from(file.encrypted())]);
+        hasher.update(file.size().to_le_bytes());
+        hasher.update(file.crc32().to_le_bytes());
+        let extra = file.extra_data().unwrap_or_default().to_vec();
+        hasher.update((extra.len() as u64).to_le_bytes());
+        hasher.update(&extra);
+        io::copy(&mut file, &mut hasher)?;
+    }
+    Ok(hasher.finalize().into())
+}
+
+/// Embed a signature in the archive at `path`, replacing any existing one.
+/// Returns the id of the signing key.
+///
+/// The signed archive is written next to the original and renamed over it, so
+/// a failed write never leaves a truncated archive behind.
+pub fn sign_archive(path: &Path, signing_key: &SigningKey) -> io::Result<String> {
+    // The signature lives in the zip inside the container, which cannot be
+    // rewritten without the key it is encrypted with
+    if crate::sealed::is_sealed(path)? || crate::recipients::is_recipient_encrypted(path)? {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "{} is sealed or encrypted to recipients, and sealed archives cannot be signed \
+                 afterwards; sign it while creating it with `pytron zip --sign`",
+                path.display()
+            ),
+        ));
+    }
+    let signed = sign_archive_bytes(fs::read(path)?, signing_key)?;
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let mut temp = tempfile::NamedTempFile::new_in(dir)?;
+    temp.write_all(&signed)?;
+    temp.as_file().sync_all()?;
+    fs::set_permissions(temp.path(), fs::metadata(path)?.permissions())?;
+    temp.persist(path).map_err(|err| err.error)?;
+    Ok(key_id(&signing_key.verifying_key()))
+}
+
+/// Sign an in-memory archive, returning the signed archive bytes
+pub(crate) fn sign_archive_bytes(data: Vec<u8>, signing_key: &SigningKey) -> io::Result<Vec<u8>> {
+    let mut archive = ZipArchive::new(Cursor::new(data))?;
+    let digest = archive_digest(&mut archive)?;
+    let signature = signing_key.sign(&digest);
+
+    let comment = format!(
+        "{}:{}:{}",
+        SIGNATURE_PREFIX,
+        BASE64.encode(signing_key.verifying_key().as_bytes()),
+        BASE64.encode(signature.to_bytes())
+    );
+    let mut data = archive.into_inner().into_inner();
+    set_archive_comment(&mut data, comment.as_bytes())?;
+    Ok(data)
+}
+
+/// Replace the comment of a zip archive in place.
+///
+/// Entries are left byte-for-byte untouched, which matters for AES entries whose
+/// metadata the zip crate does not carry over when copying raw entries.
+fn set_archive_comment(data: &mut Vec<u8>, comment: &[u8]) -> io::Result<()> {
+    // End of central directory record: signature, 16 bytes of fields, 2-byte comment length
+    const EOCD_SIGNATURE: &[u8] = b"PK\x05\x06";
+    const EOCD_LEN: usize = 22;
+
+    let comment_len = u16::try_from(comment.len())
+        .map_err(|_| invalid_data("archive comment is too long"))?;
+    let eocd = (0..=data.len().saturating_sub(EOCD_LEN))
+        .rev()
+        .find(|&pos| {
+            data[pos..pos + 4] == *EOCD_SIGNATURE && {
+                let len = u16::from_le_bytes([data[pos + 20], data[pos + 21]]) as usize;
+                pos + EOCD_LEN + len == data.len()
+            }
+        })
+        .ok_or_else(|| invalid_data("could not locate the end of the zip central directory"))?;
+
+    data.truncate(eocd + 20);
+    data.extend_from_slice(&comment_len.to_le_bytes());
+    data.extend_from_slice(comment);
+    Ok(())
+}
+
+/// Load every public key in the trusted keys directory
+pub fn load_trusted_keys() -> io::Result<Vec<(PathBuf, VerifyingKey)>> {
+    let dir = get_trusted_keys_dir();
+    let entries = match fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err),
+    };
+
+    let mut keys = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "pub") {
+            match read_public_key(&path) {
+                Ok(key) => keys.push((path, key)),
+                Err(err) => status!("Warning: ignoring trusted key {}", err),
+            }
+        }
+    }
+    keys.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(keys)
+}
+
+/// Check the signature of an archive against `trusted` public keys
+pub fn verify_archive_with<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    trusted: &[(PathBuf, VerifyingKey)],
+) -> io::Result<SignatureStatus> {
+    let comment = String::from_utf8_lossy(archive.comment()).to_string();
+    let Some(rest) = comment.strip_prefix(SIGNATURE_PREFIX) else {
+        return Ok(SignatureStatus::Unsigned);
+    };
+
+    let mut parts = rest.trim_start_matches(':').split(':');
+    let decode = |part: Option<&str>| part.and_then(|p| BASE64.decode(p.trim()).ok());
+    let (Some(key_bytes), Some(sig_bytes)) = (decode(parts.next()), decode(parts.next())) else {
+        return Ok(SignatureStatus::Invalid(
+            "malformed signature block".to_string(),
+        ));
+    };
+    let Ok(key_bytes) = <[u8; 32]>::try_from(key_bytes) else {
+        return Ok(SignatureStatus::Invalid("malformed signer key".to_string()));
+    };
+    let Ok(key) = VerifyingKey::from_bytes(&key_bytes) else {
+        return Ok(SignatureStatus::Invalid("malformed signer key".to_string()));
+    };
+    let Ok(signature) = Signature::from_slice(&sig_bytes) else {
+        return Ok(SignatureStatus::Invalid("malformed signature".to_string()));
+    };
+
+    let digest = archive_digest(archive)?;
+    if key.verify(&digest, &signature).is_err() {
+        return Ok(SignatureStatus::Invalid(format!(
+            "signature by key {} does not match the archive contents",
+            key_id(&key)
+        )));
+    }
+
+    let trusted_key = trusted
+        .iter()
+        .find(|(_, trusted_key)| *trusted_key == key)
+        .map(|(path, _)| path.clone());
+    Ok(SignatureStatus::Valid {
+        key_id: key_id(&key),
+        trusted_key,
+    })
+}
+
+/// Check the signature of the archive file at `path` against the trusted keys
+/// directory, or against `public_key` only if given. Sealed and recipient
+/// archives are decrypted in memory with `password` or `identities` first, as
+/// their signature is on the zip inside.
+pub fn verify_archive(
+    path: &Path,
+    public_key: Option<&Path>,
+    password: Option<&str>,
+    identities: &[PathBuf],
+) -> io::Result<SignatureStatus> {
+    let trusted = match public_key {
+        Some(key_path) => vec![(key_path.to_path_buf(), read_public_key(key_path)?)],
+        None => load_trusted_keys()?,
+    };
+    let mut archive = crate::archive::open_archive_with_keys(path, password, identities)?;
+    verify_archive_with(&mut archive, &trusted)
+}
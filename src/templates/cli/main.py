@@ -0,0 +1,21 @@
+import argparse
+import sys
+
+
+def parse_args(argv):
+    parser = argparse.ArgumentParser(prog="{{name}}", description="Command line tool packaged with pytron")
+    parser.add_argument("--name", default="world", help="who to greet")
+    parser.add_argument("-v", "--verbose", action="store_true", help="print more details")
+    return parser.parse_args(argv)
+
+
+def main(argv=None):
+    args = parse_args(sys.argv[1:] if argv is None else argv)
+    print(f"Hello, {args.name}!")
+    if args.verbose:
+        print(f"Python {sys.version.split()[0]}, arguments: {sys.argv[1:]}")
+    return 0
+
+
+if __name__ == "__main__":
+    sys.exit(main())
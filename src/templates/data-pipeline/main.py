@@ -0,0 +1,22 @@
+import argparse
+import sys
+from pathlib import Path
+
+from {{module}} import steps
+
+
+def main(argv=None):
+    parser = argparse.ArgumentParser(prog="{{name}}")
+    parser.add_argument("--input", type=Path, default=Path("data/input.csv"), help="CSV file to read")
+    parser.add_argument("--output", type=Path, default=Path("data/output.csv"), help="CSV file to write")
+    args = parser.parse_args(sys.argv[1:] if argv is None else argv)
+
+    rows = steps.extract(args.input)
+    rows = steps.transform(rows)
+    steps.load(rows, args.output)
+    print(f"Wrote {len(rows)} row(s) to {args.output}")
+    return 0
+
+
+if __name__ == "__main__":
+    sys.exit(main())
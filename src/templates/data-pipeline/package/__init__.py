@@ -0,0 +1 @@
+"""Steps of the {{name}} pipeline."""
@@ -0,0 +1,37 @@
+import csv
+import sys
+from pathlib import Path
+
+
+def extract(path):
+    """Read the input rows."""
+    path = Path(path)
+    if not path.exists():
+        print(f"{path} does not exist, nothing to extract")
+        return []
+    with path.open(newline="") as handle:
+        rows = list(csv.DictReader(handle))
+    print(f"Read {len(rows)} row(s) from {path}")
+    return rows
+
+
+def transform(rows):
+    """Clean up the rows, replace with the real transformation."""
+    return [{key.strip().lower(): value.strip() for key, value in row.items()} for row in rows]
+
+
+def load(rows, path):
+    """Write the rows as CSV."""
+    path = Path(path)
+    path.parent.mkdir(parents=True, exist_ok=True)
+    with path.open("w", newline="") as handle:
+        if rows:
+            writer = csv.DictWriter(handle, fieldnames=list(rows[0]))
+            writer.writeheader()
+            writer.writerows(rows)
+
+
+def extract_only():
+    """Entry point of `pytron run <archive> extract [input.csv]`."""
+    extract(sys.argv[1] if len(sys.argv) > 1 else "data/input.csv")
+    return 0
@@ -0,0 +1,49 @@
+import argparse
+import logging
+import os
+import signal
+import sys
+import time
+
+log = logging.getLogger("{{name}}")
+running = True
+
+
+def stop(signum, _frame):
+    global running
+    log.info("Received signal %s, stopping", signum)
+    running = False
+
+
+def work():
+    """One iteration of the service, replace with the real work."""
+    log.info("Working")
+
+
+def main(argv=None):
+    parser = argparse.ArgumentParser(prog="{{name}}")
+    parser.add_argument("--once", action="store_true", help="run a single iteration and exit")
+    parser.add_argument(
+        "--interval",
+        type=float,
+        default=float(os.environ.get("SERVICE_INTERVAL", "60")),
+        help="seconds between iterations (default: $SERVICE_INTERVAL or 60)",
+    )
+    args = parser.parse_args(sys.argv[1:] if argv is None else argv)
+
+    logging.basicConfig(level=logging.INFO, format="%(asctime)s %(levelname)s %(message)s")
+    signal.signal(signal.SIGINT, stop)
+    signal.signal(signal.SIGTERM, stop)
+
+    while running:
+        work()
+        if args.once:
+            break
+        deadline = time.monotonic() + args.interval
+        while running and time.monotonic() < deadline:
+            time.sleep(min(1.0, args.interval))
+    return 0
+
+
+if __name__ == "__main__":
+    sys.exit(main())
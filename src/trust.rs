@@ -0,0 +1,478 @@
+use clap::ValueEnum;
+use ed25519_dalek::VerifyingKey;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{self, Read, Seek};
+use std::path::{Path, PathBuf};
+use zip::ZipArchive;
+
+use crate::signing::{self, SignatureStatus};
+use crate::{get_pytron_home, get_system_config_dir, sha256_file, status};
+
+/// File name of the trust policy, both system-wide and in PYTRON_HOME
+pub const TRUST_POLICY_FILE: &str = "trust.toml";
+
+/// How `pytron run` reacts to archives the policy does not allow
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, ValueEnum,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum EnforcementMode {
+    /// Run everything (broken signatures are still refused)
+    #[default]
+    Off,
+    /// Run, but print a warning for archives outside the policy
+    Warn,
+    /// Refuse archives outside the policy
+    Enforce,
+}
+
+impl std::fmt::Display for EnforcementMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EnforcementMode::Off => write!(f, "off"),
+            EnforcementMode::Warn => write!(f, "warn"),
+            EnforcementMode::Enforce => write!(f, "enforce"),
+        }
+    }
+}
+
+/// A trusted publisher key as stored in the policy file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrustedKey {
+    pub name: String,
+    /// Public key in `.pub` file format ("pytron-ed25519 <base64>")
+    pub key: String,
+}
+
+/// Contents of one trust policy file
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TrustPolicy {
+    pub mode: EnforcementMode,
+    pub keys: Vec<TrustedKey>,
+    /// SHA-256 digests of archive files that may run regardless of signature
+    pub hashes: Vec<String>,
+    /// Glob patterns matched against the archive file name, or its full path if
+    /// the pattern contains a '/'
+    pub patterns: Vec<String>,
+    /// Whether the keys in PYTRON_HOME/trusted_keys are trusted as well. Only
+    /// set by `effective_policy`, never stored in a policy file.
+    #[serde(skip)]
+    pub trusted_keys_dir: bool,
+}
+
+/// Which policy file to read or edit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PolicyScope {
+    System,
+    User,
+}
+
+/// Path of the policy file for a scope
+pub fn policy_path(scope: PolicyScope) -> PathBuf {
+    match scope {
+        PolicyScope::System => get_system_config_dir().join(TRUST_POLICY_FILE),
+        PolicyScope::User => get_pytron_home().join(TRUST_POLICY_FILE),
+    }
+}
+
+/// Load the policy file for a scope; a missing file is an empty policy
+pub fn load_policy(scope: PolicyScope) -> io::Result<TrustPolicy> {
+    let path = policy_path(scope);
+    match fs::read_to_string(&path) {
+        Ok(text) => toml::from_str(&text).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Invalid trust policy {}: {}", path.display(), e),
+            )
+        }),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(TrustPolicy::default()),
+        Err(err) => Err(err),
+    }
+}
+
+/// Write the policy file for a scope, creating its directory if needed
+pub fn save_policy(scope: PolicyScope, policy: &TrustPolicy) -> io::Result<PathBuf> {
+    let path = policy_path(scope);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let text = toml::to_string_pretty(policy).map_err(io::Error::other)?;
+    fs::write(&path, text)?;
+    Ok(path)
+}
+
+/// The system and user policies combined.
+///
+/// The stricter mode wins. The user's keys, hashes and patterns and the keys in
+/// PYTRON_HOME/trusted_keys extend the system lists, unless the system policy
+/// enforces: then only what the administrator allowed may run, so a user
+/// policy can never weaken it.
+pub fn effective_policy() -> io::Result<TrustPolicy> {
+    let system = load_policy(PolicyScope::System)?;
+    if system.mode == EnforcementMode::Enforce {
+        return Ok(system);
+    }
+    let user = load_policy(PolicyScope::User)?;
+
+    let mut merged = system.clone();
+    merged.mode = system.mode.max(user.mode);
+    merged.trusted_keys_dir = true;
+    for key in user.keys {
+        if !merged.keys.iter().any(|k| k.key == key.key) {
+            merged.keys.push(key);
+        }
+    }
+    for hash in user.hashes {
+        if !merged.hashes.contains(&hash) {
+            merged.hashes.push(hash);
+        }
+    }
+    for pattern in user.patterns {
+        if !merged.patterns.contains(&pattern) {
+            merged.patterns.push(pattern);
+        }
+    }
+    Ok(merged)
+}
+
+/// Parse the public keys of a policy, skipping (and reporting) malformed ones
+fn policy_keys(policy: &TrustPolicy) -> Vec<(PathBuf, VerifyingKey)> {
+    policy
+        .keys
+        .iter()
+        .filter_map(|entry| match signing::parse_public_key(&entry.key) {
+            Ok(key) => Some((
+                PathBuf::from(format!("trust policy key '{}'", entry.name)),
+                key,
+            )),
+            Err(err) => {
+                status!(
+                    "Warning: ignoring trust policy key '{}': {}",
+                    entry.name, err
+                );
+                None
+            }
+        })
+        .collect()
+}
+
+fn matches_pattern(pattern: &str, archive_path: &Path) -> bool {
+    let Ok(glob) = globset::Glob::new(pattern) else {
+        status!("Warning: ignoring invalid trust pattern '{}'", pattern);
+        return false;
+    };
+    let matcher = glob.compile_matcher();
+    if pattern.contains('/') {
+        let full = archive_path
+            .canonicalize()
+            .unwrap_or_else(|_| archive_path.to_path_buf());
+        matcher.is_match(full.to_string_lossy().replace('\\', "/"))
+    } else {
+        archive_path
+            .file_name()
+            .is_some_and(|name| matcher.is_match(name))
+    }
+}
+
+/// Outcome of checking an archive against the trust policy, with the reason
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TrustDecision {
+    Allowed(String),
+    NotAllowed(String),
+    /// The archive carries a signature that does not match its contents
+    InvalidSignature(String),
+}
+
+/// Decide whether the policy allows an archive
+pub fn evaluate<R: Read + Seek>(
+    policy: &TrustPolicy,
+    archive_path: &Path,
+    archive: &mut ZipArchive<R>,
+) -> io::Result<TrustDecision> {
+    let mut trusted = policy_keys(policy);
+    if policy.trusted_keys_dir {
+        trusted.extend(signing::load_trusted_keys()?);
+    }
+
+    let signature = signing::verify_archive_with(archive, &trusted)?;
+    match &signature {
+        SignatureStatus::Invalid(reason) => {
+            return Ok(TrustDecision::InvalidSignature(format!(
+                "archive signature is invalid: {}",
+                reason
+            )))
+        }
+        SignatureStatus::Valid {
+            key_id,
+            trusted_key: Some(source),
+        } => {
+            return Ok(TrustDecision::Allowed(format!(
+                "signed by trusted key {} ({})",
+                key_id,
+                source.display()
+            )))
+        }
+        _ => {}
+    }
+
+    if !policy.hashes.is_empty() {
+        let hash = sha256_file(archive_path)?;
+        if policy.hashes.iter().any(|h| h.eq_ignore_ascii_case(&hash)) {
+            return Ok(TrustDecision::Allowed(format!(
+                "archive hash {} is allowed",
+                hash
+            )));
+        }
+    }
+
+    if let Some(pattern) = policy
+        .patterns
+        .iter()
+        .find(|pattern| matches_pattern(pattern, archive_path))
+    {
+        return Ok(TrustDecision::Allowed(format!(
+            "archive name matches pattern '{}'",
+            pattern
+        )));
+    }
+
+    Ok(TrustDecision::NotAllowed(match signature {
+        SignatureStatus::Valid { key_id, .. } => {
+            format!("archive is signed by untrusted key {}", key_id)
+        }
+        _ => "archive is not signed and not allowed by hash or name".to_string(),
+    }))
+}
+
+/// Apply the trust policy before an archive is extracted.
+///
+/// `require_signature` forces enforcement regardless of the configured mode.
+/// A broken signature is refused in every mode.
+pub fn enforce_policy<R: Read + Seek>(
+    archive_path: &Path,
+    archive: &mut ZipArchive<R>,
+    require_signature: bool,
+) -> io::Result<()> {
+    let policy = effective_policy()?;
+    let mode = if require_signature {
+        EnforcementMode::Enforce
+    } else {
+        policy.mode
+    };
+
+    match evaluate(&policy, archive_path, archive)? {
+        TrustDecision::Allowed(reason) => {
+            status!("Archive trusted: {}", reason);
+            Ok(())
+        }
+        TrustDecision::InvalidSignature(reason) => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Refusing to run {}: {}", archive_path.display(), reason),
+        )),
+        TrustDecision::NotAllowed(reason) => match mode {
+            EnforcementMode::Off => Ok(()),
+            EnforcementMode::Warn => {
+                status!("Warning: {} is not trusted: {}", archive_path.display(), reason);
+                Ok(())
+            }
+            EnforcementMode::Enforce => {
+                let hint = if policy.trusted_keys_dir {
+                    format!(
+                        "Trust it with `pytron trust add` or add its public key to {}",
+                        signing::get_trusted_keys_dir().display()
+                    )
+                } else {
+                    format!(
+                        "The system policy enforces, only an administrator can trust it in {}",
+                        policy_path(PolicyScope::System).display()
+                    )
+                };
+                Err(io::Error::new(
+                    io::ErrorKind::PermissionDenied,
+                    format!(
+                        "Refusing to run {}: {}. {}",
+                        archive_path.display(),
+                        reason,
+                        hint
+                    ),
+                ))
+            }
+        },
+    }
+}
+
+/// What a `pytron trust add|remove` invocation refers to
+#[derive(Debug, Clone)]
+pub enum TrustEntry {
+    /// A public key file (add) or a key name, id or file (remove)
+    Key(String),
+    Hash(String),
+    /// An archive file, trusted by its SHA-256
+    Archive(PathBuf),
+    Pattern(String),
+}
+
+/// Add an entry to the policy for `scope`. Returns a description of what was added.
+pub fn add_entry(scope: PolicyScope, entry: &TrustEntry, name: Option<&str>) -> io::Result<String> {
+    let mut policy = load_policy(scope)?;
+    let added = match entry {
+        TrustEntry::Key(path) => {
+            let path = Path::new(path);
+            let key = signing::read_public_key(path)?;
+            let key_text = signing::format_public_key(&key);
+            let name = name
+                .map(str::to_string)
+                .or_else(|| path.file_stem().map(|s| s.to_string_lossy().to_string()))
+                .unwrap_or_else(|| signing::key_id(&key));
+            policy.keys.retain(|k| k.key != key_text && k.name != name);
+            policy.keys.push(TrustedKey {
+                name: name.clone(),
+                key: key_text,
+            });
+            format!("key '{}' ({})", name, signing::key_id(&key))
+        }
+        TrustEntry::Hash(hash) => {
+            let hash = hash.trim().to_lowercase();
+            if hash.len() != 64 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("'{}' is not a SHA-256 hex digest", hash),
+                ));
+            }
+            if !policy.hashes.contains(&hash) {
+                policy.hashes.push(hash.clone());
+            }
+            format!("hash {}", hash)
+        }
+        TrustEntry::Archive(path) => {
+            let hash = sha256_file(path)?;
+            if !policy.hashes.contains(&hash) {
+                policy.hashes.push(hash.clone());
+            }
+            format!("hash {} of {}", hash, path.display())
+        }
+        TrustEntry::Pattern(pattern) => {
+            globset::Glob::new(pattern).map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Invalid pattern: {}", e),
+                )
+            })?;
+            if !policy.patterns.contains(pattern) {
+                policy.patterns.push(pattern.clone());
+            }
+            format!("pattern '{}'", pattern)
+        }
+    };
+    let path = save_policy(scope, &policy)?;
+    Ok(format!("Added {} to {}", added, path.display()))
+}
+
+/// Remove an entry from the policy for `scope`. Returns a description of what was removed.
+pub fn remove_entry(scope: PolicyScope, entry: &TrustEntry) -> io::Result<String> {
+    let mut policy = load_policy(scope)?;
+    let before = (
+        policy.keys.len(),
+        policy.hashes.len(),
+        policy.patterns.len(),
+    );
+    let what = match entry {
+        TrustEntry::Key(selector) => {
+            // A key can be named by policy name, key id or public key file
+            let from_file = signing::read_public_key(Path::new(selector))
+                .ok()
+                .map(|key| signing::format_public_key(&key));
+            policy.keys.retain(|k| {
+                let id = signing::parse_public_key(&k.key)
+                    .map(|key| signing::key_id(&key))
+                    .unwrap_or_default();
+                k.name != *selector && id != *selector && Some(&k.key) != from_file.as_ref()
+            });
+            format!("key '{}'", selector)
+        }
+        TrustEntry::Hash(hash) => {
+            let hash = hash.trim().to_lowercase();
+            policy.hashes.retain(|h| *h != hash);
+            format!("hash {}", hash)
+        }
+        TrustEntry::Archive(path) => {
+            let hash = sha256_file(path)?;
+            policy.hashes.retain(|h| *h != hash);
+            format!("hash {} of {}", hash, path.display())
+        }
+        TrustEntry::Pattern(pattern) => {
+            policy.patterns.retain(|p| p != pattern);
+            format!("pattern '{}'", pattern)
+        }
+    };
+    if before
+        == (
+            policy.keys.len(),
+            policy.hashes.len(),
+            policy.patterns.len(),
+        )
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{} is not in {}", what, policy_path(scope).display()),
+        ));
+    }
+    let path = save_policy(scope, &policy)?;
+    Ok(format!("Removed {} from {}", what, path.display()))
+}
+
+/// Set the enforcement mode of the policy for `scope`
+pub fn set_mode(scope: PolicyScope, mode: EnforcementMode) -> io::Result<PathBuf> {
+    let mut policy = load_policy(scope)?;
+    policy.mode = mode;
+    save_policy(scope, &policy)
+}
+
+/// Render both policies and the effective mode for `pytron trust list`
+pub fn format_policies() -> io::Result<String> {
+    let mut out = String::new();
+    for (label, scope) in [("System", PolicyScope::System), ("User", PolicyScope::User)] {
+        let policy = load_policy(scope)?;
+        out.push_str(&format!(
+            "{} policy ({}): mode {}\n",
+            label,
+            policy_path(scope).display(),
+            policy.mode
+        ));
+        for key in &policy.keys {
+            let id = signing::parse_public_key(&key.key)
+                .map(|k| signing::key_id(&k))
+                .unwrap_or_else(|_| "invalid".to_string());
+            out.push_str(&format!("  key     {} ({})\n", key.name, id));
+        }
+        for hash in &policy.hashes {
+            out.push_str(&format!("  hash    {}\n", hash));
+        }
+        for pattern in &policy.patterns {
+            out.push_str(&format!("  pattern {}\n", pattern));
+        }
+    }
+
+    let trusted_dir = signing::get_trusted_keys_dir();
+    let dir_keys = signing::load_trusted_keys()?;
+    out.push_str(&format!(
+        "Trusted keys directory ({}):\n",
+        trusted_dir.display()
+    ));
+    for (path, key) in dir_keys {
+        out.push_str(&format!(
+            "  key     {} ({})\n",
+            path.display(),
+            signing::key_id(&key)
+        ));
+    }
+
+    let effective = effective_policy()?;
+    out.push_str(&format!("Effective mode: {}\n", effective.mode));
+    if !effective.trusted_keys_dir {
+        out.push_str("The system policy enforces, user entries and trusted keys are ignored\n");
+    }
+    Ok(out)
+}
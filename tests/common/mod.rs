@@ -1,3 +1,47 @@
-// This module previously contained common test utility functions
-// that are now handled directly in the test files.
-// We're keeping the module for organizational purposes and potential future utility functions.
+// Helpers shared by the integration tests. Each test crate uses only some of them.
+#![allow(dead_code)]
+
+use pytron::zip_directory;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// The uv project files of the SBOM fixture, for tests that need dependencies
+pub const UV_PROJECT_FILES: [&str; 2] = ["pyproject.toml", "uv.lock"];
+
+// Helper function to write a project made of (relative path, contents) pairs to `dir/project`
+pub fn create_project(dir: &Path, files: &[(&str, &str)]) -> PathBuf {
+    let project = dir.join("project");
+    fs::create_dir_all(&project).unwrap();
+    for (name, contents) in files {
+        let path = project.join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+    project
+}
+
+// Helper function to copy the pyproject.toml and uv.lock of the SBOM fixture into a project
+pub fn add_uv_project_files(project: &Path) {
+    for name in UV_PROJECT_FILES {
+        fs::copy(Path::new("tests/fixtures/sbom_project").join(name), project.join(name)).unwrap();
+    }
+}
+
+// Helper function to archive a project, AES-encrypted if a password is given
+pub fn zip_project(project: &Path, output: &Path, password: Option<&str>) {
+    zip_directory(
+        project.to_str().unwrap(),
+        output.to_str().unwrap(),
+        None,
+        password.map(str::to_string).as_ref(),
+    )
+    .expect("Failed to create archive");
+}
+
+// Helper function to create `dir/robot.zip` of a project made of (relative path, contents) pairs
+pub fn create_archive(dir: &Path, files: &[(&str, &str)], password: Option<&str>) -> PathBuf {
+    let project = create_project(dir, files);
+    let archive = dir.join("robot.zip");
+    zip_project(&project, &archive, password);
+    archive
+}
@@ -0,0 +1,134 @@
+use pytron::archive::{self, ArchiveError};
+use pytron::{run_from_zip_with_options, RunOptions};
+use serial_test::serial;
+use std::env;
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use tempfile::tempdir;
+use zip::write::SimpleFileOptions;
+
+// Helper function to write an archive where only secret.py is AES-encrypted
+fn create_mixed_archive(dir: &Path) -> PathBuf {
+    let path = dir.join("mixed.zip");
+    let mut writer = zip::ZipWriter::new(File::create(&path).unwrap());
+    writer
+        .start_file("main.py", SimpleFileOptions::default())
+        .unwrap();
+    writer.write_all(b"print('plain')\n").unwrap();
+    writer
+        .start_file(
+            "secret.py",
+            SimpleFileOptions::default().with_aes_encryption(zip::AesMode::Aes256, "fooPass"),
+        )
+        .unwrap();
+    writer.write_all(b"print('secret')\n").unwrap();
+    writer.finish().unwrap();
+    path
+}
+
+fn run_error(archive: &Path, password: Option<&str>) -> ArchiveError {
+    let options = RunOptions {
+        password: password.map(String::from),
+        ..Default::default()
+    };
+    let err = run_from_zip_with_options(archive.to_str().unwrap(), "main.py", &[], &[], &options)
+        .expect_err("Running the archive should fail");
+    archive::archive_error(&err)
+        .unwrap_or_else(|| panic!("Expected an archive error, got: {}", err))
+        .clone()
+}
+
+// Test the password required, wrong password and corrupt archive errors of run
+#[test]
+#[serial]
+fn test_run_reports_archive_errors() {
+    let pytron_home = tempdir().expect("Failed to create PYTRON_HOME directory");
+    env::set_var("PYTRON_HOME", pytron_home.path());
+    let dir = tempdir().expect("Failed to create temp directory");
+    let archive = create_mixed_archive(dir.path());
+
+    let err = run_error(&archive, None);
+    assert_eq!(
+        err,
+        ArchiveError::PasswordRequired {
+            encrypted: vec!["secret.py".to_string()],
+            total: 2,
+        }
+    );
+    assert_eq!(err.exit_code(), archive::EXIT_PASSWORD_REQUIRED);
+    assert!(err.to_string().contains("secret.py"));
+
+    let err = run_error(&archive, Some("wrongPassword"));
+    assert!(
+        matches!(err, ArchiveError::WrongPassword { .. }),
+        "Got: {:?}",
+        err
+    );
+    assert_eq!(err.exit_code(), archive::EXIT_WRONG_PASSWORD);
+
+    let garbage = dir.path().join("garbage.zip");
+    fs::write(&garbage, b"this is not a zip archive").unwrap();
+    let err = run_error(&garbage, None);
+    assert!(matches!(err, ArchiveError::Corrupt(_)), "Got: {:?}", err);
+    assert_eq!(err.exit_code(), archive::EXIT_CORRUPT_ARCHIVE);
+
+    env::remove_var("PYTRON_HOME");
+}
+
+// Test that a mixed archive reads plain entries without and encrypted ones with the password
+#[test]
+fn test_mixed_archive_entries() {
+    let dir = tempdir().expect("Failed to create temp directory");
+    let path = create_mixed_archive(dir.path());
+    let mut zip = archive::open_archive(&path).unwrap();
+
+    assert_eq!(
+        archive::encrypted_entries(&mut zip).unwrap(),
+        vec!["secret.py".to_string()]
+    );
+    archive::check_password(&mut zip, Some("fooPass")).expect("Password should be accepted");
+
+    for i in 0..zip.len() {
+        let mut content = Vec::new();
+        let mut entry = archive::read_entry(&mut zip, i, Some("fooPass")).unwrap();
+        archive::copy_entry(&mut entry, &mut content).unwrap();
+        assert!(!content.is_empty());
+    }
+
+    let mut plain = String::new();
+    archive::read_entry(&mut zip, 0, None)
+        .expect("Plain entry needs no password")
+        .read_to_string(&mut plain)
+        .unwrap();
+    assert_eq!(plain, "print('plain')\n");
+}
+
+// Test that damaged entry data is reported as a corrupt archive
+#[test]
+fn test_damaged_entry_is_corrupt() {
+    let dir = tempdir().expect("Failed to create temp directory");
+    let path = dir.path().join("damaged.zip");
+    let mut writer = zip::ZipWriter::new(File::create(&path).unwrap());
+    let stored = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored);
+    writer.start_file("main.py", stored).unwrap();
+    writer.write_all(b"print('intact')\n").unwrap();
+    writer.finish().unwrap();
+
+    // Flip a byte of the stored file data so the CRC no longer matches
+    let mut data = fs::read(&path).unwrap();
+    let pos = data
+        .windows(6)
+        .position(|w| w == b"intact")
+        .expect("Stored data should be present");
+    data[pos] ^= 0xff;
+    fs::write(&path, data).unwrap();
+
+    let mut zip = archive::open_archive(&path).unwrap();
+    let mut entry = archive::read_entry(&mut zip, 0, None).unwrap();
+    let err = archive::copy_entry(&mut entry, &mut Vec::new()).expect_err("CRC should not match");
+    assert!(matches!(
+        archive::archive_error(&err),
+        Some(ArchiveError::Corrupt(_))
+    ));
+}
@@ -0,0 +1,146 @@
+use serial_test::serial;
+use std::env;
+use std::ffi::OsStr;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+use tempfile::tempdir;
+
+fn command_env(cmd: &std::process::Command, var: &str) -> Option<String> {
+    cmd.get_envs()
+        .find(|(name, _)| *name == OsStr::new(var))
+        .and_then(|(_, value)| value)
+        .map(|value| value.to_string_lossy().to_string())
+}
+
+fn write_file(path: &Path, size: usize) {
+    fs::create_dir_all(path.parent().unwrap()).expect("Failed to create directory");
+    fs::write(path, vec![b'x'; size]).expect("Failed to write file");
+}
+
+// Test that uv is pointed at directories inside PYTRON_HOME
+#[test]
+#[serial]
+fn test_uv_command_isolated_in_pytron_home() {
+    let pytron_home = tempdir().expect("Failed to create PYTRON_HOME directory");
+    env::set_var("PYTRON_HOME", pytron_home.path());
+    for dir in pytron::UvStateDir::ALL {
+        env::remove_var(dir.env_var());
+    }
+
+    let cmd = pytron::get_uv_command();
+    env::remove_var("PYTRON_HOME");
+
+    let home = pytron_home.path();
+    assert_eq!(
+        command_env(&cmd, "UV_CACHE_DIR"),
+        Some(home.join("cache").join("uv").to_string_lossy().to_string())
+    );
+    assert_eq!(
+        command_env(&cmd, "UV_PYTHON_INSTALL_DIR"),
+        Some(home.join("python").to_string_lossy().to_string())
+    );
+    assert_eq!(
+        command_env(&cmd, "UV_TOOL_DIR"),
+        Some(home.join("tools").to_string_lossy().to_string())
+    );
+}
+
+// Test that an explicitly set uv variable wins over the PYTRON_HOME default
+#[test]
+#[serial]
+fn test_uv_state_dir_can_be_overridden() {
+    let pytron_home = tempdir().expect("Failed to create PYTRON_HOME directory");
+    let shared_cache = tempdir().expect("Failed to create cache directory");
+    env::set_var("PYTRON_HOME", pytron_home.path());
+    env::set_var("UV_CACHE_DIR", shared_cache.path());
+
+    let cmd = pytron::get_uv_command();
+    let areas = pytron::cache::cache_info();
+    env::remove_var("UV_CACHE_DIR");
+    env::remove_var("PYTRON_HOME");
+
+    assert_eq!(
+        command_env(&cmd, "UV_CACHE_DIR"),
+        Some(shared_cache.path().to_string_lossy().to_string())
+    );
+    let uv_cache = areas.iter().find(|a| a.name == "uv cache").unwrap();
+    assert!(!uv_cache.managed, "External cache must not be managed");
+}
+
+// Test cache info, prune and clear on a populated PYTRON_HOME
+#[test]
+#[serial]
+fn test_cache_info_prune_and_clear() {
+    let pytron_home = tempdir().expect("Failed to create PYTRON_HOME directory");
+    let home = pytron_home.path();
+    env::set_var("PYTRON_HOME", home);
+
+    write_file(&home.join("cache/uv/wheels/a.whl"), 1000);
+    write_file(&home.join("python/cpython-3.12/bin/python"), 500);
+    write_file(&home.join("temp/pytron_abc/main.py"), 10);
+    write_file(&home.join("pytron_download_xyz/uv.tar.gz"), 20);
+    write_file(&home.join("keep.txt"), 5);
+
+    let areas = pytron::cache::cache_info();
+    let size_of = |name: &str| areas.iter().find(|a| a.name == name).unwrap().size;
+    assert_eq!(size_of("uv cache"), 1000);
+    assert_eq!(size_of("python installs"), 500);
+    assert_eq!(size_of("temp"), 10);
+    assert!(pytron::cache::format_cache_info(&areas).contains("total"));
+
+    // Nothing is old enough yet
+    let freed = pytron::cache::prune_cache(Duration::from_secs(3600)).expect("Prune failed");
+    assert_eq!(freed, 0);
+    assert!(home.join("temp/pytron_abc").exists());
+
+    let freed = pytron::cache::prune_cache(Duration::ZERO).expect("Prune failed");
+    assert_eq!(freed, 30);
+    assert!(!home.join("temp/pytron_abc").exists());
+    assert!(!home.join("pytron_download_xyz").exists());
+
+    let freed = pytron::cache::clear_cache(false).expect("Clear failed");
+    assert_eq!(freed, 1000);
+    assert!(!home.join("cache/uv").exists());
+    assert!(
+        home.join("python").exists(),
+        "Python installs kept without --all"
+    );
+
+    pytron::cache::clear_cache(true).expect("Clear failed");
+    assert!(!home.join("python").exists());
+    assert!(
+        home.join("keep.txt").exists(),
+        "Unrelated files must be kept"
+    );
+
+    env::remove_var("PYTRON_HOME");
+}
+
+// Test that prune and clear leave the extraction directory of a running script alone
+#[test]
+#[serial]
+fn test_prune_skips_directories_in_use() {
+    let pytron_home = tempdir().expect("Failed to create PYTRON_HOME directory");
+    let home = pytron_home.path();
+    env::set_var("PYTRON_HOME", home);
+
+    write_file(&home.join("temp/pytron_busy/main.py"), 10);
+    write_file(&home.join("temp/pytron_done/main.py"), 10);
+    let busy = pytron::cache::mark_in_use(&home.join("temp/pytron_busy"))
+        .expect("Failed to lock the extraction directory");
+
+    let freed = pytron::cache::prune_cache(Duration::ZERO).expect("Prune failed");
+    assert_eq!(freed, 10);
+    assert!(!home.join("temp/pytron_done").exists());
+    pytron::cache::clear_cache(false).expect("Clear failed");
+    assert!(home.join("temp/pytron_busy/main.py").exists());
+
+    drop(busy);
+    assert!(!home.join("temp/pytron_busy.lock").exists());
+    let freed = pytron::cache::prune_cache(Duration::ZERO).expect("Prune failed");
+    assert_eq!(freed, 10);
+    assert!(!home.join("temp/pytron_busy").exists());
+
+    env::remove_var("PYTRON_HOME");
+}
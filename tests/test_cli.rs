@@ -8,7 +8,7 @@ fn test_cli_parsing() {
     let args = vec!["pytron", "zip"];
     let cli = Cli::parse_from(args);
 
-    if let Commands::Zip { directory, output, ignore_patterns, password } = cli.command {
+    if let Commands::Zip { directory, output, ignore_patterns, password, .. } = cli.command {
         assert_eq!(directory, ".", "Default directory should be '.'");
         assert_eq!(
             output, "robot.zip",
@@ -25,7 +25,7 @@ fn test_cli_parsing() {
     let args = vec!["pytron", "zip", "--ignore-patterns", "node_modules,*.log,*.tmp"];
     let cli = Cli::parse_from(args);
 
-    if let Commands::Zip { directory, output, ignore_patterns, password } = cli.command {
+    if let Commands::Zip { directory, output, ignore_patterns, password, .. } = cli.command {
         assert_eq!(directory, ".", "Default directory should be '.'");
         assert_eq!(
             output, "robot.zip",
@@ -47,7 +47,7 @@ fn test_cli_parsing() {
     let args = vec!["pytron", "zip", "--ignore-patterns", ""];
     let cli = Cli::parse_from(args);
 
-    if let Commands::Zip { directory, output, ignore_patterns, password } = cli.command {
+    if let Commands::Zip { directory, output, ignore_patterns, password, .. } = cli.command {
         assert_eq!(directory, ".", "Default directory should be '.'");
         assert_eq!(
             output, "robot.zip",
@@ -73,6 +73,7 @@ fn test_cli_parsing() {
         password,
         uv_args,
         script_args,
+        ..
     } = cli.command
     {
         assert_eq!(
@@ -98,6 +99,7 @@ fn test_cli_parsing() {
         password,
         uv_args,
         script_args,
+        ..
     } = cli.command
     {
         assert_eq!(zipfile, "custom.zip", "Custom zip file name not matched");
@@ -128,6 +130,7 @@ fn test_cli_parsing() {
         password,
         uv_args,
         script_args,
+        ..
     } = cli.command
     {
         assert_eq!(zipfile, "custom.zip", "Custom zip file should be 'custom.zip'");
@@ -0,0 +1,191 @@
+use pytron::archive;
+use pytron::commands::{self, CommandTarget, NamedCommand};
+use pytron::run_args::parse_run_args;
+use pytron::{run_from_zip_with_options, RunOptions};
+use serial_test::serial;
+use std::env;
+use tempfile::tempdir;
+
+mod common;
+
+const PYPROJECT: &str = r#"
+[project]
+name = "robot"
+version = "0.1.0"
+
+[tool.pytron.commands]
+sync = "tools/sync.py"
+cleanup = "robot.cleanup:main"
+report = { script = "tools/report.py", args = ["--format", "md"], description = "Weekly report" }
+broken = "tools/missing.py"
+"#;
+
+// A project declaring named commands
+const PROJECT: &[(&str, &str)] = &[
+    ("pyproject.toml", PYPROJECT),
+    ("main.py", "print('main')\n"),
+    ("tools/sync.py", "print('sync')\n"),
+    ("tools/report.py", "print('report')\n"),
+];
+
+// Test the string and table forms of [tool.pytron.commands]
+#[test]
+fn test_parse_commands() {
+    let commands = commands::parse_commands(PYPROJECT).unwrap();
+    let names: Vec<_> = commands.iter().map(|c| c.name.as_str()).collect();
+    // Listed by name
+    assert_eq!(names, vec!["broken", "cleanup", "report", "sync"]);
+
+    assert_eq!(
+        commands[1].target,
+        CommandTarget::Module {
+            module: "robot.cleanup".to_string(),
+            function: "main".to_string(),
+        }
+    );
+    assert_eq!(
+        commands[2],
+        NamedCommand {
+            name: "report".to_string(),
+            target: CommandTarget::Script("tools/report.py".to_string()),
+            args: vec!["--format".to_string(), "md".to_string()],
+            description: Some("Weekly report".to_string()),
+        }
+    );
+
+    let listing = commands::format_commands(&commands);
+    assert!(listing.contains("report   Weekly report (tools/report.py --format md)"));
+
+    // Projects without commands declare none
+    assert!(commands::parse_commands("[project]\nname = \"x\"\n")
+        .unwrap()
+        .is_empty());
+}
+
+// Test that invalid declarations are rejected
+#[test]
+fn test_invalid_commands() {
+    for declaration in [
+        r#"x = "../escape.py""#,
+        r#"x = "robot.clean-up:main""#,
+        r#"x = "mod:func; import os""#,
+        r#"x = { script = "a.py", module = "a:b" }"#,
+        r#"x = { script = "a.py", args = [1] }"#,
+        r#""-x" = "a.py""#,
+        r#"x = 1"#,
+    ] {
+        let text = format!("[tool.pytron.commands]\n{}\n", declaration);
+        assert!(
+            commands::parse_commands(&text).is_err(),
+            "{} should be rejected",
+            declaration
+        );
+    }
+}
+
+// Test reading the commands from an archive and the --list option
+#[test]
+fn test_read_commands_from_archive() {
+    let dir = tempdir().expect("Failed to create temp directory");
+    let archive = common::create_archive(dir.path(), PROJECT, None);
+
+    let mut zip = archive::open_archive(&archive).unwrap();
+    let commands = commands::read_commands(&mut zip, None).unwrap();
+    assert_eq!(commands.len(), 4);
+
+    let args: Vec<String> = ["robot.zip", "--list"]
+        .iter()
+        .map(|a| a.to_string())
+        .collect();
+    assert!(parse_run_args(&args).unwrap().list_commands);
+
+    // Only an argument before `--` may name a command
+    let parse = |args: &[&str]| {
+        let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+        parse_run_args(&args).unwrap()
+    };
+    assert!(parse(&["robot.zip", "sync", "--", "x"]).may_name_command);
+    let run = parse(&["robot.zip", "--", "sync"]);
+    assert!(!run.may_name_command);
+    assert_eq!(run.script_args, vec!["sync"]);
+
+    let runner = commands::module_runner("cleanup", "robot.cleanup", "main");
+    assert_eq!(
+        runner,
+        "import sys; sys.argv[0] = \"cleanup\"; from robot.cleanup import main; sys.exit(main())"
+    );
+}
+
+// Test that run dispatches the first script argument to a named command
+#[test]
+#[serial]
+fn test_run_named_command() {
+    let pytron_home = tempdir().expect("Failed to create PYTRON_HOME directory");
+    env::set_var("PYTRON_HOME", pytron_home.path());
+    let dir = tempdir().expect("Failed to create temp directory");
+    let archive = common::create_archive(dir.path(), PROJECT, None);
+    let archive = archive.to_str().unwrap();
+    let options = RunOptions {
+        named_commands: true,
+        ..Default::default()
+    };
+
+    // The command's script replaces main.py, which exists
+    let err = run_from_zip_with_options(archive, "main.py", &[], &["broken".to_string()], &options)
+        .expect_err("The declared script is missing");
+    assert!(err.to_string().contains("tools/missing.py"), "{}", err);
+
+    // Without dispatch the name is just an argument of main.py
+    let result = run_from_zip_with_options(
+        archive,
+        "main.py",
+        &[],
+        &["broken".to_string()],
+        &RunOptions::default(),
+    );
+    if let Err(err) = result {
+        assert!(!err.to_string().contains("tools/missing.py"), "{}", err);
+        assert!(archive::archive_error(&err).is_none());
+    }
+
+    env::remove_var("PYTRON_HOME");
+}
+
+// Test that a run reports the command and the script it resolved to
+#[cfg(unix)]
+#[test]
+#[serial]
+fn test_run_reports_resolved_command() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let pytron_home = tempdir().expect("Failed to create PYTRON_HOME directory");
+    env::set_var("PYTRON_HOME", pytron_home.path());
+    // A stand-in for uv that succeeds without running anything
+    let uv = pytron_home.path().join("uv");
+    std::fs::write(&uv, "#!/bin/sh\nexit 0\n").unwrap();
+    std::fs::set_permissions(&uv, std::fs::Permissions::from_mode(0o755)).unwrap();
+    let dir = tempdir().expect("Failed to create temp directory");
+    let archive = common::create_archive(dir.path(), PROJECT, None);
+    let archive = archive.to_str().unwrap();
+    let options = RunOptions {
+        named_commands: true,
+        ..Default::default()
+    };
+
+    let outcome = run_from_zip_with_options(archive, "main.py", &[], &["report".to_string()], &options)
+        .expect("Failed to run the command");
+    assert_eq!(outcome.entry, "tools/report.py");
+    assert_eq!(outcome.command.as_deref(), Some("report"));
+    assert_eq!(outcome.exit_code, 0);
+
+    let outcome = run_from_zip_with_options(archive, "main.py", &[], &["cleanup".to_string()], &options)
+        .expect("Failed to run the command");
+    assert_eq!(outcome.entry, "robot.cleanup:main");
+
+    let outcome = run_from_zip_with_options(archive, "main.py", &[], &[], &options)
+        .expect("Failed to run the script");
+    assert_eq!(outcome.entry, "main.py");
+    assert!(outcome.command.is_none());
+
+    env::remove_var("PYTRON_HOME");
+}
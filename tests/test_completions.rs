@@ -0,0 +1,122 @@
+use pytron::completions::{complete, complete_run, generate_script, CompletionShell};
+use std::fs;
+use tempfile::tempdir;
+
+mod common;
+
+// A project declaring a named command
+const PROJECT: &[(&str, &str)] = &[
+    ("main.py", "print('hello')\n"),
+    ("tools/report.py", "print('report')\n"),
+    (
+        "pyproject.toml",
+        "[project]\nname = \"robot\"\n\n[tool.pytron.commands]\nsync = \"tools/report.py\"\n",
+    ),
+];
+
+fn words(args: &[&str]) -> Vec<String> {
+    args.iter().map(|arg| arg.to_string()).collect()
+}
+
+// Test completing the archive, its commands and scripts, and entries attached with ':'
+#[test]
+fn test_complete_archive_contents() {
+    let dir = tempdir().expect("Failed to create temp directory");
+    let archive = common::create_archive(dir.path(), PROJECT, None);
+    let archive = archive.to_str().unwrap().to_string();
+    fs::write(dir.path().join("notes.txt"), "").unwrap();
+
+    let prefix = format!("{}/", dir.path().display());
+    let targets = complete_run(&[], &prefix);
+    assert!(targets.contains(&archive), "{:?}", targets);
+    assert!(targets.contains(&format!("{}project/", prefix)), "{:?}", targets);
+    assert!(!targets.iter().any(|t| t.ends_with("notes.txt")), "{:?}", targets);
+
+    // Only named commands follow the archive, scripts are selected with ':' or --entry
+    assert_eq!(complete_run(&words(&[&archive]), ""), vec!["sync"]);
+    assert!(complete_run(&words(&[&archive]), "to").is_empty());
+    assert_eq!(
+        complete_run(&[], &format!("{}:t", archive)),
+        vec![format!("{}:tools/report.py", archive)]
+    );
+    // After the archive --entry is an argument of the script
+    assert!(!complete_run(&words(&[&archive, "--entry"]), "").contains(&"tools/report.py".to_string()));
+    // Only the first argument after the archive can name a command
+    assert!(!complete_run(&words(&[&archive, "sync"]), "").contains(&"sync".to_string()));
+}
+
+// Test that options are completed as `parse_run_args` accepts them
+#[test]
+fn test_complete_run_options() {
+    let before = complete_run(&[], "--");
+    assert!(before.contains(&"--sandbox".to_string()));
+    assert!(before.contains(&"--require-signature".to_string()));
+    assert!(before.contains(&"--uv-run-help".to_string()));
+
+    // After the archive, only --list is pytron's
+    let after = complete_run(&words(&["robot.zip"]), "--");
+    assert_eq!(after, vec!["--", "--list"]);
+    // and only until the first script argument
+    let after = complete_run(&words(&["robot.zip", "report"]), "--");
+    assert_eq!(after, vec!["--"]);
+
+    // Nothing is suggested for a literal password
+    assert!(complete_run(&words(&["--password"]), "").is_empty());
+    assert!(complete_run(&words(&["-p"]), "").is_empty());
+}
+
+// Test that option values complete files, also in the --flag=value form
+#[test]
+fn test_complete_option_values() {
+    let dir = tempdir().expect("Failed to create temp directory");
+    fs::write(dir.path().join("secret.txt"), "pw\n").unwrap();
+    fs::create_dir(dir.path().join("out")).unwrap();
+    let prefix = format!("{}/", dir.path().display());
+
+    assert_eq!(
+        complete_run(&words(&["--password-file"]), &format!("{}s", prefix)),
+        vec![format!("{}secret.txt", prefix)]
+    );
+    assert_eq!(
+        complete_run(&[], &format!("--password-file={}s", prefix)),
+        vec![format!("--password-file={}secret.txt", prefix)]
+    );
+    assert_eq!(
+        complete_run(&words(&["--sandbox", "--allow-write"]), &prefix),
+        vec![format!("{}out/", prefix)]
+    );
+    // After `--` every argument is the script's
+    assert_eq!(
+        complete_run(&words(&["robot.zip", "--", "--password-file"]), &format!("{}s", prefix)),
+        vec![format!("{}secret.txt", prefix)]
+    );
+}
+
+// Test that only `run` is completed dynamically
+#[test]
+fn test_complete_dispatch() {
+    assert!(complete(&words(&["pytron", "zip"]), "--").is_empty());
+    assert!(!complete(&words(&["pytron", "run"]), "--").is_empty());
+}
+
+// Test that the scripts route `run` to `pytron __complete` and keep clap's completions
+#[test]
+fn test_generate_scripts() {
+    let bash = generate_script(CompletionShell::Bash);
+    assert!(bash.contains("_pytron_clap() {"));
+    assert!(bash.contains("pytron __complete --current="));
+    assert!(bash.contains("complete -F _pytron"));
+    assert!(!bash.contains(" __complete uv"), "`__complete` is internal and must not be offered");
+
+    let zsh = generate_script(CompletionShell::Zsh);
+    assert!(zsh.contains("_pytron_clap() {"));
+    assert!(zsh.contains("pytron __complete --current="));
+
+    let fish = generate_script(CompletionShell::Fish);
+    assert!(fish.contains("pytron __complete --current="));
+    assert!(!fish.contains("__fish_pytron_using_subcommand run\" -l sandbox"));
+
+    let powershell = generate_script(CompletionShell::Powershell);
+    assert!(powershell.contains("$global:PytronClapCompleter = {"));
+    assert_eq!(powershell.matches("Register-ArgumentCompleter").count(), 1);
+}
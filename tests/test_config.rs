@@ -0,0 +1,202 @@
+use clap::ValueEnum;
+use pytron::config::{Config, ConfigSource, SETTINGS};
+use pytron::scan::ScanMode;
+use pytron::run_args::parse_run_args;
+use serial_test::serial;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::{tempdir, TempDir};
+
+// System, user and project directories with PYTRON_* variables pointing at them
+struct Layers {
+    system: TempDir,
+    home: TempDir,
+    project: TempDir,
+}
+
+impl Layers {
+    fn new() -> Self {
+        let layers = Layers {
+            system: tempdir().expect("Failed to create system config directory"),
+            home: tempdir().expect("Failed to create PYTRON_HOME directory"),
+            project: tempdir().expect("Failed to create project directory"),
+        };
+        env::set_var(pytron::SYSTEM_CONFIG_DIR_ENV, layers.system.path());
+        env::set_var("PYTRON_HOME", layers.home.path());
+        for setting in SETTINGS {
+            env::remove_var(setting.env_var());
+        }
+        layers
+    }
+
+    fn write(&self, dir: &Path, name: &str, text: &str) -> PathBuf {
+        let path = dir.join(name);
+        fs::write(&path, text).expect("Failed to write configuration");
+        path
+    }
+
+    fn load(&self) -> std::io::Result<Config> {
+        Config::load(self.project.path())
+    }
+}
+
+impl Drop for Layers {
+    fn drop(&mut self) {
+        env::remove_var(pytron::SYSTEM_CONFIG_DIR_ENV);
+        env::remove_var("PYTRON_HOME");
+        for setting in SETTINGS {
+            env::remove_var(setting.env_var());
+        }
+    }
+}
+
+// Test that each layer overrides the ones below it and reports where values come from
+#[test]
+#[serial]
+fn test_layer_precedence() {
+    let layers = Layers::new();
+    let system = layers.write(
+        layers.system.path(),
+        "config.toml",
+        "[zip]\noutput = \"system.zip\"\nscan = \"fail\"\n[run]\nrequire_signature = true\n",
+    );
+    let user = layers.write(
+        layers.home.path(),
+        "config.toml",
+        "[zip]\noutput = \"user.zip\"\nignore_patterns = [\"*.log\"]\n",
+    );
+    let project = layers.write(
+        layers.project.path(),
+        "pytron.toml",
+        "[zip]\noutput = \"project.zip\"\n[run]\nuv_args = [\"--python\", \"3.12\"]\n",
+    );
+    env::set_var("PYTRON_RUN_UV_ARGS", "--offline -q");
+
+    let config = layers.load().unwrap();
+    assert_eq!(config.get_str("zip.output").unwrap(), "project.zip");
+    assert_eq!(config.get("zip.output").unwrap().1, ConfigSource::Project(project));
+    assert_eq!(config.get("zip.scan").unwrap().1, ConfigSource::System(system));
+    assert_eq!(config.get_list("zip.ignore_patterns").unwrap(), vec!["*.log"]);
+    assert_eq!(config.get("zip.ignore_patterns").unwrap().1, ConfigSource::User(user));
+    assert!(config.get_bool("run.require_signature"));
+    // Lists are replaced by higher layers, not merged
+    assert_eq!(config.get_list("run.uv_args").unwrap(), vec!["--offline", "-q"]);
+    assert_eq!(
+        config.get("run.uv_args").unwrap().1,
+        ConfigSource::Env("PYTRON_RUN_UV_ARGS".to_string())
+    );
+    // Unset settings fall back to their defaults
+    assert_eq!(config.get("run.archive").unwrap().1, ConfigSource::Default);
+    assert!(config.get("run.password_file").is_none());
+
+    let text = config.to_text();
+    assert!(text.contains("zip.output"));
+    assert!(text.contains("PYTRON_RUN_UV_ARGS"));
+}
+
+// Test that [tool.pytron] in pyproject.toml is read when there is no pytron.toml
+#[test]
+#[serial]
+fn test_pyproject_tool_table() {
+    let layers = Layers::new();
+    let pyproject = layers.write(
+        layers.project.path(),
+        "pyproject.toml",
+        "[project]\nname = \"robot\"\n\n[tool.pytron.zip]\noutput = \"dist/robot.zip\"\n\n[tool.pytron.commands]\nsync = \"tools/sync.py\"\n",
+    );
+
+    let config = layers.load().unwrap();
+    assert_eq!(config.get_str("zip.output").unwrap(), "dist/robot.zip");
+    assert_eq!(config.get("zip.output").unwrap().1, ConfigSource::Project(pyproject));
+
+    // pytron.toml takes the place of [tool.pytron]
+    layers.write(layers.project.path(), "pytron.toml", "[run]\narchive = \"app.zip\"\n");
+    let config = layers.load().unwrap();
+    assert_eq!(config.get("zip.output").unwrap().1, ConfigSource::Default);
+    assert_eq!(config.get_str("run.archive").unwrap(), "app.zip");
+}
+
+// Test that unknown settings and values of the wrong type are reported with their origin
+#[test]
+#[serial]
+fn test_invalid_configuration() {
+    let layers = Layers::new();
+    let path = layers.write(layers.home.path(), "config.toml", "[zip]\noutptu = \"x.zip\"\n");
+    let err = layers.load().unwrap_err();
+    assert!(err.to_string().contains("zip.outptu"), "{}", err);
+    assert!(err.to_string().contains(&path.display().to_string()), "{}", err);
+
+    layers.write(layers.home.path(), "config.toml", "[zip]\nscan = \"loud\"\n");
+    let err = layers.load().unwrap_err();
+    assert!(err.to_string().contains("off, warn, fail"), "{}", err);
+    env::set_var("PYTRON_ZIP_SCAN", "Fail");
+    layers.write(layers.home.path(), "config.toml", "");
+    let err = layers.load().unwrap_err();
+    assert!(err.to_string().contains("PYTRON_ZIP_SCAN"), "{}", err);
+    env::remove_var("PYTRON_ZIP_SCAN");
+
+    // The accepted values are exactly the scan modes
+    let scan = SETTINGS.iter().find(|setting| setting.key == "zip.scan").unwrap();
+    let modes: Vec<String> = ScanMode::value_variants()
+        .iter()
+        .map(|mode| mode.to_possible_value().unwrap().get_name().to_string())
+        .collect();
+    assert_eq!(scan.choices, modes);
+    for mode in scan.choices {
+        env::set_var("PYTRON_ZIP_SCAN", mode);
+        assert!(layers.load().unwrap().get_enum::<ScanMode>("zip.scan").unwrap().is_some());
+    }
+    env::remove_var("PYTRON_ZIP_SCAN");
+
+    layers.write(layers.home.path(), "config.toml", "");
+    env::set_var("PYTRON_RUN_REQUIRE_SIGNATURE", "maybe");
+    let err = layers.load().unwrap_err();
+    assert!(err.to_string().contains("PYTRON_RUN_REQUIRE_SIGNATURE"), "{}", err);
+}
+
+// Test that run skips a pyproject.toml that is not valid TOML
+#[test]
+#[serial]
+fn test_lenient_project_configuration() {
+    let layers = Layers::new();
+    layers.write(layers.project.path(), "pyproject.toml", "[project\nname = \"other\"\n");
+    assert!(layers.load().is_err());
+    let config = Config::load_lenient(layers.project.path()).expect("Broken pyproject.toml should be skipped");
+    assert!(!config.files.iter().any(|file| matches!(file.source, ConfigSource::Project(_)) && file.found));
+
+    // Invalid settings in pytron's own table still count
+    layers.write(layers.project.path(), "pyproject.toml", "[tool]\npytron = { run = { archiv = \"app.zip\" } }\n");
+    assert!(Config::load_lenient(layers.project.path()).is_err());
+    layers.write(layers.project.path(), "pytron.toml", "[run\n");
+    assert!(Config::load_lenient(layers.project.path()).is_err());
+}
+
+// Test that configured run settings only fill in what the command line leaves open
+#[test]
+#[serial]
+fn test_run_args_apply_config() {
+    let layers = Layers::new();
+    layers.write(
+        layers.project.path(),
+        "pytron.toml",
+        "[run]\narchive = \"dist/robot.zip\"\nuv_args = [\"--offline\"]\npassword_env = \"ROBOT_PASSWORD\"\nidentity = [\"host.key\"]\n",
+    );
+    let config = layers.load().unwrap();
+    let args = |args: &[&str]| -> Vec<String> { args.iter().map(|a| a.to_string()).collect() };
+
+    let mut run = parse_run_args(&args(&["--entry", "tools/report.py"])).unwrap();
+    run.apply_config(&config).unwrap();
+    assert_eq!(run.target, "dist/robot.zip");
+    assert_eq!(run.entry_script(), "tools/report.py");
+    assert_eq!(run.uv_args, vec!["--offline"]);
+    assert_eq!(run.password.env.as_deref(), Some("ROBOT_PASSWORD"));
+    assert_eq!(run.identities, vec![PathBuf::from("host.key")]);
+
+    let mut run = parse_run_args(&args(&["-q", "--password-file", "pw.txt", "other.zip"])).unwrap();
+    run.apply_config(&config).unwrap();
+    assert_eq!(run.target, "other.zip");
+    assert_eq!(run.uv_args, vec!["-q"]);
+    assert_eq!(run.password.file, Some(PathBuf::from("pw.txt")));
+    assert!(run.password.env.is_none());
+}
@@ -0,0 +1,170 @@
+use pytron::archive;
+use pytron::diff::{diff_archives, ArchiveDiff, ArchiveSnapshot};
+use serial_test::serial;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::tempdir;
+
+mod common;
+
+// Helper function to create a project with uv project files
+fn create_project(dir: &Path) -> PathBuf {
+    let project = common::create_project(
+        dir,
+        &[
+            ("main.py", "import requests\n\nprint('hello')\n"),
+            ("tools/report.py", "pass\n"),
+        ],
+    );
+    common::add_uv_project_files(&project);
+    fs::write(project.join("logo.png"), [0x89, b'P', b'N', b'G', 0, 1]).unwrap();
+    project
+}
+
+// Helper function to compare two archives on disk
+fn diff(old: &Path, new: &Path, password: Option<&str>) -> std::io::Result<ArchiveDiff> {
+    let snapshot = |path: &Path| {
+        let zip = archive::open_archive_with_password(path, password)?;
+        ArchiveSnapshot::read(zip, path, password)
+    };
+    diff_archives(&mut snapshot(old)?, &mut snapshot(new)?)
+}
+
+// Create the old archive, change the project and create the new one
+fn create_versions(dir: &Path, password: Option<&str>) -> (PathBuf, PathBuf) {
+    let project = create_project(dir);
+    let old = dir.join("old.zip");
+    common::zip_project(&project, &old, password);
+
+    fs::write(project.join("main.py"), "import requests\n\nprint('hello, world')\n").unwrap();
+    fs::remove_file(project.join("tools/report.py")).unwrap();
+    fs::write(project.join("tools/sync.py"), "pass\n").unwrap();
+    fs::write(project.join("logo.png"), [0x89, b'P', b'N', b'G', 0, 2]).unwrap();
+    let pyproject = fs::read_to_string(project.join("pyproject.toml")).unwrap();
+    fs::write(
+        project.join("pyproject.toml"),
+        pyproject
+            .replace("0.1.0", "0.2.0")
+            .replace("[\"requests>=2.32\"]", "[\"requests>=2.32\", \"rich\"]"),
+    )
+    .unwrap();
+    let lock = fs::read_to_string(project.join("uv.lock")).unwrap();
+    fs::write(project.join("uv.lock"), lock.replace("2025.1.31", "2025.4.26")).unwrap();
+
+    let new = dir.join("new.zip");
+    common::zip_project(&project, &new, password);
+    (old, new)
+}
+
+// Test the entry, dependency and metadata changes between two versions
+#[test]
+#[serial]
+fn test_diff_archives() {
+    let pytron_home = tempdir().expect("Failed to create PYTRON_HOME directory");
+    env::set_var("PYTRON_HOME", pytron_home.path());
+    let dir = tempdir().expect("Failed to create temp directory");
+    let (old, new) = create_versions(dir.path(), None);
+
+    let report = diff(&old, &new, None).unwrap();
+    let names = |entries: &[pytron::diff::EntryChange]| -> Vec<String> {
+        entries.iter().map(|entry| entry.name.clone()).collect()
+    };
+    assert_eq!(names(&report.added), vec!["tools/sync.py"]);
+    assert_eq!(names(&report.removed), vec!["tools/report.py"]);
+
+    let modified: Vec<&str> = report.modified.iter().map(|m| m.name.as_str()).collect();
+    assert_eq!(modified, vec!["logo.png", "main.py", "pyproject.toml", "uv.lock"]);
+    assert!(report.modified[0].binary);
+    assert!(report.modified[0].diff.is_none());
+    let main = &report.modified[1];
+    assert_eq!((main.lines_added, main.lines_removed), (1, 1));
+    let unified = main.diff.as_deref().unwrap();
+    assert!(unified.contains("--- a/main.py\n+++ b/main.py\n"), "{}", unified);
+    assert!(unified.contains("-print('hello')\n+print('hello, world')\n"), "{}", unified);
+
+    let deps = &report.dependencies;
+    assert_eq!(deps.changed.len(), 1);
+    assert_eq!(deps.changed[0].name, "certifi");
+    assert_eq!(deps.changed[0].old, "2025.1.31");
+    assert_eq!(deps.changed[0].new, "2025.4.26");
+    assert!(deps.added.is_empty() && deps.removed.is_empty());
+    assert_eq!(deps.declared_added, vec!["rich"]);
+
+    let version = report.metadata.iter().find(|c| c.field == "version").unwrap();
+    assert_eq!(version.old.as_deref(), Some("0.1.0"));
+    assert_eq!(version.new.as_deref(), Some("0.2.0"));
+
+    let text = report.to_text();
+    assert!(text.contains("~ certifi 2025.1.31 -> 2025.4.26"), "{}", text);
+    assert!(text.contains("+ rich"), "{}", text);
+    assert!(text.contains("A tools/sync.py"), "{}", text);
+    assert!(text.contains("D tools/report.py"), "{}", text);
+    assert!(text.contains("M main.py (+1 -1)"), "{}", text);
+
+    let json = serde_json::to_value(&report).unwrap();
+    assert_eq!(json["added"][0]["name"], "tools/sync.py");
+    assert_eq!(json["dependencies"]["changed"][0]["new"], "2025.4.26");
+}
+
+// Test that comparing an archive with itself reports no differences
+#[test]
+#[serial]
+fn test_diff_identical() {
+    let pytron_home = tempdir().expect("Failed to create PYTRON_HOME directory");
+    env::set_var("PYTRON_HOME", pytron_home.path());
+    let dir = tempdir().expect("Failed to create temp directory");
+    let project = create_project(dir.path());
+    let path = dir.path().join("robot.zip");
+    common::zip_project(&project, &path, None);
+
+    let report = diff(&path, &path, None).unwrap();
+    assert!(report.is_empty());
+    assert_eq!(report.unchanged, 5);
+    assert!(report.to_text().contains("No differences"));
+}
+
+// Test that encrypted archives are compared with their password and fail without it
+#[test]
+#[serial]
+fn test_diff_encrypted() {
+    let pytron_home = tempdir().expect("Failed to create PYTRON_HOME directory");
+    env::set_var("PYTRON_HOME", pytron_home.path());
+    let dir = tempdir().expect("Failed to create temp directory");
+    let (old, new) = create_versions(dir.path(), Some("hunter2"));
+
+    let report = diff(&old, &new, Some("hunter2")).unwrap();
+    assert_eq!(report.modified.len(), 4);
+    assert!(report.modified[1].diff.is_some());
+
+    let err = diff(&old, &new, None).unwrap_err();
+    assert!(archive::archive_error(&err).is_some(), "{}", err);
+}
+
+// Test that a mode only known on one side is still rendered
+#[test]
+fn test_diff_mode_added() {
+    let report = ArchiveDiff {
+        old: "old.zip".to_string(),
+        new: "new.zip".to_string(),
+        metadata: Vec::new(),
+        dependencies: Default::default(),
+        added: Vec::new(),
+        removed: Vec::new(),
+        modified: vec![pytron::diff::ModifiedEntry {
+            name: "run.sh".to_string(),
+            old_size: 10,
+            new_size: 10,
+            old_mode: None,
+            new_mode: Some("0755".to_string()),
+            content_changed: false,
+            binary: false,
+            lines_added: 0,
+            lines_removed: 0,
+            diff: None,
+        }],
+        unchanged: 0,
+    };
+    let text = report.to_text();
+    assert!(text.contains("M run.sh (mode none -> 0755)"), "{}", text);
+}
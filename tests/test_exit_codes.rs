@@ -20,13 +20,13 @@ fn create_exit_code_test_directory() -> tempfile::TempDir {
 
         // Read the fixture content
         let content = std::fs::read_to_string(&fixture_path)
-            .expect(&format!("Failed to read fixture {}", fixture_path));
+            .unwrap_or_else(|_| panic!("Failed to read fixture {}", fixture_path));
 
         // Write it to the temp directory
         let mut file =
-            File::create(&dest_path).expect(&format!("Failed to create {}", dest_path.display()));
+            File::create(&dest_path).unwrap_or_else(|_| panic!("Failed to create {}", dest_path.display()));
         file.write_all(content.as_bytes())
-            .expect(&format!("Failed to write to {}", dest_path.display()));
+            .unwrap_or_else(|_| panic!("Failed to write to {}", dest_path.display()));
 
         // Set executable permissions on Unix
         #[cfg(unix)]
@@ -50,7 +50,7 @@ fn test_exit_code_handling() {
     let output_zip = test_dir.path().join("exit_code_test.zip");
 
     // Create the zip file
-    let _ = pytron::zip_directory(
+    pytron::zip_directory(
         test_dir.path().to_str().unwrap(),
         output_zip.to_str().unwrap(),
         None,
@@ -119,7 +119,7 @@ fn test_exit_code_forwarding_integration() {
     let output_zip = test_dir.path().join("exit_code_test.zip");
 
     // Create the zip file
-    let _ = pytron::zip_directory(
+    pytron::zip_directory(
         test_dir.path().to_str().unwrap(),
         output_zip.to_str().unwrap(),
         None,
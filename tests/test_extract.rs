@@ -0,0 +1,150 @@
+use pytron::archive::{self, ArchiveError};
+use pytron::extract::{extract_entries, verify_extracted, ExtractOptions, Extracted};
+use pytron::{manifest, run_from_zip};
+use serial_test::serial;
+use std::env;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+use tempfile::tempdir;
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+
+mod common;
+
+const PROJECT: &[(&str, &str)] = &[
+    ("main.py", "print('hello')\n"),
+    ("tools/report.py", "print('report')\n"),
+    ("tools/data/rows.csv", "a,b\n"),
+];
+
+// Helper function to extract an archive on disk
+fn extract(
+    path: &Path,
+    dest: &Path,
+    password: Option<&str>,
+    options: &ExtractOptions,
+) -> std::io::Result<Extracted> {
+    let mut zip = archive::open_archive(path)?;
+    archive::check_password(&mut zip, password)?;
+    extract_entries(&mut zip, dest, password, options)
+}
+
+// Helper function to create an archive with an entry escaping the extraction directory
+fn create_malicious_archive(path: &Path) {
+    let mut zip = ZipWriter::new(File::create(path).unwrap());
+    let options = SimpleFileOptions::default();
+    zip.start_file("main.py", options).unwrap();
+    zip.write_all(b"print('hi')\n").unwrap();
+    zip.start_file("../escaped.py", options).unwrap();
+    zip.write_all(b"evil()\n").unwrap();
+    zip.finish().unwrap();
+}
+
+// Test extracting a whole archive and checking it against its manifest
+#[test]
+fn test_extract_all() {
+    let dir = tempdir().expect("Failed to create temp directory");
+    let archive = common::create_archive(dir.path(), PROJECT, Some("fooPass"));
+    let dest = dir.path().join("out");
+
+    let extracted = extract(&archive, &dest, Some("fooPass"), &ExtractOptions::default()).unwrap();
+    assert_eq!(
+        fs::read_to_string(dest.join("tools/report.py")).unwrap(),
+        "print('report')\n"
+    );
+    assert!(dest.join(manifest::MANIFEST_ENTRY).exists());
+
+    let mut zip = archive::open_archive(&archive).unwrap();
+    let expected = manifest::read_manifest(&mut zip, Some("fooPass"))
+        .unwrap()
+        .unwrap();
+    assert_eq!(verify_extracted(&expected, &extracted).unwrap(), 3);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode(&dest.join("main.py")), 0o755);
+        assert_eq!(mode(&dest.join("tools/data/rows.csv")) & 0o111, 0);
+    }
+
+    // Existing files are only replaced with --overwrite
+    let err = extract(&archive, &dest, Some("fooPass"), &ExtractOptions::default()).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::AlreadyExists);
+    let overwrite = ExtractOptions {
+        overwrite: true,
+        ..Default::default()
+    };
+    assert!(extract(&archive, &dest, Some("fooPass"), &overwrite).is_ok());
+
+    // The password is checked before anything is written
+    let err = extract(&archive, &dir.path().join("none"), None, &overwrite).unwrap_err();
+    assert!(matches!(
+        archive::archive_error(&err),
+        Some(ArchiveError::PasswordRequired { .. })
+    ));
+    assert!(!dir.path().join("none").exists());
+}
+
+// Test --only and --strip-components
+#[test]
+fn test_extract_selection() {
+    let dir = tempdir().expect("Failed to create temp directory");
+    let archive = common::create_archive(dir.path(), PROJECT, None);
+
+    let dest = dir.path().join("only");
+    let options = ExtractOptions {
+        only: vec!["tools".to_string()],
+        ..Default::default()
+    };
+    let extracted = extract(&archive, &dest, None, &options).unwrap();
+    let mut names: Vec<_> = extracted
+        .files
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect();
+    names.sort();
+    assert_eq!(names, vec!["tools/data/rows.csv", "tools/report.py"]);
+    assert!(!dest.join("main.py").exists());
+
+    let dest = dir.path().join("stripped");
+    let options = ExtractOptions {
+        only: vec!["tools/*.py".to_string()],
+        strip_components: 1,
+        ..Default::default()
+    };
+    extract(&archive, &dest, None, &options).unwrap();
+    assert!(dest.join("report.py").exists());
+    assert!(!dest.join("data").exists());
+    assert!(!dest.join("main.py").exists());
+}
+
+// Test that entries escaping the destination are rejected before anything is written
+#[test]
+#[serial]
+fn test_extract_rejects_path_traversal() {
+    let dir = tempdir().expect("Failed to create temp directory");
+    let archive = dir.path().join("evil.zip");
+    create_malicious_archive(&archive);
+
+    let dest = dir.path().join("nested/out");
+    let err = extract(&archive, &dest, None, &ExtractOptions::default()).unwrap_err();
+    assert!(matches!(
+        archive::archive_error(&err),
+        Some(ArchiveError::Corrupt(_))
+    ));
+    assert!(!dest.exists());
+    assert!(!dir.path().join("nested/escaped.py").exists());
+
+    // run uses the same extraction
+    let pytron_home = tempdir().expect("Failed to create PYTRON_HOME directory");
+    env::set_var("PYTRON_HOME", pytron_home.path());
+    let err = run_from_zip(archive.to_str().unwrap(), None, "main.py", &[], &[]).unwrap_err();
+    assert_eq!(
+        archive::archive_error(&err).map(|e| e.exit_code()),
+        Some(archive::EXIT_CORRUPT_ARCHIVE)
+    );
+    assert!(!pytron_home.path().join("temp/escaped.py").exists());
+    env::remove_var("PYTRON_HOME");
+}
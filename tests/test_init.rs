@@ -0,0 +1,148 @@
+use pytron::init::{init_project, list_templates, Template, TemplateSource, TemplateVars};
+use serial_test::serial;
+use std::env;
+use std::fs;
+use std::path::Path;
+use tempfile::tempdir;
+
+// Helper function to find a template with PYTRON_HOME pointing at `home`
+fn find_template(home: &Path, name: &str) -> Template {
+    env::set_var("PYTRON_HOME", home);
+    let templates = list_templates().expect("Failed to list templates");
+    env::remove_var("PYTRON_HOME");
+    templates
+        .into_iter()
+        .find(|t| t.name == name)
+        .unwrap_or_else(|| panic!("Template {} not found", name))
+}
+
+// Test that project names are normalized into package and module names
+#[test]
+fn test_template_vars_normalize_name() {
+    let vars = TemplateVars::new("My Robot_2", "3.11");
+    assert_eq!(vars.name, "my-robot-2");
+    assert_eq!(vars.module, "my_robot_2");
+    assert_eq!(vars.python_version, "3.11");
+
+    assert_eq!(TemplateVars::new("2fa-bot", "3.12").module, "_2fa_bot");
+    assert_eq!(TemplateVars::new("--", "3.12").name, "project");
+}
+
+// Test that every builtin template is listed and generates the common files
+#[test]
+#[serial]
+fn test_builtin_templates() {
+    let home = tempdir().expect("Failed to create PYTRON_HOME directory");
+    for name in ["cli", "service", "data-pipeline"] {
+        let template = find_template(home.path(), name);
+        assert_eq!(template.source, TemplateSource::Builtin);
+
+        let dir = tempdir().expect("Failed to create temp directory");
+        let vars = TemplateVars::new("weekly report", "3.12");
+        let written = init_project(dir.path(), &template, &vars, false).unwrap();
+        assert!(written.len() >= 4);
+
+        let pyproject = fs::read_to_string(dir.path().join("pyproject.toml")).unwrap();
+        assert!(pyproject.contains("name = \"weekly-report\""));
+        assert!(pyproject.contains("requires-python = \">=3.12\""));
+        assert!(!pyproject.contains("{{"));
+        assert!(pyproject.contains("[tool.pytron.commands]"));
+        assert_eq!(
+            fs::read_to_string(dir.path().join(".python-version")).unwrap().trim(),
+            "3.12"
+        );
+        assert!(dir.path().join(".gitignore").exists());
+        assert!(dir.path().join("main.py").exists());
+    }
+}
+
+// Test that the data-pipeline template places its package under the module name
+#[test]
+#[serial]
+fn test_module_placeholder_in_paths() {
+    let home = tempdir().expect("Failed to create PYTRON_HOME directory");
+    let template = find_template(home.path(), "data-pipeline");
+    let dir = tempdir().expect("Failed to create temp directory");
+
+    init_project(dir.path(), &template, &TemplateVars::new("etl", "3.12"), false).unwrap();
+    assert!(dir.path().join("etl/steps.py").exists());
+    let main = fs::read_to_string(dir.path().join("main.py")).unwrap();
+    assert!(main.contains("from etl import steps"));
+}
+
+// Test that existing files are kept unless overwriting is requested
+#[test]
+#[serial]
+fn test_existing_files_need_force() {
+    let home = tempdir().expect("Failed to create PYTRON_HOME directory");
+    let template = find_template(home.path(), "cli");
+    let dir = tempdir().expect("Failed to create temp directory");
+    fs::write(dir.path().join("main.py"), "print('mine')\n").unwrap();
+    let vars = TemplateVars::new("robot", "3.12");
+
+    let err = init_project(dir.path(), &template, &vars, false).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::AlreadyExists);
+    assert!(err.to_string().contains("--force"));
+    // Nothing is written when a file is in the way
+    assert!(!dir.path().join("pyproject.toml").exists());
+    assert_eq!(
+        fs::read_to_string(dir.path().join("main.py")).unwrap(),
+        "print('mine')\n"
+    );
+
+    init_project(dir.path(), &template, &vars, true).unwrap();
+    assert!(fs::read_to_string(dir.path().join("main.py"))
+        .unwrap()
+        .contains("argparse"));
+}
+
+// Test that a user template in PYTRON_HOME/templates is listed and replaces common files
+#[test]
+#[serial]
+fn test_user_template() {
+    let home = tempdir().expect("Failed to create PYTRON_HOME directory");
+    let user = home.path().join("templates").join("scraper");
+    fs::create_dir_all(user.join("{{module}}")).unwrap();
+    fs::write(user.join(".description"), "web scraper\n").unwrap();
+    fs::write(user.join("pyproject.toml"), "[project]\nname = \"{{name}}\"\n").unwrap();
+    fs::write(user.join("{{module}}/crawl.py"), "# {{name}}\n").unwrap();
+    fs::write(user.join(".gitignore"), "output/\n").unwrap();
+
+    let template = find_template(home.path(), "scraper");
+    assert_eq!(template.description, "web scraper");
+    assert_eq!(template.source, TemplateSource::User(user.clone()));
+
+    let dir = tempdir().expect("Failed to create temp directory");
+    init_project(dir.path(), &template, &TemplateVars::new("shop-scraper", "3.13"), false).unwrap();
+    assert_eq!(
+        fs::read_to_string(dir.path().join("shop_scraper/crawl.py")).unwrap(),
+        "# shop-scraper\n"
+    );
+    assert_eq!(
+        fs::read_to_string(dir.path().join(".gitignore")).unwrap(),
+        "output/\n"
+    );
+    assert_eq!(
+        fs::read_to_string(dir.path().join(".python-version")).unwrap().trim(),
+        "3.13"
+    );
+    assert!(!dir.path().join(".description").exists());
+}
+
+// Test that a user template replaces the builtin template of the same name
+#[test]
+#[serial]
+fn test_user_template_overrides_builtin() {
+    let home = tempdir().expect("Failed to create PYTRON_HOME directory");
+    let user = home.path().join("templates").join("cli");
+    fs::create_dir_all(&user).unwrap();
+    fs::write(user.join("main.py"), "print('company cli')\n").unwrap();
+
+    env::set_var("PYTRON_HOME", home.path());
+    let templates = list_templates().unwrap();
+    env::remove_var("PYTRON_HOME");
+    let cli: Vec<&Template> = templates.iter().filter(|t| t.name == "cli").collect();
+    assert_eq!(cli.len(), 1);
+    assert_eq!(cli[0].source, TemplateSource::User(user));
+    assert!(templates.iter().any(|t| t.name == "service"));
+}
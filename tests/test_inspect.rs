@@ -0,0 +1,108 @@
+use pytron::archive;
+use pytron::inspect::{inspect_archive, ArchiveInfo, Container};
+use pytron::sealed;
+use serial_test::serial;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::tempdir;
+
+mod common;
+
+// Helper function to create an archive of a project with uv project files
+fn create_archive(dir: &Path, password: Option<&str>) -> PathBuf {
+    let main = "print('hello')\n".repeat(50);
+    let project = common::create_project(
+        dir,
+        &[
+            (".python-version", "3.12\n"),
+            ("main.py", &main),
+            ("tools/report.py", "pass\n"),
+        ],
+    );
+    common::add_uv_project_files(&project);
+    let archive = dir.join("robot.zip");
+    common::zip_project(&project, &archive, password);
+    archive
+}
+
+// Helper function to inspect an archive on disk
+fn inspect(path: &Path, password: Option<&str>) -> std::io::Result<ArchiveInfo> {
+    let mut zip = archive::open_archive_with_password(path, password)?;
+    inspect_archive(&mut zip, path, password)
+}
+
+// Test the entries, project details and metadata of a plain archive
+#[test]
+#[serial]
+fn test_inspect_archive() {
+    let pytron_home = tempdir().expect("Failed to create PYTRON_HOME directory");
+    env::set_var("PYTRON_HOME", pytron_home.path());
+    let dir = tempdir().expect("Failed to create temp directory");
+    let archive = create_archive(dir.path(), None);
+
+    let info = inspect(&archive, None).unwrap();
+    assert_eq!(info.container, Container::Zip);
+    assert_eq!(info.entry_script.as_deref(), Some("main.py"));
+    assert_eq!(
+        info.project.files,
+        vec!["pyproject.toml", "uv.lock", ".python-version"]
+    );
+    assert_eq!(info.project.name.as_deref(), Some("robot"));
+    assert_eq!(info.project.python_version.as_deref(), Some("3.12"));
+    assert_eq!(info.project.locked_packages, Some(2));
+    assert_eq!(info.metadata.signature, "unsigned");
+    assert_eq!(info.metadata.manifest_files, Some(5));
+
+    let main = info.entries.iter().find(|e| e.name == "main.py").unwrap();
+    assert_eq!(main.size, 750);
+    assert!(!main.encrypted);
+    assert_eq!(main.permissions(), "-rw-r--r--");
+
+    let text = info.to_text();
+    assert!(text.contains("Entry:      main.py"));
+    assert!(text.contains("tools/report.py"));
+    let tree = info.to_tree();
+    assert!(
+        tree.contains("├── tools/\n│   └── report.py (5 B)"),
+        "{}",
+        tree
+    );
+
+    let json = serde_json::to_value(&info).unwrap();
+    assert_eq!(json["container"], "zip");
+    assert_eq!(json["project"]["locked_packages"], 2);
+    assert!(json["entries"].as_array().unwrap().len() >= 6);
+
+    env::remove_var("PYTRON_HOME");
+}
+
+// Test that encrypted and sealed archives are inspected transparently
+#[test]
+#[serial]
+fn test_inspect_encrypted_archives() {
+    let pytron_home = tempdir().expect("Failed to create PYTRON_HOME directory");
+    env::set_var("PYTRON_HOME", pytron_home.path());
+    let dir = tempdir().expect("Failed to create temp directory");
+    let archive = create_archive(dir.path(), Some("fooPass"));
+
+    // Without the password the listing works but the project files stay unreadable
+    let info = inspect(&archive, None).unwrap();
+    assert!(info.entries.iter().all(|e| e.is_dir || e.encrypted));
+    assert_eq!(info.project.unreadable.len(), 3);
+    assert!(info.project.name.is_none());
+    assert!(info.metadata.manifest && info.metadata.manifest_files.is_none());
+
+    let info = inspect(&archive, Some("fooPass")).unwrap();
+    assert_eq!(info.project.name.as_deref(), Some("robot"));
+    assert!(info.to_text().contains("AES"));
+
+    let sealed = sealed::seal(&fs::read(&archive).unwrap(), "fooPass").unwrap();
+    fs::write(&archive, sealed).unwrap();
+    assert!(inspect(&archive, None).is_err());
+    let info = inspect(&archive, Some("fooPass")).unwrap();
+    assert_eq!(info.container, Container::Sealed);
+    assert_eq!(info.entry_script.as_deref(), Some("main.py"));
+
+    env::remove_var("PYTRON_HOME");
+}
@@ -0,0 +1,129 @@
+use pytron::archive::{self, ArchiveError};
+use pytron::manifest::{self, Manifest, Mismatch, MANIFEST_ENTRY};
+use pytron::run_from_zip;
+use serial_test::serial;
+use std::env;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+use tempfile::tempdir;
+use zip::write::SimpleFileOptions;
+use zip::{ZipArchive, ZipWriter};
+
+mod common;
+
+const PROJECT: &[(&str, &str)] = &[("main.py", "print('hello')\n"), ("lib/util.py", "VALUE = 1\n")];
+
+// Helper function to copy an archive, replacing, dropping or adding entries
+fn rewrite_archive(source: &Path, target: &Path, changes: &[(&str, Option<&str>)]) {
+    let mut input = ZipArchive::new(File::open(source).unwrap()).unwrap();
+    let mut output = ZipWriter::new(File::create(target).unwrap());
+    let options = SimpleFileOptions::default();
+
+    for i in 0..input.len() {
+        let mut entry = input.by_index(i).unwrap();
+        let name = entry.name().to_string();
+        let mut data = Vec::new();
+        entry.read_to_end(&mut data).unwrap();
+        match changes.iter().find(|(changed, _)| *changed == name) {
+            Some((_, None)) => continue,
+            Some((_, Some(content))) => data = content.as_bytes().to_vec(),
+            None => {}
+        }
+        output.start_file(name, options).unwrap();
+        output.write_all(&data).unwrap();
+    }
+    for (name, content) in changes {
+        if let (None, Some(content)) = (input.index_for_name(name), content) {
+            output.start_file(*name, options).unwrap();
+            output.write_all(content.as_bytes()).unwrap();
+        }
+    }
+    output.finish().unwrap();
+}
+
+fn verify(path: &Path) -> std::io::Result<usize> {
+    let mut zip = archive::open_archive(path)?;
+    manifest::verify_archive(&mut zip, None)
+}
+
+// Test that the manifest lists every file and round-trips through its text form
+#[test]
+fn test_manifest_written() {
+    let dir = tempdir().expect("Failed to create temp directory");
+    let archive = common::create_archive(dir.path(), PROJECT, None);
+
+    let mut zip = archive::open_archive(&archive).unwrap();
+    let manifest = manifest::read_manifest(&mut zip, None)
+        .unwrap()
+        .expect("Archive should have a manifest");
+    assert_eq!(
+        manifest.files.keys().collect::<Vec<_>>(),
+        vec!["lib/util.py", "main.py"]
+    );
+    assert_eq!(Manifest::parse(&manifest.to_text()).unwrap(), manifest);
+    assert!(!manifest.files.contains_key(MANIFEST_ENTRY));
+
+    assert_eq!(verify(&archive).unwrap(), 2);
+    assert!(Manifest::parse("not a manifest line\n").is_err());
+}
+
+// Test that modified, missing and extra entries are reported
+#[test]
+fn test_tampered_contents() {
+    let dir = tempdir().expect("Failed to create temp directory");
+    let archive = common::create_archive(dir.path(), PROJECT, None);
+    let tampered = dir.path().join("tampered.zip");
+
+    rewrite_archive(
+        &archive,
+        &tampered,
+        &[
+            ("main.py", Some("import os\n")),
+            ("lib/util.py", None),
+            ("payload.py", Some("evil()\n")),
+        ],
+    );
+    let err = verify(&tampered).unwrap_err();
+    assert!(matches!(
+        archive::archive_error(&err),
+        Some(ArchiveError::Corrupt(_))
+    ));
+
+    let mut zip = archive::open_archive(&tampered).unwrap();
+    let expected = manifest::read_manifest(&mut zip, None).unwrap().unwrap();
+    let actual = manifest::hash_archive(&mut zip, None).unwrap();
+    assert_eq!(
+        manifest::compare(&expected, &actual),
+        vec![
+            Mismatch::Missing("lib/util.py".to_string()),
+            Mismatch::Modified("main.py".to_string()),
+            Mismatch::Extra("payload.py".to_string()),
+        ]
+    );
+
+    // Archives without a manifest cannot be checked
+    let stripped = dir.path().join("stripped.zip");
+    rewrite_archive(&archive, &stripped, &[(MANIFEST_ENTRY, None)]);
+    assert!(verify(&stripped).is_err());
+}
+
+// Test that run refuses tampered archives before starting the script
+#[test]
+#[serial]
+fn test_run_rejects_tampered_archive() {
+    let pytron_home = tempdir().expect("Failed to create PYTRON_HOME directory");
+    env::set_var("PYTRON_HOME", pytron_home.path());
+    let dir = tempdir().expect("Failed to create temp directory");
+    let archive = common::create_archive(dir.path(), PROJECT, None);
+    let tampered = dir.path().join("tampered.zip");
+    rewrite_archive(&archive, &tampered, &[("main.py", Some("import os\n"))]);
+
+    let err = run_from_zip(tampered.to_str().unwrap(), None, "main.py", &[], &[])
+        .expect_err("Tampered archive must not run");
+    let archive_err = archive::archive_error(&err).expect("Expected an archive error");
+    assert!(archive_err.to_string().contains("modified: main.py"));
+    assert_eq!(archive_err.exit_code(), archive::EXIT_CORRUPT_ARCHIVE);
+
+    env::remove_var("PYTRON_HOME");
+}
@@ -0,0 +1,115 @@
+use pytron::archive::{ArchiveError, EXIT_CORRUPT_ARCHIVE, EXIT_WRONG_PASSWORD};
+use pytron::output::{self, CommandResult, Status, SCHEMA_NAME, SCHEMA_VERSION};
+use pytron::run_args::parse_run_args;
+use pytron::{zip_directory_with_options, ZipOptions};
+use std::fs;
+use std::io;
+use std::time::Instant;
+use tempfile::tempdir;
+
+// Helper function to turn a result document into JSON the way it is printed
+fn to_json(document: &CommandResult) -> serde_json::Value {
+    serde_json::to_value(document).expect("Failed to serialize result")
+}
+
+// Test the envelope of a successful command and its command-specific result
+#[test]
+fn test_ok_document() {
+    let dir = tempdir().expect("Failed to create temp directory");
+    fs::write(dir.path().join("main.py"), "print('hello')\n").unwrap();
+    fs::write(dir.path().join("debug.log"), "log").unwrap();
+    let output = dir.path().join("robot.zip");
+    let options = ZipOptions {
+        ignore_patterns: Some(vec!["*.log".to_string()]),
+        ..Default::default()
+    };
+    let selection =
+        zip_directory_with_options(dir.path().to_str().unwrap(), output.to_str().unwrap(), &options)
+            .expect("Failed to create archive");
+
+    let document = CommandResult::ok("zip", Instant::now(), 0, &selection);
+    assert_eq!(document.status, Status::Ok);
+    assert!(document.error.is_none());
+
+    let json = to_json(&document);
+    assert_eq!(json["schema"], SCHEMA_NAME);
+    assert_eq!(json["schema_version"], SCHEMA_VERSION);
+    assert_eq!(json["pytron_version"], env!("CARGO_PKG_VERSION"));
+    assert_eq!(json["command"], "zip");
+    assert_eq!(json["status"], "ok");
+    assert_eq!(json["exit_code"], 0);
+    assert!(json["duration_ms"].is_u64());
+    assert!(json["error"].is_null());
+    assert_eq!(json["result"]["included"][0]["name"], "main.py");
+    assert_eq!(json["result"]["total_size"], 15);
+    assert_eq!(json["result"]["excluded"][0]["name"], "debug.log");
+}
+
+// Test that errors carry a stable code and the exit code of the process
+#[test]
+fn test_error_document() {
+    let cases: Vec<(io::Error, &str, i32)> = vec![
+        (
+            ArchiveError::WrongPassword {
+                entry: "main.py".to_string(),
+            }
+            .into(),
+            "wrong_password",
+            EXIT_WRONG_PASSWORD,
+        ),
+        (
+            ArchiveError::Corrupt("bad header".to_string()).into(),
+            "corrupt_archive",
+            EXIT_CORRUPT_ARCHIVE,
+        ),
+        (
+            io::Error::new(io::ErrorKind::NotFound, "robot.zip not found"),
+            "not_found",
+            1,
+        ),
+        (io::Error::other("network down"), "io_error", 1),
+    ];
+
+    for (err, code, exit_code) in cases {
+        assert_eq!(output::error_code(&err), code);
+        let json = to_json(&CommandResult::error("run", Instant::now(), &err));
+        assert_eq!(json["status"], "error");
+        assert_eq!(json["exit_code"], exit_code);
+        assert!(json["result"].is_null());
+        assert_eq!(json["error"]["code"], code);
+        assert_eq!(json["error"]["message"], err.to_string());
+    }
+}
+
+// Test that --json is accepted among the leading run options, not passed to the script
+#[test]
+fn test_run_json_flag() {
+    let args = |list: &[&str]| list.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+    let run = parse_run_args(&args(&["--json", "robot.zip", "main.py", "--json"])).unwrap();
+    assert!(run.json);
+    assert_eq!(run.target, "robot.zip");
+    assert_eq!(run.script_args, args(&["main.py", "--json"]));
+
+    let run = parse_run_args(&args(&["robot.zip", "main.py"])).unwrap();
+    assert!(!run.json);
+}
+
+// Test that --json only shows up in the help of commands with JSON output
+#[test]
+fn test_json_flag_help() {
+    let help = |args: &[&str]| {
+        let err = pytron::cli_command().try_get_matches_from(args).unwrap_err();
+        assert_eq!(err.kind(), clap::error::ErrorKind::DisplayHelp);
+        err.to_string()
+    };
+    assert!(help(&["pytron", "zip", "--help"]).contains("--json"));
+    assert!(help(&["pytron", "cache", "prune", "--help"]).contains("--json"));
+    for command in ["verify", "sign", "extract", "trust"] {
+        assert!(!help(&["pytron", command, "--help"]).contains("--json"), "{}", command);
+    }
+    assert!(!help(&["pytron", "trust", "list", "--help"]).contains("--json"));
+
+    let matches = pytron::cli_command().try_get_matches_from(["pytron", "verify", "--json"]);
+    assert!(matches.unwrap().subcommand_matches("verify").unwrap().get_flag("json"));
+}
@@ -0,0 +1,108 @@
+use clap::Parser;
+use pytron::password::{resolve_password, PasswordInput, PASSWORD_ENV};
+use pytron::{Cli, Commands};
+use serial_test::serial;
+use std::env;
+use std::fs;
+use tempfile::tempdir;
+
+// Test reading the password from the first line of a file
+#[test]
+#[serial]
+fn test_password_from_file() {
+    let dir = tempdir().expect("Failed to create temp directory");
+    let path = dir.path().join("password.txt");
+    fs::write(&path, "fooPass\nignored\n").unwrap();
+
+    let input = PasswordInput {
+        file: Some(path.clone()),
+        ..Default::default()
+    };
+    assert_eq!(
+        resolve_password(&input, false).unwrap().as_deref(),
+        Some("fooPass")
+    );
+
+    fs::write(&path, "\n").unwrap();
+    assert!(
+        resolve_password(&input, false).is_err(),
+        "Empty password must be rejected"
+    );
+
+    let missing = PasswordInput {
+        file: Some(dir.path().join("missing.txt")),
+        ..Default::default()
+    };
+    assert!(resolve_password(&missing, false).is_err());
+}
+
+// Test reading the password from a named variable and from PYTRON_PASSWORD
+#[test]
+#[serial]
+fn test_password_from_environment() {
+    env::remove_var(PASSWORD_ENV);
+    assert_eq!(
+        resolve_password(&PasswordInput::default(), false).unwrap(),
+        None
+    );
+
+    env::set_var(PASSWORD_ENV, "defaultPass");
+    assert_eq!(
+        resolve_password(&PasswordInput::default(), false)
+            .unwrap()
+            .as_deref(),
+        Some("defaultPass")
+    );
+
+    env::set_var("PYTRON_TEST_ROBOT_PASSWORD", "namedPass");
+    let input = PasswordInput {
+        env: Some("PYTRON_TEST_ROBOT_PASSWORD".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(
+        resolve_password(&input, false).unwrap().as_deref(),
+        Some("namedPass")
+    );
+
+    env::remove_var("PYTRON_TEST_ROBOT_PASSWORD");
+    assert!(
+        resolve_password(&input, false).is_err(),
+        "Unset variable must be an error"
+    );
+    env::remove_var(PASSWORD_ENV);
+}
+
+// Test that only one password source may be given and that it is never printed
+#[test]
+#[serial]
+fn test_password_sources_are_exclusive() {
+    let input = PasswordInput {
+        literal: Some("secretValue".to_string()),
+        env: Some("SOME_VAR".to_string()),
+        ..Default::default()
+    };
+    assert!(resolve_password(&input, false).is_err());
+    assert!(!format!("{:?}", input).contains("secretValue"));
+
+    let options = pytron::RunOptions {
+        password: Some("secretValue".to_string()),
+        ..Default::default()
+    };
+    assert!(!format!("{:?}", options).contains("secretValue"));
+
+    let result = Cli::try_parse_from(["pytron", "zip", "-p", "a", "--password-env", "VAR"]);
+    assert!(result.is_err(), "clap should reject two password sources");
+
+    let cli = Cli::parse_from(["pytron", "zip", "--password-file", "pw.txt"]);
+    if let Commands::Zip {
+        password,
+        password_file,
+        ..
+    } = cli.command
+    {
+        assert!(password.is_none());
+        assert_eq!(password_file.as_deref(), Some("pw.txt"));
+    } else {
+        panic!("Expected Zip command");
+    }
+}
@@ -88,7 +88,7 @@ fn test_run_from_zip_uses_pytron_home_for_temp() {
     let custom_path = "/tmp/pytron_test_home_for_temp";
     // Clean up any existing directory first to ensure a fresh start
     let _ = fs::remove_dir_all(custom_path);
-    let _ = fs::create_dir_all(custom_path).expect("Failed to create PYTRON_HOME directory");
+    fs::create_dir_all(custom_path).expect("Failed to create PYTRON_HOME directory");
 
     // Set the environment variable
     env::set_var("PYTRON_HOME", custom_path);
@@ -105,7 +105,7 @@ fn test_run_from_zip_uses_pytron_home_for_temp() {
 
     // Create a zip file
     let zip_path = test_dir.path().join("test.zip");
-    let _ = pytron::zip_directory(
+    pytron::zip_directory(
         test_dir.path().to_str().unwrap(),
         zip_path.to_str().unwrap(),
         None,
@@ -0,0 +1,132 @@
+use pytron::archive::{self, ArchiveError};
+use pytron::{
+    recipients, run_from_zip_with_options, zip_directory_with_options, ArchiveEncryption,
+    RunOptions, ZipOptions,
+};
+use serial_test::serial;
+use std::env;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use tempfile::tempdir;
+
+// Helper function to create an archive encrypted to the given recipient specs
+fn create_encrypted_archive(dir: &Path, specs: &[String]) -> PathBuf {
+    let project = dir.join("project");
+    fs::create_dir_all(&project).unwrap();
+    fs::write(project.join("main.py"), "print('for your eyes only')\n").unwrap();
+
+    let archive = dir.join("robot.zip");
+    let parsed = recipients::parse_recipients(specs).expect("Failed to parse recipients");
+    let options = ZipOptions {
+        encryption: ArchiveEncryption::Recipients(parsed),
+        ..Default::default()
+    };
+    zip_directory_with_options(project.to_str().unwrap(), archive.to_str().unwrap(), &options)
+        .expect("Failed to create encrypted archive");
+    // Only the encrypted bytes reach the disk
+    assert!(!fs::read(&archive).unwrap().starts_with(b"PK"));
+    archive
+}
+
+fn read_main(archive: &Path, identities: &[PathBuf]) -> std::io::Result<String> {
+    let mut zip = archive::open_archive_with_keys(archive, None, identities)?;
+    let mut content = String::new();
+    zip.by_name("main.py")?.read_to_string(&mut content)?;
+    Ok(content)
+}
+
+// Test that every recipient can decrypt and others cannot
+#[test]
+#[serial]
+fn test_multiple_recipients() {
+    let pytron_home = tempdir().expect("Failed to create PYTRON_HOME directory");
+    env::set_var("PYTRON_HOME", pytron_home.path());
+    let dir = tempdir().expect("Failed to create temp directory");
+
+    let (host_a, host_a_pub) =
+        recipients::generate_identity(&dir.path().join("host_a"), false).expect("Keygen failed");
+    let (host_b, host_b_pub) =
+        recipients::generate_identity(&dir.path().join("host_b"), false).expect("Keygen failed");
+    let (outsider, _) =
+        recipients::generate_identity(&dir.path().join("outsider"), false).expect("Keygen failed");
+
+    // One recipient given inline, one through a recipients file
+    let inline = fs::read_to_string(&host_a_pub).unwrap().trim().to_string();
+    let recipients_file = dir.path().join("recipients.txt");
+    fs::write(
+        &recipients_file,
+        format!(
+            "# build hosts\n{}\n",
+            fs::read_to_string(&host_b_pub).unwrap()
+        ),
+    )
+    .unwrap();
+    let archive = create_encrypted_archive(
+        dir.path(),
+        &[inline, recipients_file.to_string_lossy().to_string()],
+    );
+    assert!(recipients::is_recipient_encrypted(&archive).unwrap());
+
+    assert_eq!(
+        read_main(&archive, &[host_a]).unwrap(),
+        "print('for your eyes only')\n"
+    );
+    assert!(read_main(&archive, std::slice::from_ref(&host_b)).is_ok());
+
+    let err = read_main(&archive, &[outsider]).unwrap_err();
+    let archive_err = archive::archive_error(&err).expect("Expected an archive error");
+    assert!(matches!(
+        archive_err,
+        ArchiveError::NoMatchingIdentity { .. }
+    ));
+    assert_eq!(archive_err.exit_code(), archive::EXIT_NO_MATCHING_IDENTITY);
+
+    // Without --identity the keys in PYTRON_HOME/keys are tried
+    assert!(read_main(&archive, &[]).is_err());
+    let keys_dir = recipients::get_keys_dir();
+    fs::create_dir_all(&keys_dir).unwrap();
+    fs::copy(&host_b, keys_dir.join("host_b.key")).unwrap();
+    fs::write(keys_dir.join("unrelated.key"), "not a key\n").unwrap();
+    assert!(read_main(&archive, &[]).is_ok());
+
+    env::remove_var("PYTRON_HOME");
+}
+
+// Test that invalid recipients are rejected
+#[test]
+fn test_invalid_recipients() {
+    let dir = tempdir().expect("Failed to create temp directory");
+    assert!(recipients::parse_recipients(&["age1notakey".to_string()]).is_err());
+
+    let empty = dir.path().join("empty.txt");
+    fs::write(&empty, "# nobody\n").unwrap();
+    assert!(recipients::parse_recipients(&[empty.to_string_lossy().to_string()]).is_err());
+}
+
+// Test that run reports a missing key with its own error
+#[test]
+#[serial]
+fn test_run_without_matching_key() {
+    let pytron_home = tempdir().expect("Failed to create PYTRON_HOME directory");
+    env::set_var("PYTRON_HOME", pytron_home.path());
+    let dir = tempdir().expect("Failed to create temp directory");
+    let (_, public_key) =
+        recipients::generate_identity(&dir.path().join("host"), false).expect("Keygen failed");
+    let archive = create_encrypted_archive(dir.path(), &[public_key.to_string_lossy().to_string()]);
+
+    let err = run_from_zip_with_options(
+        archive.to_str().unwrap(),
+        "main.py",
+        &[],
+        &[],
+        &RunOptions::default(),
+    )
+    .expect_err("Archive should not decrypt without a key");
+    assert!(matches!(
+        archive::archive_error(&err),
+        Some(ArchiveError::NoMatchingIdentity { .. })
+    ));
+
+    env::remove_var("PYTRON_HOME");
+}
@@ -13,7 +13,7 @@ fn test_run_from_zip() {
     let output_zip = test_dir.path().join("test_output.zip");
 
     // Create the zip file first
-    let _ = pytron::zip_directory(
+    pytron::zip_directory(
         test_dir.path().to_str().unwrap(),
         output_zip.to_str().unwrap(),
         None,
@@ -72,7 +72,7 @@ print(f"Arguments received: {sys.argv[1:]}")
 
     // Create a zip file
     let zip_path = test_dir.path().join("arg_test.zip");
-    let _ = pytron::zip_directory(
+    pytron::zip_directory(
         test_dir.path().to_str().unwrap(),
         zip_path.to_str().unwrap(),
         None,
@@ -187,7 +187,7 @@ print('Hello World')
 
     // Create a zip file
     let zip_path = test_dir.path().join("password_test.zip");
-    let _ = pytron::zip_directory(
+    pytron::zip_directory(
         test_dir.path().to_str().unwrap(),
         zip_path.to_str().unwrap(),
         None,
@@ -0,0 +1,141 @@
+use clap::Parser;
+use pytron::run_args::{parse_run_args, split_target, validate_entry, RunArgs};
+use pytron::{Cli, Commands};
+use std::path::PathBuf;
+
+// Helper function to parse the arguments following `pytron run`
+fn parse(args: &[&str]) -> std::io::Result<RunArgs> {
+    let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+    parse_run_args(&args)
+}
+
+// Test the defaults and the split between uv, pytron and script arguments
+#[test]
+fn test_parse_run_args_defaults() {
+    let run = parse(&[]).unwrap();
+    assert_eq!(run.target, "robot.zip");
+    assert_eq!(run.entry_script(), "main.py");
+    assert!(run.is_archive());
+
+    let run = parse(&["--with", "-v", "robot.zip", "--verbose", "-o", "out.txt"]).unwrap();
+    assert_eq!(run.target, "robot.zip");
+    assert_eq!(run.uv_args, vec!["--with", "-v"]);
+    assert_eq!(run.script_args, vec!["--verbose", "-o", "out.txt"]);
+
+    let run = parse(&["script.py", "--", "--password", "x"]).unwrap();
+    assert!(!run.is_archive());
+    assert_eq!(run.script_args, vec!["--password", "x"]);
+    assert!(run.password.literal.is_none());
+
+    assert!(parse(&["-hh"]).unwrap().uv_help);
+}
+
+// Test selecting the entry with --entry or robot.zip:path
+#[test]
+fn test_parse_run_args_entry() {
+    for args in [
+        &["--entry", "tools/report.py", "robot.zip"][..],
+        &["--entry=tools/report.py", "robot.zip"][..],
+        &["robot.zip:tools/report.py"][..],
+    ] {
+        let run = parse(args).unwrap();
+        assert_eq!(run.target, "robot.zip", "{:?}", args);
+        assert_eq!(run.entry_script(), "tools/report.py", "{:?}", args);
+    }
+
+    assert!(parse(&["--entry", "a.py", "robot.zip:b.py"]).is_err());
+    assert!(parse(&["--entry", "a.py", "script.py"]).is_err());
+    assert!(parse(&["--entry", "../escape.py", "robot.zip"]).is_err());
+    assert!(parse(&["--entry"]).is_err());
+}
+
+// Test the --flag=value forms of the value options
+#[test]
+fn test_parse_run_args_inline_values() {
+    let run = parse(&[
+        "--password=secret",
+        "--identity=host.key",
+        "--sandbox",
+        "--allow-write=out",
+        "--no-network",
+        "robot.zip",
+    ])
+    .unwrap();
+    assert_eq!(run.password.literal.as_deref(), Some("secret"));
+    assert_eq!(run.identities, vec![PathBuf::from("host.key")]);
+    let sandbox = run.sandbox.expect("Sandbox should be enabled");
+    assert_eq!(sandbox.allow_write, vec![PathBuf::from("out")]);
+    assert!(sandbox.no_network);
+
+    let run = parse(&["--password-file=pw.txt", "robot.zip", "--config=app.json"]).unwrap();
+    assert_eq!(run.password.file, Some(PathBuf::from("pw.txt")));
+    assert_eq!(run.script_args, vec!["--config=app.json"]);
+
+    // pytron's options only count before the archive, afterwards they belong to the script
+    let script_options = [
+        "--sandbox",
+        "--require-signature",
+        "--entry",
+        "tools/report.py",
+        "--identity=host.key",
+        "--password-file",
+        "pw.txt",
+        "--password-env",
+        "PW",
+        "--ask-password",
+        "-p",
+        "secret",
+    ];
+    let mut args = vec!["robot.zip"];
+    args.extend(script_options);
+    let run = parse(&args).unwrap();
+    assert!(run.sandbox.is_none() && !run.require_signature);
+    assert!(run.entry.is_none() && run.identities.is_empty());
+    assert!(run.password.file.is_none() && run.password.env.is_none());
+    assert!(!run.password.ask && run.password.literal.is_none());
+    assert_eq!(run.script_args, script_options);
+    assert!(parse(&["robot.zip", "--list"]).unwrap().list_commands);
+    // Once a script argument is given, --list is the script's too
+    let run = parse(&["robot.zip", "report", "--list"]).unwrap();
+    assert!(!run.list_commands);
+    assert_eq!(run.script_args, vec!["report", "--list"]);
+
+    assert!(parse(&["--no-network", "robot.zip"]).is_err());
+}
+
+// Test splitting the entry off the archive path
+#[test]
+fn test_split_target() {
+    assert_eq!(
+        split_target("robot.zip:tools/report.py"),
+        ("robot.zip".to_string(), Some("tools/report.py".to_string()))
+    );
+    assert_eq!(
+        split_target(r"C:\robots\robot.ZIP:main.py"),
+        (
+            r"C:\robots\robot.ZIP".to_string(),
+            Some("main.py".to_string())
+        )
+    );
+    assert_eq!(
+        split_target(r"C:\robots\robot.zip"),
+        (r"C:\robots\robot.zip".to_string(), None)
+    );
+    assert_eq!(split_target("robot.zip:"), ("robot.zip:".to_string(), None));
+
+    assert!(validate_entry("tools/report.py").is_ok());
+    assert!(validate_entry("/etc/passwd").is_err());
+    assert!(validate_entry("").is_err());
+}
+
+// Test the --entry option of the clap definition
+#[test]
+fn test_cli_entry() {
+    let cli = Cli::parse_from(["pytron", "run", "--entry", "tools/report.py", "robot.zip"]);
+    if let Commands::Run { entry, script, .. } = cli.command {
+        assert_eq!(entry.as_deref(), Some("tools/report.py"));
+        assert_eq!(script, "main.py");
+    } else {
+        panic!("Expected Run command");
+    }
+}
@@ -0,0 +1,202 @@
+use clap::Parser;
+use pytron::sandbox::{self, SandboxOptions};
+use pytron::{Cli, Commands};
+use serial_test::serial;
+use std::env;
+use std::fs;
+use std::process::Command;
+use tempfile::tempdir;
+
+// Test the sandbox flags of the run command
+#[test]
+fn test_sandbox_flags() {
+    let cli = Cli::parse_from([
+        "pytron",
+        "run",
+        "--sandbox",
+        "--no-network",
+        "--allow-write",
+        "out",
+        "robot.zip",
+    ]);
+    if let Commands::Run {
+        sandbox,
+        no_network,
+        allow_write,
+        ..
+    } = cli.command
+    {
+        assert!(sandbox && no_network);
+        assert_eq!(allow_write, vec!["out".to_string()]);
+    } else {
+        panic!("Expected Run command");
+    }
+
+    // The sandbox options make no sense without the sandbox
+    assert!(Cli::try_parse_from(["pytron", "run", "--no-network", "robot.zip"]).is_err());
+}
+
+// Test which paths stay writable and visible
+#[test]
+#[serial]
+fn test_sandbox_paths() {
+    let home = tempdir().expect("Failed to create home directory");
+    let home_path = home.path().canonicalize().unwrap();
+    let pytron_home = home_path.join("pytron_home");
+    env::set_var("PYTRON_HOME", &pytron_home);
+    let workdir = pytron_home.join("temp/pytron_test");
+    fs::create_dir_all(&workdir).unwrap();
+    let output = home_path.join("reports");
+    fs::create_dir_all(&output).unwrap();
+    fs::create_dir_all(pytron_home.join("python")).unwrap();
+    fs::create_dir_all(pytron_home.join("keys")).unwrap();
+    fs::write(pytron_home.join("uv"), "").unwrap();
+    fs::write(pytron_home.join("trust.toml"), "").unwrap();
+
+    let options = SandboxOptions {
+        allow_write: vec![output.clone()],
+        no_network: false,
+    };
+    let writable = sandbox::writable_paths(&workdir, &options).unwrap();
+    assert_eq!(writable, vec![workdir.clone(), output.clone()]);
+
+    // Only uv, the Python installs and the writable paths stay visible
+    assert_eq!(
+        sandbox::kept_home_paths(&home_path, &writable),
+        vec![
+            pytron_home.join("python"),
+            workdir.clone(),
+            pytron_home.join("uv"),
+            output
+        ]
+    );
+    assert_eq!(
+        sandbox::hidden_paths(),
+        vec![pytron_home.join("keys"), pytron_home.join("trust.toml")]
+    );
+
+    let missing = SandboxOptions {
+        allow_write: vec![home_path.join("missing")],
+        no_network: false,
+    };
+    assert!(sandbox::writable_paths(&workdir, &missing).is_err());
+
+    env::remove_var("PYTRON_HOME");
+}
+
+// Test that the sandbox environment only installs locked wheels
+#[cfg(unix)]
+#[test]
+#[serial]
+fn test_sandbox_prepare_installs_only_locked_wheels() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let pytron_home = tempdir().expect("Failed to create PYTRON_HOME directory");
+    env::set_var("PYTRON_HOME", pytron_home.path());
+    // A stand-in for uv that records its arguments
+    let uv = pytron_home.path().join("uv");
+    let recorded = pytron_home.path().join("args.txt");
+    fs::write(&uv, format!("#!/bin/sh\necho \"$@\" > {}\n", recorded.display())).unwrap();
+    fs::set_permissions(&uv, fs::Permissions::from_mode(0o755)).unwrap();
+    let workdir = tempdir().expect("Failed to create work directory");
+    let options = SandboxOptions {
+        no_network: true,
+        ..Default::default()
+    };
+
+    // Nothing to prepare without a project
+    assert!(!sandbox::prepare(workdir.path(), &options).unwrap());
+
+    // A project without a lock cannot be installed without resolving it
+    fs::write(workdir.path().join("pyproject.toml"), "[project]\nname = \"robot\"\n").unwrap();
+    let err = sandbox::prepare(workdir.path(), &options).unwrap_err();
+    assert!(err.to_string().contains("no uv.lock"));
+    assert!(!recorded.exists());
+
+    fs::write(workdir.path().join("uv.lock"), "version = 1\n").unwrap();
+    assert!(sandbox::prepare(workdir.path(), &options).unwrap());
+    let args = fs::read_to_string(&recorded).unwrap();
+    let args: Vec<&str> = args.split_whitespace().collect();
+    for flag in ["--frozen", "--no-build", "--no-install-project", "--offline"] {
+        assert!(args.contains(&flag), "uv sync should get {}", flag);
+    }
+    assert_eq!(args[0], "sync");
+
+    // A failed sync explains what the sandbox can install
+    fs::write(&uv, "#!/bin/sh\nexit 2\n").unwrap();
+    let err = sandbox::prepare(workdir.path(), &options).unwrap_err();
+    assert!(err.to_string().contains("prebuilt wheels"));
+
+    env::remove_var("PYTRON_HOME");
+}
+
+// Test that the sandbox hides $HOME and limits writes where the kernel allows it
+#[cfg(target_os = "linux")]
+#[test]
+#[serial]
+fn test_sandbox_enforced() {
+    let support = sandbox::probe();
+    let home = tempdir().expect("Failed to create home directory");
+    let home_path = home.path().canonicalize().unwrap();
+    let pytron_home = home_path.join("pytron_home");
+    let workdir = pytron_home.join("temp/pytron_test");
+    fs::create_dir_all(&workdir).unwrap();
+    fs::write(home_path.join("id_rsa"), "private").unwrap();
+    fs::create_dir_all(pytron_home.join("keys")).unwrap();
+    fs::write(pytron_home.join("keys/host.key"), "private").unwrap();
+    let outside = tempdir().expect("Failed to create outside directory");
+    let original_home = env::var_os("HOME");
+    env::set_var("HOME", &home_path);
+    env::set_var("PYTRON_HOME", &pytron_home);
+
+    let run = |script: &str, options: &SandboxOptions| {
+        let mut cmd = Command::new("/bin/sh");
+        cmd.args(["-c", script]);
+        sandbox::apply(&mut cmd, &workdir, options).unwrap();
+        cmd.status().unwrap().success()
+    };
+    let options = SandboxOptions::default();
+
+    // The extraction directory is the working directory and stays writable
+    assert!(run("touch ./result.txt", &options));
+    assert!(workdir.join("result.txt").exists());
+
+    if support.user_namespaces {
+        let script = format!("test ! -e {}", home_path.join("id_rsa").display());
+        assert!(run(&script, &options), "$HOME should be hidden");
+        let script = format!("test ! -e {}", pytron_home.join("keys/host.key").display());
+        assert!(run(&script, &options), "Keys should be hidden");
+
+        // Keys stay hidden when they are inside a writable path
+        let allow_home = SandboxOptions {
+            allow_write: vec![pytron_home.clone()],
+            ..Default::default()
+        };
+        assert!(run(&script, &allow_home), "Keys should be covered");
+
+        let no_network = SandboxOptions {
+            no_network: true,
+            ..Default::default()
+        };
+        assert!(
+            run("! grep -q eth /proc/net/dev", &no_network),
+            "Only loopback should be left"
+        );
+    }
+
+    if support.landlock_abi.is_some() {
+        let script = format!("touch {}", outside.path().join("escape.txt").display());
+        assert!(!run(&script, &options), "Writes outside should be denied");
+        let allowed = SandboxOptions {
+            allow_write: vec![outside.path().to_path_buf()],
+            ..Default::default()
+        };
+        assert!(run(&script, &allowed));
+    }
+
+    match original_home {
+        Some(value) => env::set_var("HOME", value),
+        None => env::remove_var("HOME"),
+    }
+    env::remove_var("PYTRON_HOME");
+}
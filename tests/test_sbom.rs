@@ -0,0 +1,167 @@
+use pytron::sbom::{self, SbomFormat};
+use pytron::{archive, manifest, zip_directory_with_options, ZipOptions};
+use serde_json::Value;
+use serial_test::serial;
+use std::env;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use tempfile::tempdir;
+
+const PROJECT: &str = "tests/fixtures/sbom_project";
+
+// Helper function to package the fixture project, optionally embedding SBOMs
+fn create_archive(dir: &Path, embed: Vec<SbomFormat>) -> PathBuf {
+    std::fs::create_dir_all(dir).unwrap();
+    let archive = dir.join("robot.zip");
+    let options = ZipOptions {
+        sbom: embed,
+        ..Default::default()
+    };
+    zip_directory_with_options(PROJECT, archive.to_str().unwrap(), &options)
+        .expect("Failed to create archive");
+    archive
+}
+
+fn find<'a>(items: &'a Value, key: &str, value: &str) -> &'a Value {
+    items
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|item| item[key] == value)
+        .unwrap_or_else(|| panic!("No item with {} = {}", key, value))
+}
+
+// Test reading the pinned packages from uv.lock
+#[test]
+fn test_parse_uv_lock() {
+    let text = std::fs::read_to_string(Path::new(PROJECT).join("uv.lock")).unwrap();
+    let (packages, requires_python) = sbom::parse_uv_lock(&text).unwrap();
+    assert_eq!(requires_python.as_deref(), Some(">=3.12"));
+    assert_eq!(packages.len(), 3);
+
+    let requests = packages.iter().find(|p| p.name == "requests").unwrap();
+    assert_eq!(requests.version, "2.32.3");
+    assert_eq!(requests.dependencies, vec!["certifi".to_string()]);
+    assert!(requests.sha256.as_deref().unwrap().starts_with("70761cfe"));
+    assert_eq!(sbom::purl(requests), "pkg:pypi/requests@2.32.3");
+
+    let root = packages.iter().find(|p| p.is_root).unwrap();
+    assert_eq!(root.name, "robot");
+}
+
+// Test the CycloneDX document of an archive
+#[test]
+#[serial]
+fn test_cyclonedx_from_archive() {
+    let dir = tempdir().expect("Failed to create temp directory");
+    let archive_path = create_archive(dir.path(), Vec::new());
+    let mut zip = archive::open_archive(&archive_path).unwrap();
+    let input = sbom::collect_from_archive(&mut zip, None, &archive_path).unwrap();
+    assert_eq!(input.name, "robot");
+    assert_eq!(input.python_version.as_deref(), Some("3.12"));
+
+    let document = sbom::cyclonedx(&input, "2024-01-01T00:00:00Z");
+    assert_eq!(document["bomFormat"], "CycloneDX");
+    assert_eq!(document["metadata"]["component"]["version"], "0.1.0");
+
+    let components = &document["components"];
+    let requests = find(components, "name", "requests");
+    assert_eq!(requests["purl"], "pkg:pypi/requests@2.32.3");
+    assert_eq!(requests["hashes"][0]["alg"], "SHA-256");
+    assert_eq!(find(components, "name", "python")["version"], "3.12");
+    // Without an embedded SBOM the uv version is unknown and left out
+    assert!(input.uv_version.is_none());
+    assert!(!components.as_array().unwrap().iter().any(|c| c["name"] == "uv"));
+    let main = find(components, "name", "main.py");
+    assert_eq!(main["type"], "file");
+    assert_eq!(
+        main["hashes"][0]["content"],
+        pytron::sha256_file(&Path::new(PROJECT).join("main.py")).unwrap()
+    );
+    // pytron's own entries are not part of the project
+    assert!(components
+        .as_array()
+        .unwrap()
+        .iter()
+        .all(|c| !c["name"].as_str().unwrap().starts_with(".pytron/")));
+
+    let root = find(&document["dependencies"], "ref", "pytron:robot");
+    assert_eq!(root["dependsOn"][0], "pkg:pypi/requests@2.32.3");
+}
+
+// Test that the SBOM names the uv installed when the archive was built, not the current one
+#[cfg(unix)]
+#[test]
+#[serial]
+fn test_sbom_reports_build_uv_version() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempdir().expect("Failed to create temp directory");
+    let uv = dir.path().join("uv");
+    env::set_var("PYTRON_HOME", dir.path());
+    std::fs::write(&uv, "#!/bin/sh\necho 'uv 0.9.1 (abcdef123 2025-10-01)'\n").unwrap();
+    std::fs::set_permissions(&uv, std::fs::Permissions::from_mode(0o755)).unwrap();
+    let cyclonedx = create_archive(&dir.path().join("cdx"), vec![SbomFormat::Cyclonedx]);
+    let spdx = create_archive(&dir.path().join("spdx"), vec![SbomFormat::Spdx]);
+
+    // Upgrading uv afterwards does not change what the archives were built with
+    std::fs::write(&uv, "#!/bin/sh\necho 'uv 1.0.0'\n").unwrap();
+    for archive_path in [cyclonedx, spdx] {
+        let mut zip = archive::open_archive(&archive_path).unwrap();
+        let input = sbom::collect_from_archive(&mut zip, None, &archive_path).unwrap();
+        assert_eq!(input.uv_version.as_deref(), Some("0.9.1"));
+    }
+    env::remove_var("PYTRON_HOME");
+}
+
+// Test the SPDX document and reproducible output
+#[test]
+#[serial]
+fn test_spdx_reproducible() {
+    let dir = tempdir().expect("Failed to create temp directory");
+    let archive_path = create_archive(dir.path(), Vec::new());
+    let mut zip = archive::open_archive(&archive_path).unwrap();
+    let input = sbom::collect_from_archive(&mut zip, None, &archive_path).unwrap();
+
+    env::set_var("SOURCE_DATE_EPOCH", "1700000000");
+    let first = sbom::render(&input, SbomFormat::Spdx);
+    let second = sbom::render(&input, SbomFormat::Spdx);
+    env::remove_var("SOURCE_DATE_EPOCH");
+    assert_eq!(first, second);
+
+    let document: Value = serde_json::from_str(&first).unwrap();
+    assert_eq!(document["spdxVersion"], "SPDX-2.3");
+    assert_eq!(document["creationInfo"]["created"], "2023-11-14T22:13:20Z");
+    let relationships = document["relationships"].as_array().unwrap();
+    assert!(relationships.iter().any(|r| {
+        r["spdxElementId"] == "SPDXRef-Package-requests-2.32.3"
+            && r["relationshipType"] == "DEPENDS_ON"
+            && r["relatedSpdxElement"] == "SPDXRef-Package-certifi-2025.1.31"
+    }));
+    assert_eq!(document["files"].as_array().unwrap().len(), 4);
+    assert_eq!(sbom::format_timestamp(0), "1970-01-01T00:00:00Z");
+}
+
+// Test embedding SBOMs with pytron zip
+#[test]
+fn test_embedded_sbom() {
+    let dir = tempdir().expect("Failed to create temp directory");
+    let archive_path = create_archive(dir.path(), vec![SbomFormat::Cyclonedx, SbomFormat::Spdx]);
+    let mut zip = archive::open_archive(&archive_path).unwrap();
+
+    for format in [SbomFormat::Cyclonedx, SbomFormat::Spdx] {
+        let mut text = String::new();
+        zip.by_name(format.entry_name())
+            .expect("SBOM should be embedded")
+            .read_to_string(&mut text)
+            .unwrap();
+        let document: Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(
+            document["name"].as_str().is_some(),
+            format == SbomFormat::Spdx
+        );
+    }
+
+    // Embedded documents are covered by the content manifest
+    assert_eq!(manifest::verify_archive(&mut zip, None).unwrap(), 6);
+}
@@ -0,0 +1,139 @@
+use pytron::archive::{self, ArchiveError};
+use pytron::scan::{ScanMode, Scanner};
+use pytron::{
+    run_from_zip_with_options, sealed, zip_directory_with_options, ArchiveEncryption, RunOptions,
+    ZipOptions,
+};
+use serial_test::serial;
+use std::env;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use tempfile::tempdir;
+
+// Helper function to create a sealed archive of a small project
+fn create_sealed_archive(dir: &Path, password: &str) -> PathBuf {
+    let project = dir.join("project");
+    fs::create_dir_all(project.join("internal_tooling")).unwrap();
+    fs::write(project.join("main.py"), "print('sealed')\n").unwrap();
+    fs::write(project.join("internal_tooling/deploy.py"), "pass\n").unwrap();
+
+    let archive = dir.join("robot.zip");
+    let options = ZipOptions {
+        encryption: ArchiveEncryption::Sealed(password.to_string()),
+        ..Default::default()
+    };
+    zip_directory_with_options(project.to_str().unwrap(), archive.to_str().unwrap(), &options)
+        .expect("Failed to create sealed archive");
+    archive
+}
+
+fn expect_archive_error(err: std::io::Error) -> ArchiveError {
+    archive::archive_error(&err)
+        .unwrap_or_else(|| panic!("Expected an archive error, got: {}", err))
+        .clone()
+}
+
+// Test that sealing hides entry names and unsealing restores the archive
+#[test]
+fn test_seal_hides_names_and_round_trips() {
+    let dir = tempdir().expect("Failed to create temp directory");
+    let archive = create_sealed_archive(dir.path(), "fooPass");
+
+    assert!(sealed::is_sealed(&archive).unwrap());
+    let raw = fs::read(&archive).unwrap();
+    assert!(
+        !raw.windows(16).any(|w| w == b"internal_tooling"),
+        "Entry names must not be readable"
+    );
+
+    let mut zip = archive::open_archive_with_password(&archive, Some("fooPass"))
+        .expect("Failed to unseal archive");
+    let mut content = String::new();
+    zip.by_name("main.py")
+        .unwrap()
+        .read_to_string(&mut content)
+        .unwrap();
+    assert_eq!(content, "print('sealed')\n");
+    assert!(zip.by_name("internal_tooling/deploy.py").is_ok());
+}
+
+// Test missing password, wrong password, tampering and truncation
+#[test]
+fn test_unseal_errors() {
+    let dir = tempdir().expect("Failed to create temp directory");
+    let archive = create_sealed_archive(dir.path(), "fooPass");
+
+    let err = expect_archive_error(archive::open_archive(&archive).err().unwrap());
+    assert!(matches!(err, ArchiveError::PasswordRequired { .. }));
+    assert!(err.to_string().contains("sealed"));
+
+    let err = expect_archive_error(
+        archive::open_archive_with_password(&archive, Some("wrongPassword"))
+            .err()
+            .unwrap(),
+    );
+    assert!(matches!(err, ArchiveError::WrongPassword { .. }));
+
+    let mut data = fs::read(&archive).unwrap();
+    let last = data.len() - 1;
+    data[last] ^= 0x01;
+    let err = expect_archive_error(sealed::unseal(&data, "fooPass").unwrap_err());
+    assert!(matches!(err, ArchiveError::WrongPassword { .. }));
+
+    let err = expect_archive_error(sealed::unseal(&data[..20], "fooPass").unwrap_err());
+    assert!(matches!(err, ArchiveError::Corrupt(_)));
+}
+
+// Test that run unseals with the password and refuses without it
+#[test]
+#[serial]
+fn test_run_sealed_archive() {
+    let pytron_home = tempdir().expect("Failed to create PYTRON_HOME directory");
+    env::set_var("PYTRON_HOME", pytron_home.path());
+    let dir = tempdir().expect("Failed to create temp directory");
+    let archive = create_sealed_archive(dir.path(), "fooPass");
+    let archive_str = archive.to_str().unwrap();
+
+    let err = run_from_zip_with_options(archive_str, "main.py", &[], &[], &RunOptions::default())
+        .expect_err("Sealed archive needs a password");
+    assert!(matches!(
+        expect_archive_error(err),
+        ArchiveError::PasswordRequired { .. }
+    ));
+
+    // With the password the archive is extracted; any remaining failure comes from uv
+    let options = RunOptions {
+        password: Some("fooPass".to_string()),
+        ..Default::default()
+    };
+    if let Err(err) = run_from_zip_with_options(archive_str, "main.py", &[], &[], &options) {
+        assert!(
+            archive::archive_error(&err).is_none(),
+            "Unexpected archive error: {}",
+            err
+        );
+    }
+
+    env::remove_var("PYTRON_HOME");
+}
+
+// Test that a failed sealed zip leaves no plaintext archive behind
+#[test]
+fn test_failed_seal_writes_nothing() {
+    let dir = tempdir().expect("Failed to create temp directory");
+    let project = dir.path().join("project");
+    fs::create_dir_all(&project).unwrap();
+    fs::write(project.join("main.py"), "print('sealed')\n").unwrap();
+    fs::write(project.join(".env"), "TOKEN=1\n").unwrap();
+
+    let archive = dir.path().join("robot.zip");
+    let options = ZipOptions {
+        encryption: ArchiveEncryption::Sealed("fooPass".to_string()),
+        scanner: Some(Scanner::new(ScanMode::Fail, None, &[]).unwrap()),
+        ..Default::default()
+    };
+    zip_directory_with_options(project.to_str().unwrap(), archive.to_str().unwrap(), &options)
+        .expect_err("The scan should refuse the .env file");
+    assert!(!archive.exists(), "Nothing may be written when zipping fails");
+}
@@ -0,0 +1,153 @@
+use pytron::archive::{self, ArchiveError};
+use pytron::secrets::{self, SECRETS_ENTRY};
+use pytron::{
+    recipients, run_from_zip_with_options, zip_directory_with_options, RunOptions, ZipOptions,
+};
+use serial_test::serial;
+use std::env;
+use std::fs;
+use std::io::Read;
+use tempfile::tempdir;
+
+mod common;
+
+const SECRETS: &str =
+    "# robot credentials\nAPI_TOKEN=tok-12345\nexport DB_URL=\"postgres://db/robot\"\n";
+
+// A project with a secrets file inside it
+const PROJECT: &[(&str, &str)] = &[
+    ("main.py", "import os\nprint(os.environ['API_TOKEN'])\n"),
+    ("secrets.env", SECRETS),
+];
+
+// Test parsing of dotenv files
+#[test]
+fn test_parse_env() {
+    let vars = secrets::parse_env(SECRETS).unwrap();
+    assert_eq!(
+        vars,
+        vec![
+            ("API_TOKEN".to_string(), "tok-12345".to_string()),
+            ("DB_URL".to_string(), "postgres://db/robot".to_string()),
+        ]
+    );
+
+    let err = secrets::parse_env("API_TOKEN tok-12345\n").unwrap_err();
+    assert!(
+        !err.to_string().contains("tok-12345"),
+        "Errors must not echo secret values"
+    );
+    assert!(secrets::parse_env("BAD NAME=1\n").is_err());
+}
+
+// Test that secrets are stored encrypted and the plain file is left out
+#[test]
+fn test_secrets_stored_encrypted() {
+    let dir = tempdir().expect("Failed to create temp directory");
+    let project = common::create_project(dir.path(), PROJECT);
+    let secrets_file = project.join("secrets.env");
+    let archive = dir.path().join("robot.zip");
+
+    let (data, count) = secrets::encrypt_secrets_file(&secrets_file, Some("fooPass"), &[]).unwrap();
+    assert_eq!(count, 2);
+    let options = ZipOptions {
+        exclude: vec![secrets_file.clone()],
+        extra_entries: vec![(SECRETS_ENTRY.to_string(), data)],
+        ..Default::default()
+    };
+    zip_directory_with_options(
+        project.to_str().unwrap(),
+        archive.to_str().unwrap(),
+        &options,
+    )
+    .expect("Failed to create archive");
+
+    let raw = fs::read(&archive).unwrap();
+    assert!(!raw.windows(9).any(|w| w == b"tok-12345"));
+
+    let mut zip = archive::open_archive(&archive).unwrap();
+    assert!(
+        zip.by_name("secrets.env").is_err(),
+        "Plain secrets file must be excluded"
+    );
+    let mut encrypted = Vec::new();
+    zip.by_name(SECRETS_ENTRY)
+        .unwrap()
+        .read_to_end(&mut encrypted)
+        .unwrap();
+
+    let vars = secrets::decrypt_secrets(&encrypted, Some("fooPass"), &[]).unwrap();
+    assert_eq!(vars[0], ("API_TOKEN".to_string(), "tok-12345".to_string()));
+
+    let err = secrets::decrypt_secrets(&encrypted, Some("wrongPassword"), &[]).unwrap_err();
+    assert!(matches!(
+        archive::archive_error(&err),
+        Some(ArchiveError::WrongPassword { .. })
+    ));
+
+    // Without any key material there is nothing to encrypt the secrets with
+    assert!(secrets::encrypt_secrets_file(&secrets_file, None, &[]).is_err());
+}
+
+// Test secrets encrypted to a recipient
+#[test]
+fn test_secrets_for_recipient() {
+    let dir = tempdir().expect("Failed to create temp directory");
+    let project = common::create_project(dir.path(), PROJECT);
+    let (identity, public_key) =
+        recipients::generate_identity(&dir.path().join("host"), false).unwrap();
+    let parsed = recipients::parse_recipients(&[public_key.to_string_lossy().to_string()]).unwrap();
+
+    let (data, _) =
+        secrets::encrypt_secrets_file(&project.join("secrets.env"), None, &parsed).unwrap();
+    let vars = secrets::decrypt_secrets(&data, None, &[identity]).unwrap();
+    assert_eq!(vars.len(), 2);
+}
+
+// Test that run needs the password to decrypt the secrets before anything is started
+#[test]
+#[serial]
+fn test_run_requires_secrets_password() {
+    let pytron_home = tempdir().expect("Failed to create PYTRON_HOME directory");
+    env::set_var("PYTRON_HOME", pytron_home.path());
+    let dir = tempdir().expect("Failed to create temp directory");
+    let project = common::create_project(dir.path(), PROJECT);
+    let secrets_file = project.join("secrets.env");
+    let archive = dir.path().join("robot.zip");
+
+    let (data, _) = secrets::encrypt_secrets_file(&secrets_file, Some("fooPass"), &[]).unwrap();
+    let options = ZipOptions {
+        exclude: vec![secrets_file],
+        extra_entries: vec![(SECRETS_ENTRY.to_string(), data)],
+        ..Default::default()
+    };
+    zip_directory_with_options(
+        project.to_str().unwrap(),
+        archive.to_str().unwrap(),
+        &options,
+    )
+    .unwrap();
+
+    let err = run_from_zip_with_options(
+        archive.to_str().unwrap(),
+        "main.py",
+        &[],
+        &[],
+        &RunOptions::default(),
+    )
+    .expect_err("Secrets cannot be decrypted without the password");
+    assert!(matches!(
+        archive::archive_error(&err),
+        Some(ArchiveError::PasswordRequired { .. })
+    ));
+
+    // The extraction directory is cleaned up and never held the secrets
+    let leftovers = walkdir::WalkDir::new(pytron_home.path().join("temp"))
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_name().to_string_lossy().contains("secrets"))
+        .count();
+    assert_eq!(leftovers, 0);
+
+    env::remove_var("PYTRON_HOME");
+}
@@ -0,0 +1,145 @@
+use pytron::selection::{select_files, RuleSource, Selection};
+use pytron::zip_directory;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::tempdir;
+
+mod common;
+
+// A git project with ignore rules at several levels
+const PROJECT: &[(&str, &str)] = &[
+    (".git/HEAD", "ref: refs/heads/main\n"),
+    (".gitignore", "# build output\nbuild/\n*.log\n"),
+    ("src/.gitignore", "cache/\n"),
+    ("main.py", "print('hello')\n"),
+    ("src/app.py", "pass\n"),
+    ("src/cache/data.bin", "x"),
+    ("build/out.txt", "out"),
+    ("build/lib/deep.txt", "deep"),
+    ("debug.log", "log"),
+    ("notes.txt", "notes"),
+];
+
+fn select(dir: &Path, patterns: Option<Vec<String>>, exclude: &[PathBuf]) -> Selection {
+    select_files(dir, &dir.join("robot.zip"), patterns.as_ref(), exclude).unwrap()
+}
+
+fn source(selection: &Selection, name: &str) -> RuleSource {
+    selection
+        .explain(name)
+        .and_then(|explanation| explanation.rule)
+        .unwrap_or_else(|| panic!("{} is not excluded", name))
+        .source
+}
+
+// Test which files are included and which rule excludes the others
+#[test]
+fn test_select_files() {
+    let dir = tempdir().expect("Failed to create temp directory");
+    let project = common::create_project(dir.path(), PROJECT);
+    fs::write(project.join("robot.zip"), "old archive").unwrap();
+
+    let selection = select(&project, Some(vec!["notes*".to_string()]), &[]);
+    let mut included: Vec<&str> = selection.included.iter().map(|f| f.name.as_str()).collect();
+    included.sort();
+    assert_eq!(included, vec![".gitignore", "main.py", "src/.gitignore", "src/app.py"]);
+    assert_eq!(selection.total_size, 28 + 15 + 7 + 5);
+
+    // Excluded directories are listed once, not with their contents
+    let excluded: Vec<&str> = selection.excluded.iter().map(|e| e.name.as_str()).collect();
+    assert_eq!(
+        excluded,
+        vec![".git", "build", "debug.log", "notes.txt", "robot.zip", "src/cache"]
+    );
+
+    assert_eq!(source(&selection, ".git"), RuleSource::Default);
+    assert_eq!(source(&selection, "notes.txt"), RuleSource::IgnorePatterns);
+    assert_eq!(source(&selection, "robot.zip"), RuleSource::Output);
+    assert_eq!(
+        source(&selection, "debug.log"),
+        RuleSource::IgnoreFile {
+            file: ".gitignore".to_string(),
+            line: Some(3)
+        }
+    );
+    assert_eq!(
+        source(&selection, "src/cache"),
+        RuleSource::IgnoreFile {
+            file: "src/.gitignore".to_string(),
+            line: Some(1)
+        }
+    );
+
+    let text = selection.to_text();
+    assert!(text.contains("build/  `build/` (.gitignore line 2)"), "{}", text);
+    assert!(text.contains("4 file(s), 55 B uncompressed; 6 path(s) excluded"), "{}", text);
+}
+
+// Test explaining files inside excluded directories, included files and unknown paths
+#[test]
+fn test_explain() {
+    let dir = tempdir().expect("Failed to create temp directory");
+    let project = common::create_project(dir.path(), PROJECT);
+    let secrets = project.join("secrets.env");
+    fs::write(&secrets, "TOKEN=1\n").unwrap();
+    let selection = select(&project, None, &[secrets]);
+
+    let explanation = selection.explain("build/out.txt").unwrap();
+    assert!(!explanation.included);
+    assert_eq!(explanation.excluded_by_parent.as_deref(), Some("build"));
+    assert_eq!(
+        explanation.to_text(),
+        "build/out.txt: excluded, its directory build is excluded by `build/` (.gitignore line 2)\n"
+    );
+    assert_eq!(source(&selection, ".git/HEAD"), RuleSource::Default);
+    assert_eq!(source(&selection, "secrets.env"), RuleSource::Protected);
+
+    let explanation = selection.explain("./src/app.py").unwrap();
+    assert!(explanation.included);
+    assert_eq!(explanation.to_text(), "src/app.py: included\n");
+    assert!(selection.explain("src").unwrap().included);
+    assert!(selection.explain("missing.py").is_none());
+}
+
+// Test that the default .git exclude leaves out the directory's contents too
+#[test]
+fn test_zip_excludes_git_directory() {
+    let dir = tempdir().expect("Failed to create temp directory");
+    let project = common::create_project(dir.path(), PROJECT);
+    let output = project.join("robot.zip");
+    zip_directory(project.to_str().unwrap(), output.to_str().unwrap(), None, None)
+        .expect("Failed to create archive");
+
+    let archive = zip::ZipArchive::new(fs::File::open(&output).unwrap()).unwrap();
+    let names: Vec<&str> = archive.file_names().collect();
+    assert!(names.contains(&"main.py"), "{:?}", names);
+    assert!(!names.iter().any(|name| name.starts_with(".git/")), "{:?}", names);
+    assert!(!names.contains(&"src/cache/data.bin"), "{:?}", names);
+}
+
+// Test that a project's own .pytron directory is never packed over pytron's metadata
+#[test]
+fn test_zip_excludes_reserved_directory() {
+    let dir = tempdir().expect("Failed to create temp directory");
+    let project = common::create_project(dir.path(), PROJECT);
+    fs::create_dir_all(project.join(".pytron")).unwrap();
+    fs::write(project.join(".pytron/manifest.sha256"), "stale\n").unwrap();
+    fs::write(project.join(".pytron/secrets.env.enc"), "stray\n").unwrap();
+
+    let selection = select(&project, None, &[]);
+    assert_eq!(source(&selection, ".pytron"), RuleSource::Reserved);
+
+    let output = project.join("robot.zip");
+    zip_directory(project.to_str().unwrap(), output.to_str().unwrap(), None, None)
+        .expect("Failed to create archive");
+    let mut archive = zip::ZipArchive::new(fs::File::open(&output).unwrap()).unwrap();
+    let names: Vec<String> = archive.file_names().map(String::from).collect();
+    assert!(!names.contains(&".pytron/secrets.env.enc".to_string()), "{:?}", names);
+    let mut manifest = String::new();
+    std::io::Read::read_to_string(
+        &mut archive.by_name(".pytron/manifest.sha256").unwrap(),
+        &mut manifest,
+    )
+    .unwrap();
+    assert_ne!(manifest, "stale\n");
+}
@@ -0,0 +1,265 @@
+use pytron::recipients;
+use pytron::signing::{self, SignatureStatus};
+use pytron::{
+    run_from_zip_with_options, zip_directory_with_options, ArchiveEncryption, RunOptions,
+    ZipOptions,
+};
+use serial_test::serial;
+use std::env;
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::Path;
+use tempfile::tempdir;
+
+mod common;
+
+const PROJECT: &[(&str, &str)] = &[("main.py", "print('signed')\n")];
+
+// Helper function to rewrite an archive with modified main.py content
+fn tamper(archive: &Path) {
+    let comment = {
+        let archive = zip::ZipArchive::new(File::open(archive).unwrap()).unwrap();
+        archive.comment().to_vec()
+    };
+    let mut writer = zip::ZipWriter::new(File::create(archive).unwrap());
+    writer
+        .start_file("main.py", zip::write::SimpleFileOptions::default())
+        .unwrap();
+    writer.write_all(b"print('evil')\n").unwrap();
+    writer.set_raw_comment(comment.into_boxed_slice());
+    writer.finish().unwrap();
+}
+
+// Helper function to change the compression method of every entry in place,
+// leaving the entry data untouched
+fn set_compression_method(archive: &Path, method: u16) {
+    let mut data = fs::read(archive).unwrap();
+    // Local file headers and central directory headers
+    for (signature, offset) in [(b"PK\x03\x04", 8), (b"PK\x01\x02", 10)] {
+        let positions: Vec<usize> = data
+            .windows(4)
+            .enumerate()
+            .filter(|(_, window)| window == signature)
+            .map(|(pos, _)| pos)
+            .collect();
+        for pos in positions {
+            data[pos + offset..pos + offset + 2].copy_from_slice(&method.to_le_bytes());
+        }
+    }
+    fs::write(archive, data).unwrap();
+}
+
+#[test]
+#[serial]
+fn test_sign_and_verify_archive() {
+    let pytron_home = tempdir().expect("Failed to create PYTRON_HOME directory");
+    env::set_var("PYTRON_HOME", pytron_home.path());
+    let dir = tempdir().expect("Failed to create temp directory");
+    let archive = common::create_archive(dir.path(), PROJECT, None);
+
+    let (private_key, public_key) =
+        signing::generate_keypair(&dir.path().join("publisher"), false).expect("Keygen failed");
+    assert!(signing::generate_keypair(&dir.path().join("publisher"), false).is_err());
+
+    assert_eq!(
+        signing::verify_archive(&archive, None, None, &[]).unwrap(),
+        SignatureStatus::Unsigned
+    );
+
+    let key = signing::read_signing_key(&private_key).expect("Failed to read private key");
+    let key_id = signing::sign_archive(&archive, &key).expect("Signing failed");
+    // The signed archive replaced the original without leaving a temporary file
+    let mut files: Vec<String> = fs::read_dir(dir.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+        .filter(|name| !name.starts_with("publisher"))
+        .collect();
+    files.sort();
+    assert_eq!(files, vec!["project", "robot.zip"]);
+
+    // Signed, but the key is not trusted yet
+    match signing::verify_archive(&archive, None, None, &[]).unwrap() {
+        SignatureStatus::Valid {
+            key_id: id,
+            trusted_key: None,
+        } => assert_eq!(id, key_id),
+        other => panic!("Expected untrusted valid signature, got {:?}", other),
+    }
+
+    // Trusting the key through the trusted keys directory
+    let trusted_dir = signing::get_trusted_keys_dir();
+    fs::create_dir_all(&trusted_dir).unwrap();
+    fs::copy(&public_key, trusted_dir.join("publisher.pub")).unwrap();
+    match signing::verify_archive(&archive, None, None, &[]).unwrap() {
+        SignatureStatus::Valid {
+            trusted_key: Some(path),
+            ..
+        } => assert!(path.ends_with("publisher.pub")),
+        other => panic!("Expected trusted signature, got {:?}", other),
+    }
+
+    // Verifying against an explicit public key
+    assert!(matches!(
+        signing::verify_archive(&archive, Some(&public_key), None, &[]).unwrap(),
+        SignatureStatus::Valid {
+            trusted_key: Some(_),
+            ..
+        }
+    ));
+
+    tamper(&archive);
+    assert!(matches!(
+        signing::verify_archive(&archive, None, None, &[]).unwrap(),
+        SignatureStatus::Invalid(_)
+    ));
+
+    env::remove_var("PYTRON_HOME");
+}
+
+#[test]
+#[serial]
+fn test_signing_keeps_encrypted_entries_readable() {
+    let pytron_home = tempdir().expect("Failed to create PYTRON_HOME directory");
+    env::set_var("PYTRON_HOME", pytron_home.path());
+    let dir = tempdir().expect("Failed to create temp directory");
+    let password = String::from("fooPass");
+    let archive = common::create_archive(dir.path(), PROJECT, Some(&password));
+
+    let (private_key, _) =
+        signing::generate_keypair(&dir.path().join("publisher"), false).expect("Keygen failed");
+    let key = signing::read_signing_key(&private_key).unwrap();
+    signing::sign_archive(&archive, &key).expect("Signing an encrypted archive failed");
+
+    let mut zip = zip::ZipArchive::new(File::open(&archive).unwrap()).unwrap();
+    let mut content = String::new();
+    zip.by_name_decrypt("main.py", password.as_bytes())
+        .expect("Entry should still decrypt after signing")
+        .read_to_string(&mut content)
+        .unwrap();
+    assert_eq!(content, "print('signed')\n");
+    drop(zip);
+
+    assert!(matches!(
+        signing::verify_archive(&archive, None, None, &[]).unwrap(),
+        SignatureStatus::Valid { .. }
+    ));
+
+    env::remove_var("PYTRON_HOME");
+}
+
+#[test]
+#[serial]
+fn test_run_refuses_unsigned_untrusted_and_tampered_archives() {
+    let pytron_home = tempdir().expect("Failed to create PYTRON_HOME directory");
+    env::set_var("PYTRON_HOME", pytron_home.path());
+    let dir = tempdir().expect("Failed to create temp directory");
+    let archive = common::create_archive(dir.path(), PROJECT, None);
+    let archive_str = archive.to_str().unwrap();
+
+    let strict = RunOptions {
+        require_signature: true,
+        ..Default::default()
+    };
+
+    let err = run_from_zip_with_options(archive_str, "main.py", &[], &[], &strict)
+        .expect_err("Unsigned archive should be refused");
+    assert!(err.to_string().contains("not signed"), "Got: {}", err);
+
+    let (private_key, _) =
+        signing::generate_keypair(&dir.path().join("publisher"), false).expect("Keygen failed");
+    let key = signing::read_signing_key(&private_key).unwrap();
+    signing::sign_archive(&archive, &key).unwrap();
+
+    let err = run_from_zip_with_options(archive_str, "main.py", &[], &[], &strict)
+        .expect_err("Archive signed by an untrusted key should be refused");
+    assert!(err.to_string().contains("untrusted key"), "Got: {}", err);
+
+    // A broken signature is refused even when signatures are optional
+    tamper(&archive);
+    let err = run_from_zip_with_options(archive_str, "main.py", &[], &[], &RunOptions::default())
+        .expect_err("Tampered archive should be refused");
+    assert!(
+        err.to_string().contains("signature is invalid"),
+        "Got: {}",
+        err
+    );
+
+    env::remove_var("PYTRON_HOME");
+}
+
+// Test that changing how the signed bytes are decoded breaks the signature
+#[test]
+fn test_signature_covers_compression_method() {
+    let dir = tempdir().expect("Failed to create temp directory");
+    let archive = dir.path().join("stored.zip");
+    let mut writer = zip::ZipWriter::new(File::create(&archive).unwrap());
+    let stored =
+        zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored);
+    writer.start_file("main.py", stored).unwrap();
+    writer.write_all(b"print('signed')\n").unwrap();
+    writer.finish().unwrap();
+
+    let (private_key, public_key) =
+        signing::generate_keypair(&dir.path().join("publisher"), false).expect("Keygen failed");
+    let key = signing::read_signing_key(&private_key).expect("Failed to read private key");
+    signing::sign_archive(&archive, &key).expect("Signing failed");
+    assert!(matches!(
+        signing::verify_archive(&archive, Some(&public_key), None, &[]).unwrap(),
+        SignatureStatus::Valid { .. }
+    ));
+
+    // Deflated
+    set_compression_method(&archive, 8);
+    assert!(matches!(
+        signing::verify_archive(&archive, Some(&public_key), None, &[]).unwrap(),
+        SignatureStatus::Invalid(_)
+    ));
+}
+
+// Test that sealed and recipient archives are signed inside when they are created
+#[test]
+#[serial]
+fn test_sign_encrypted_archives_when_creating_them() {
+    let pytron_home = tempdir().expect("Failed to create PYTRON_HOME directory");
+    env::set_var("PYTRON_HOME", pytron_home.path());
+    let dir = tempdir().expect("Failed to create temp directory");
+    let project = common::create_project(dir.path(), PROJECT);
+    let (private_key, public_key) =
+        signing::generate_keypair(&dir.path().join("publisher"), false).expect("Keygen failed");
+    let key = signing::read_signing_key(&private_key).unwrap();
+    let (identity, recipient) =
+        recipients::generate_identity(&dir.path().join("host"), false).expect("Keygen failed");
+    let recipient = recipients::parse_recipients(&[recipient.to_string_lossy().to_string()]).unwrap();
+
+    let sealed = dir.path().join("sealed.zip");
+    let encrypted = dir.path().join("encrypted.zip");
+    for (output, encryption) in [
+        (&sealed, ArchiveEncryption::Sealed("fooPass".to_string())),
+        (&encrypted, ArchiveEncryption::Recipients(recipient)),
+    ] {
+        let options = ZipOptions {
+            encryption,
+            signing_key: Some(key.clone()),
+            ..Default::default()
+        };
+        zip_directory_with_options(project.to_str().unwrap(), output.to_str().unwrap(), &options)
+            .expect("Failed to create archive");
+
+        // The container cannot be signed afterwards
+        let err = signing::sign_archive(output, &key).unwrap_err();
+        assert!(err.to_string().contains("sealed archives cannot be signed"), "{}", err);
+    }
+
+    // The signature of the zip inside is checked once it is decrypted
+    assert!(matches!(
+        signing::verify_archive(&sealed, Some(&public_key), Some("fooPass"), &[]).unwrap(),
+        SignatureStatus::Valid { .. }
+    ));
+    assert!(matches!(
+        signing::verify_archive(&encrypted, Some(&public_key), None, &[identity]).unwrap(),
+        SignatureStatus::Valid { .. }
+    ));
+    assert!(signing::verify_archive(&sealed, Some(&public_key), None, &[]).is_err());
+
+    env::remove_var("PYTRON_HOME");
+}
@@ -0,0 +1,193 @@
+use pytron::signing;
+use pytron::trust::{self, EnforcementMode, PolicyScope, TrustDecision, TrustEntry};
+use pytron::{run_from_zip_with_options, RunOptions};
+use serial_test::serial;
+use std::env;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use tempfile::{tempdir, TempDir};
+
+mod common;
+
+// Helper function to point PYTRON_HOME and the system config dir at fresh directories
+fn isolated_homes() -> (TempDir, TempDir) {
+    let pytron_home = tempdir().expect("Failed to create PYTRON_HOME directory");
+    let system_dir = tempdir().expect("Failed to create system config directory");
+    env::set_var("PYTRON_HOME", pytron_home.path());
+    env::set_var(pytron::SYSTEM_CONFIG_DIR_ENV, system_dir.path());
+    (pytron_home, system_dir)
+}
+
+fn reset_env() {
+    env::remove_var("PYTRON_HOME");
+    env::remove_var(pytron::SYSTEM_CONFIG_DIR_ENV);
+}
+
+// Helper function to create a zipped project named `name`
+fn create_archive(dir: &Path, name: &str) -> PathBuf {
+    let project = common::create_project(dir, &[("main.py", "print('trusted')\n")]);
+    let archive = dir.join(name);
+    common::zip_project(&project, &archive, None);
+    archive
+}
+
+fn decide(archive: &Path) -> TrustDecision {
+    let policy = trust::effective_policy().expect("Failed to load policy");
+    let mut zip = zip::ZipArchive::new(File::open(archive).unwrap()).unwrap();
+    trust::evaluate(&policy, archive, &mut zip).expect("Failed to evaluate policy")
+}
+
+// Test that keys, hashes and patterns each allow an archive
+#[test]
+#[serial]
+fn test_policy_entries_allow_archives() {
+    let _homes = isolated_homes();
+    let dir = tempdir().expect("Failed to create temp directory");
+    let archive = create_archive(dir.path(), "acme-robot.zip");
+
+    assert!(matches!(decide(&archive), TrustDecision::NotAllowed(_)));
+
+    // Pattern on the file name
+    trust::add_entry(
+        PolicyScope::User,
+        &TrustEntry::Pattern("acme-*.zip".to_string()),
+        None,
+    )
+    .unwrap();
+    assert!(matches!(decide(&archive), TrustDecision::Allowed(_)));
+    trust::remove_entry(
+        PolicyScope::User,
+        &TrustEntry::Pattern("acme-*.zip".to_string()),
+    )
+    .unwrap();
+    assert!(matches!(decide(&archive), TrustDecision::NotAllowed(_)));
+
+    // Hash of the archive
+    trust::add_entry(
+        PolicyScope::User,
+        &TrustEntry::Archive(archive.clone()),
+        None,
+    )
+    .unwrap();
+    assert!(matches!(decide(&archive), TrustDecision::Allowed(_)));
+    trust::remove_entry(PolicyScope::User, &TrustEntry::Archive(archive.clone())).unwrap();
+
+    // Publisher key stored in the system policy
+    let (private_key, public_key) =
+        signing::generate_keypair(&dir.path().join("ci"), false).expect("Keygen failed");
+    let key = signing::read_signing_key(&private_key).unwrap();
+    signing::sign_archive(&archive, &key).unwrap();
+    assert!(matches!(decide(&archive), TrustDecision::NotAllowed(_)));
+    trust::add_entry(
+        PolicyScope::System,
+        &TrustEntry::Key(public_key.to_string_lossy().to_string()),
+        None,
+    )
+    .unwrap();
+    match decide(&archive) {
+        TrustDecision::Allowed(reason) => assert!(reason.contains("'ci'"), "Got: {}", reason),
+        other => panic!("Expected archive to be allowed, got {:?}", other),
+    }
+    assert!(trust::format_policies().unwrap().contains("ci"));
+
+    // Removing an entry that is not there is an error
+    assert!(trust::remove_entry(PolicyScope::User, &TrustEntry::Key("ci".to_string())).is_err());
+    trust::remove_entry(PolicyScope::System, &TrustEntry::Key("ci".to_string())).unwrap();
+
+    reset_env();
+}
+
+// Test that the stricter of the system and user modes applies
+#[test]
+#[serial]
+fn test_effective_mode_is_strictest() {
+    let _homes = isolated_homes();
+
+    assert_eq!(
+        trust::effective_policy().unwrap().mode,
+        EnforcementMode::Off
+    );
+    trust::set_mode(PolicyScope::System, EnforcementMode::Enforce).unwrap();
+    trust::set_mode(PolicyScope::User, EnforcementMode::Warn).unwrap();
+    assert_eq!(
+        trust::effective_policy().unwrap().mode,
+        EnforcementMode::Enforce
+    );
+
+    trust::set_mode(PolicyScope::System, EnforcementMode::Off).unwrap();
+    assert_eq!(
+        trust::effective_policy().unwrap().mode,
+        EnforcementMode::Warn
+    );
+
+    reset_env();
+}
+
+// Test that run refuses archives outside the policy only in enforce mode
+#[test]
+#[serial]
+fn test_run_enforces_policy() {
+    let _homes = isolated_homes();
+    let dir = tempdir().expect("Failed to create temp directory");
+    let archive = create_archive(dir.path(), "robot.zip");
+    let archive_str = archive.to_str().unwrap();
+
+    trust::set_mode(PolicyScope::System, EnforcementMode::Enforce).unwrap();
+    let err = run_from_zip_with_options(archive_str, "main.py", &[], &[], &RunOptions::default())
+        .expect_err("Untrusted archive should be refused in enforce mode");
+    assert_eq!(err.kind(), std::io::ErrorKind::PermissionDenied);
+    assert!(err.to_string().contains("not signed"), "Got: {}", err);
+
+    // User entries cannot get past an enforcing system policy
+    trust::add_entry(
+        PolicyScope::User,
+        &TrustEntry::Archive(archive.clone()),
+        None,
+    )
+    .unwrap();
+    trust::add_entry(PolicyScope::User, &TrustEntry::Pattern("*".to_string()), None).unwrap();
+    let err = run_from_zip_with_options(archive_str, "main.py", &[], &[], &RunOptions::default())
+        .expect_err("User entries must not override the system policy");
+    assert!(err.to_string().contains("administrator"), "Got: {}", err);
+
+    // Once trusted by the system policy, it no longer blocks it; any later failure is about uv
+    trust::add_entry(
+        PolicyScope::System,
+        &TrustEntry::Archive(archive.clone()),
+        None,
+    )
+    .unwrap();
+    if let Err(err) =
+        run_from_zip_with_options(archive_str, "main.py", &[], &[], &RunOptions::default())
+    {
+        assert_ne!(
+            err.kind(),
+            std::io::ErrorKind::PermissionDenied,
+            "Got: {}",
+            err
+        );
+    }
+
+    reset_env();
+}
+
+// Test that an invalid policy file is reported
+#[test]
+#[serial]
+fn test_invalid_policy_file() {
+    let (pytron_home, _system) = isolated_homes();
+    fs::write(
+        pytron_home.path().join("trust.toml"),
+        "mode = \"sometimes\"\n",
+    )
+    .unwrap();
+
+    let err = trust::effective_policy().expect_err("Invalid mode should be rejected");
+    assert!(
+        err.to_string().contains("Invalid trust policy"),
+        "Got: {}",
+        err
+    );
+
+    reset_env();
+}
@@ -0,0 +1,110 @@
+use pytron::{uv_target_triple, Libc};
+
+// Test that every supported platform maps to the expected uv artifact
+#[test]
+fn test_uv_target_triple_supported_platforms() {
+    let cases = [
+        ("windows", "x86_64", None, "x86_64-pc-windows-msvc"),
+        ("windows", "aarch64", None, "aarch64-pc-windows-msvc"),
+        ("windows", "x86", None, "i686-pc-windows-msvc"),
+        ("macos", "x86_64", None, "x86_64-apple-darwin"),
+        ("macos", "aarch64", None, "aarch64-apple-darwin"),
+        (
+            "linux",
+            "x86_64",
+            Some(Libc::Gnu),
+            "x86_64-unknown-linux-gnu",
+        ),
+        (
+            "linux",
+            "x86_64",
+            Some(Libc::Musl),
+            "x86_64-unknown-linux-musl",
+        ),
+        (
+            "linux",
+            "aarch64",
+            Some(Libc::Gnu),
+            "aarch64-unknown-linux-gnu",
+        ),
+        (
+            "linux",
+            "aarch64",
+            Some(Libc::Musl),
+            "aarch64-unknown-linux-musl",
+        ),
+        (
+            "linux",
+            "arm",
+            Some(Libc::Gnu),
+            "armv7-unknown-linux-gnueabihf",
+        ),
+        (
+            "linux",
+            "arm",
+            Some(Libc::Musl),
+            "armv7-unknown-linux-musleabihf",
+        ),
+        ("linux", "x86", Some(Libc::Gnu), "i686-unknown-linux-gnu"),
+        ("linux", "x86", Some(Libc::Musl), "i686-unknown-linux-musl"),
+        (
+            "linux",
+            "powerpc64le",
+            Some(Libc::Gnu),
+            "powerpc64le-unknown-linux-gnu",
+        ),
+        ("linux", "s390x", Some(Libc::Gnu), "s390x-unknown-linux-gnu"),
+    ];
+
+    for (os, arch, libc, expected) in cases {
+        assert_eq!(
+            uv_target_triple(os, arch, libc),
+            Ok(expected),
+            "Unexpected uv target for {}/{} ({:?})",
+            os,
+            arch,
+            libc
+        );
+    }
+}
+
+// Test that unsupported platforms produce a diagnostic naming the platform
+#[test]
+fn test_uv_target_triple_unsupported_platforms() {
+    let err = uv_target_triple("linux", "s390x", Some(Libc::Musl))
+        .expect_err("s390x musl should not have a uv build");
+    assert!(
+        err.contains("linux/s390x (musl)"),
+        "Diagnostic should name the platform, got: {}",
+        err
+    );
+    assert!(
+        err.contains(pytron::UV_VERSION),
+        "Diagnostic should name the uv version, got: {}",
+        err
+    );
+
+    assert!(uv_target_triple("freebsd", "x86_64", None).is_err());
+    assert!(uv_target_triple("linux", "riscv64", Some(Libc::Gnu)).is_err());
+}
+
+// Test that the host resolves to a download URL matching its libc
+#[test]
+fn test_uv_download_url_for_host() {
+    let Some(url) = pytron::get_uv_download_url() else {
+        println!("Skipping: no uv build for this host");
+        return;
+    };
+
+    assert!(
+        url.contains(pytron::UV_VERSION),
+        "URL should contain uv version"
+    );
+    if let Some(libc) = pytron::detect_libc() {
+        let expected = match libc {
+            Libc::Gnu => "-gnu",
+            Libc::Musl => "-musl",
+        };
+        assert!(url.contains(expected), "URL {} should target {}", url, libc);
+    }
+}
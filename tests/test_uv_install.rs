@@ -0,0 +1,140 @@
+use serial_test::serial;
+use std::env;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tempfile::tempdir;
+
+const FAKE_UV: &[u8] = b"#!/bin/sh\necho 'uv 0.7.2'\n";
+
+fn binary_name() -> &'static str {
+    if cfg!(windows) {
+        "uv.exe"
+    } else {
+        "uv"
+    }
+}
+
+// Helper function to build a tar.gz laid out like a uv release artifact
+fn create_uv_tarball(dir: &Path) -> PathBuf {
+    let archive_path = dir.join("uv-test.tar.gz");
+    let file = File::create(&archive_path).expect("Failed to create tarball");
+    let encoder = flate2::write::GzEncoder::new(file, flate2::Compression::default());
+    let mut builder = tar::Builder::new(encoder);
+
+    let mut header = tar::Header::new_gnu();
+    header.set_size(FAKE_UV.len() as u64);
+    header.set_mode(0o755);
+    header.set_cksum();
+    builder
+        .append_data(&mut header, format!("uv-test/{}", binary_name()), FAKE_UV)
+        .expect("Failed to add uv to tarball");
+    builder
+        .into_inner()
+        .expect("Failed to finish tarball")
+        .finish()
+        .expect("Failed to finish gzip stream");
+
+    archive_path
+}
+
+// Helper function to build a zip laid out like a Windows uv release artifact
+fn create_uv_zip(dir: &Path) -> PathBuf {
+    let archive_path = dir.join("uv-test.zip");
+    let file = File::create(&archive_path).expect("Failed to create zip");
+    let mut zip = zip::ZipWriter::new(file);
+    zip.start_file(binary_name(), zip::write::SimpleFileOptions::default())
+        .expect("Failed to add uv to zip");
+    zip.write_all(FAKE_UV).expect("Failed to write uv");
+    zip.finish().expect("Failed to finish zip");
+    archive_path
+}
+
+#[test]
+#[serial]
+fn test_install_uv_from_tarball_with_checksum() {
+    let pytron_home = tempdir().expect("Failed to create PYTRON_HOME directory");
+    env::set_var("PYTRON_HOME", pytron_home.path());
+    let staging = tempdir().expect("Failed to create staging directory");
+    let archive = create_uv_tarball(staging.path());
+    let checksum = pytron::sha256_file(&archive).expect("Failed to hash archive");
+
+    let installed = pytron::install_uv_from_archive(&archive, Some(&checksum), false)
+        .expect("Install from archive failed");
+
+    assert_eq!(installed, pytron::get_uv_path());
+    assert!(pytron::is_uv_installed(), "uv should be installed");
+    assert_eq!(fs::read(&installed).unwrap(), FAKE_UV);
+
+    env::remove_var("PYTRON_HOME");
+}
+
+#[test]
+#[serial]
+fn test_install_uv_from_zip_with_sidecar_checksum() {
+    let pytron_home = tempdir().expect("Failed to create PYTRON_HOME directory");
+    env::set_var("PYTRON_HOME", pytron_home.path());
+    let staging = tempdir().expect("Failed to create staging directory");
+    let archive = create_uv_zip(staging.path());
+    let checksum = pytron::sha256_file(&archive).expect("Failed to hash archive");
+    fs::write(
+        staging.path().join("uv-test.zip.sha256"),
+        format!("{} *uv-test.zip\n", checksum),
+    )
+    .expect("Failed to write sidecar checksum");
+
+    pytron::install_uv_from_archive(&archive, None, false).expect("Install from zip failed");
+    assert!(pytron::is_uv_installed(), "uv should be installed");
+
+    env::remove_var("PYTRON_HOME");
+}
+
+#[test]
+#[serial]
+fn test_install_uv_rejects_bad_or_missing_checksum() {
+    let pytron_home = tempdir().expect("Failed to create PYTRON_HOME directory");
+    env::set_var("PYTRON_HOME", pytron_home.path());
+    let staging = tempdir().expect("Failed to create staging directory");
+    let archive = create_uv_tarball(staging.path());
+
+    let err = pytron::install_uv_from_archive(&archive, Some(&"0".repeat(64)), false)
+        .expect_err("Mismatching checksum should be rejected");
+    assert!(
+        err.to_string().contains("Checksum mismatch"),
+        "Got: {}",
+        err
+    );
+
+    let err = pytron::install_uv_from_archive(&archive, None, false)
+        .expect_err("Missing checksum should be rejected");
+    assert!(err.to_string().contains("No checksum"), "Got: {}", err);
+    assert!(!pytron::is_uv_installed(), "uv should not be installed");
+
+    pytron::install_uv_from_archive(&archive, None, true)
+        .expect("Install without verification should succeed when requested");
+    assert!(pytron::is_uv_installed(), "uv should be installed");
+
+    env::remove_var("PYTRON_HOME");
+}
+
+#[test]
+#[serial]
+fn test_ensure_uv_uses_staged_archive() {
+    let pytron_home = tempdir().expect("Failed to create PYTRON_HOME directory");
+    env::set_var("PYTRON_HOME", pytron_home.path());
+    let staging = tempdir().expect("Failed to create staging directory");
+    let archive = create_uv_tarball(staging.path());
+    let checksum = pytron::sha256_file(&archive).expect("Failed to hash archive");
+    env::set_var(pytron::UV_ARCHIVE_ENV, &archive);
+    env::set_var(pytron::UV_ARCHIVE_SHA256_ENV, &checksum);
+
+    let result = pytron::ensure_uv();
+
+    env::remove_var(pytron::UV_ARCHIVE_ENV);
+    env::remove_var(pytron::UV_ARCHIVE_SHA256_ENV);
+    env::remove_var("PYTRON_HOME");
+
+    let path = result.expect("ensure_uv should install from the staged archive");
+    assert!(path.starts_with(pytron_home.path()));
+    assert!(path.exists(), "uv should exist at {}", path.display());
+}
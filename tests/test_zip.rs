@@ -66,8 +66,8 @@ fn test_zip_directory() {
     let file = File::open(&output_zip).expect("Failed to open zip file");
     let mut archive = zip::ZipArchive::new(file).expect("Failed to read zip archive");
 
-    // Check file count (should be 3 files: main.py, .gitignore, subdir/helper.py)
-    assert_eq!(archive.len(), 3, "Zip archive should contain 3 files");
+    // Check file count (3 files: main.py, .gitignore, subdir/helper.py, plus the content manifest)
+    assert_eq!(archive.len(), 4, "Zip archive should contain 3 files and the manifest");
 
     // Verify specific files are present
     let file_names: Vec<String> = (0..archive.len())
@@ -125,8 +125,8 @@ fn test_zip_directory_aes_encrypted() {
     let file = File::open(&output_zip).expect("Failed to open zip file");
     let mut archive = zip::ZipArchive::new(file).expect("Failed to read zip archive");
 
-    // Check file count (should be 3 files: main.py, .gitignore, subdir/helper.py)
-    assert_eq!(archive.len(), 3, "Zip archive should contain 3 files");
+    // Check file count (3 files: main.py, .gitignore, subdir/helper.py, plus the content manifest)
+    assert_eq!(archive.len(), 4, "Zip archive should contain 3 files and the manifest");
 
     // Verify specific files are present
     let file_names: Vec<String> = (0..archive.len())
@@ -168,8 +168,8 @@ fn test_zip_directory_aes_decryption_wrong_password() {
     let file = File::open(&output_zip).expect("Failed to open zip file");
     let mut archive = zip::ZipArchive::new(file).expect("Failed to read zip archive");
 
-    // Check file count (should be 3 files: main.py, .gitignore, subdir/helper.py)
-    assert_eq!(archive.len(), 3, "Zip archive should contain 3 files");
+    // Check file count (3 files: main.py, .gitignore, subdir/helper.py, plus the content manifest)
+    assert_eq!(archive.len(), 4, "Zip archive should contain 3 files and the manifest");
 
     // Verify specific files are present
     let err = archive.by_index_decrypt(0, wrong_password.as_bytes()).err().unwrap();
@@ -204,8 +204,8 @@ fn test_zip_directory_aes_decryption_password_required() {
     let file = File::open(&output_zip).expect("Failed to open zip file");
     let mut archive = zip::ZipArchive::new(file).expect("Failed to read zip archive");
 
-    // Check file count (should be 3 files: main.py, .gitignore, subdir/helper.py)
-    assert_eq!(archive.len(), 3, "Zip archive should contain 3 files");
+    // Check file count (3 files: main.py, .gitignore, subdir/helper.py, plus the content manifest)
+    assert_eq!(archive.len(), 4, "Zip archive should contain 3 files and the manifest");
 
     // Verify specific files are present
     let err = archive.by_index(0).err().unwrap();
@@ -334,4 +334,38 @@ fn test_zip_directory_override_defaults() {
         !file_names.contains(&"ignored.log".to_string()),
         "ignored.log should still not be in the archive (from .gitignore)"
     );
-}
\ No newline at end of file
+}
+// Test that a pattern matching a directory leaves out everything inside it
+#[test]
+fn test_zip_directory_excludes_matched_directory_contents() {
+    let test_dir = create_test_directory();
+
+    // Files inside the default .git exclude and a directory matched by a custom pattern
+    fs::create_dir_all(test_dir.path().join(".git/refs")).expect("Failed to create .git directory");
+    fs::write(test_dir.path().join(".git/HEAD"), "ref: refs/heads/main\n").expect("Failed to write .git/HEAD");
+    fs::write(test_dir.path().join(".git/refs/main"), "0000\n").expect("Failed to write .git/refs/main");
+    fs::create_dir_all(test_dir.path().join("build/lib")).expect("Failed to create build directory");
+    fs::write(test_dir.path().join("build/lib/app.py"), "pass\n").expect("Failed to write build/lib/app.py");
+
+    let output_zip = test_dir.path().join("excluded_dirs_output.zip");
+    let patterns = Some(vec!["build".to_string()]);
+    zip_directory(
+        test_dir.path().to_str().unwrap(),
+        output_zip.to_str().unwrap(),
+        patterns.as_ref(),
+        None,
+    )
+    .expect("zip_directory with a directory pattern failed");
+
+    let file = File::open(&output_zip).expect("Failed to open zip file");
+    let archive = zip::ZipArchive::new(file).expect("Failed to read zip archive");
+    let file_names: Vec<&str> = archive.file_names().collect();
+
+    // Before, only the directory entry itself was matched and its files were still packed
+    assert!(
+        !file_names.iter().any(|name| name.starts_with(".git/") || name.starts_with("build/")),
+        "Contents of excluded directories should not be in the archive: {:?}",
+        file_names
+    );
+    assert!(file_names.contains(&"subdir/helper.py"), "{:?}", file_names);
+}